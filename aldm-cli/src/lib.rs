@@ -1,5 +1,5 @@
 pub fn run_cli() -> Result<(), aldm::Error> {
-    let (_cli_input, _worker_guards) = ui::run_common::<CliTemplate>()?;
+    let (cli_input, session) = ui::run_common::<CliTemplate>()?;
 
     tracing::debug!(
         "Running in {} mode... {}",
@@ -7,6 +7,676 @@ pub fn run_cli() -> Result<(), aldm::Error> {
         console::Emoji("🔤", "")
     );
 
+    match &cli_input.command {
+        None => list(&cli_input.arguments, &session)?,
+        Some(ActionCommand::List(arguments)) => list(arguments, &session)?,
+        Some(ActionCommand::Search(arguments)) => search(arguments, &session)?,
+        Some(ActionCommand::Install(arguments)) => install(arguments, &session)?,
+        Some(ActionCommand::Rollback(arguments)) => rollback(arguments, &session)?,
+        Some(ActionCommand::History(_)) => history(&session)?,
+        Some(ActionCommand::GenerateDatabase(arguments)) => generate_database(arguments)?,
+        Some(ActionCommand::ReportBug(arguments)) => report_bug(arguments, &session)?,
+        Some(ActionCommand::ElevatedHelper(_)) => elevated_helper()?,
+    }
+
+    Ok(())
+}
+
+fn list(arguments: &ListActionArguments, session: &ui::Session) -> Result<(), aldm::Error> {
+    let database_filepath = arguments
+        .database_file
+        .clone()
+        .unwrap_or_else(data::default_database_filepath);
+    let driver_filter = driver_filter(
+        arguments.hardware,
+        &arguments.tags,
+        &arguments.driver_id,
+        &session.config,
+    );
+
+    let progress = CliProgress::new(
+        session
+            .logging_handle
+            .output_mode(),
+    );
+    let devices_drivers = actions::list(
+        &database_filepath,
+        &data::configured_hwdata_dirpath(&session.config),
+        &system::configured_root(&session.config),
+        &driver_filter,
+        &progress,
+        &actions::CancellationToken::new(),
+    );
+    progress.finish();
+    let devices_drivers = devices_drivers
+        .context(actions::CouldNotListSnafu {})
+        .context(aldm::ActionsSnafu {})?;
+
+    if devices_drivers.is_empty() {
+        tracing::info!("No installed drivers were found.");
+    }
+    for device_drivers in &devices_drivers {
+        tracing::info!("{}", format_device(device_drivers));
+        tracing::info!(
+            "  {}    {}",
+            "In use:".yellow(),
+            device_drivers
+                .device
+                .bound_driver
+                .as_deref()
+                .unwrap_or("none")
+        );
+        if !device_drivers
+            .device
+            .candidate_modules
+            .is_empty()
+        {
+            tracing::info!(
+                "  {}   {}",
+                "Modules:".dimmed(),
+                device_drivers
+                    .device
+                    .candidate_modules
+                    .join(", ")
+            );
+        }
+        for choice in &device_drivers.installed {
+            tracing::info!("  {} {}", "Installed:".green(), format_choice(choice));
+        }
+    }
+    print_plain_and_json(&devices_drivers);
+
+    Ok(())
+}
+
+fn search(arguments: &SearchActionArguments, session: &ui::Session) -> Result<(), aldm::Error> {
+    let database_filepath = arguments
+        .database_file
+        .clone()
+        .unwrap_or_else(data::default_database_filepath);
+    let driver_filter = driver_filter(
+        arguments.hardware,
+        &arguments.tags,
+        &arguments.driver_id,
+        &session.config,
+    );
+
+    let progress = CliProgress::new(
+        session
+            .logging_handle
+            .output_mode(),
+    );
+    let devices_drivers = actions::search_system(
+        &database_filepath,
+        &data::configured_hwdata_dirpath(&session.config),
+        &system::configured_root(&session.config),
+        &driver_filter,
+        &progress,
+        &actions::CancellationToken::new(),
+    );
+    progress.finish();
+    let devices_drivers = devices_drivers
+        .context(actions::CouldNotSearchSnafu {})
+        .context(aldm::ActionsSnafu {})?;
+
+    if devices_drivers.is_empty() {
+        tracing::info!("No drivers were found for the detected hardware.");
+    }
+    for device_drivers in &devices_drivers {
+        tracing::info!("{}", format_device(device_drivers));
+        for choice in &device_drivers.installed {
+            tracing::info!("  {}    {}", "Installed:".green(), format_choice(choice));
+        }
+        if let Some(choice) = &device_drivers.recommended {
+            tracing::info!("  {}  {}", "Recommended:".cyan(), format_choice(choice));
+        }
+        for choice in &device_drivers.alternatives {
+            tracing::info!("  {}  {}", "Alternative:".dimmed(), format_choice(choice));
+        }
+    }
+    print_plain_and_json(&devices_drivers);
+
+    Ok(())
+}
+
+// Install the driver with the given ID, or else the recommended driver, for
+// every device that has it
+fn install(arguments: &InstallActionArguments, session: &ui::Session) -> Result<(), aldm::Error> {
+    let database_filepath = arguments
+        .database_file
+        .clone()
+        .unwrap_or_else(data::default_database_filepath);
+    let driver_filter = driver_filter(
+        arguments.hardware,
+        &arguments.tags,
+        &arguments.driver_id,
+        &session.config,
+    );
+    let mut backend_settings = backend::BackendSettings::from_config(&session.config);
+    backend_settings.enable_aur |= arguments.enable_aur;
+    let root = system::configured_root(&session.config);
+
+    let progress = CliProgress::new(
+        session
+            .logging_handle
+            .output_mode(),
+    );
+    let cancellation_token = actions::CancellationToken::new();
+    let devices_drivers = actions::search_system(
+        &database_filepath,
+        &data::configured_hwdata_dirpath(&session.config),
+        &root,
+        &driver_filter,
+        &progress,
+        &cancellation_token,
+    );
+    progress.finish();
+    let devices_drivers = devices_drivers
+        .context(actions::CouldNotSearchSnafu {})
+        .context(aldm::ActionsSnafu {})?;
+
+    let plans = devices_drivers
+        .iter()
+        .filter_map(|device_drivers| {
+            let driver_id = arguments
+                .driver_id
+                .clone()
+                .or_else(|| {
+                    device_drivers
+                        .recommended
+                        .as_ref()
+                        .map(|choice| choice.id.clone())
+                })?;
+            Some(actions::plan_install(device_drivers, &driver_id))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .context(actions::CouldNotInstallSnafu {})
+        .context(aldm::ActionsSnafu {})?;
+    let plans = plans
+        .into_iter()
+        .filter(|plan| !plan.is_empty())
+        .collect::<Vec<_>>();
+    if plans.is_empty() {
+        match devices_drivers.is_empty() {
+            true => tracing::info!("No drivers were found for the detected hardware."),
+            false => tracing::info!("Nothing to install. The drivers are already installed."),
+        }
+        tracing::info!(target:"JSON", "{}", serde_json::json!([]));
+        return Ok(());
+    }
+
+    let output_mode = session
+        .logging_handle
+        .output_mode();
+    let transaction_root = transaction_root(&session.config, output_mode);
+    let backend = backend(&backend_settings, output_mode, &transaction_root)
+        .context(actions::BackendSnafu {})
+        .context(actions::CouldNotInstallSnafu {})
+        .context(aldm::ActionsSnafu {})?;
+    let command_runner = command_runner(output_mode, &transaction_root);
+    if let Some(plan) = plans
+        .iter()
+        .find(|plan| {
+            !plan
+                .aur_packages
+                .is_empty()
+                && !backend.supports_aur()
+        })
+    {
+        return Err(actions::AurNotEnabledSnafu {
+            packages: plan
+                .aur_packages
+                .clone(),
+        }
+        .build())
+        .context(actions::CouldNotInstallSnafu {})
+        .context(aldm::ActionsSnafu {});
+    }
+
+    for plan in &plans {
+        for line in plan
+            .to_string()
+            .lines()
+        {
+            tracing::info!("{}", line);
+        }
+        match plan.kms_hook_conflicts(&root) {
+            Ok(modules) => {
+                for module in modules {
+                    tracing::warn!(
+                        "The kms hook in /etc/mkinitcpio.conf adds the blacklisted {} to the initramfs. Remove kms from HOOKS for the driver to work.",
+                        module
+                    );
+                }
+            }
+            Err(error) => tracing::warn!("Could not check the mkinitcpio hooks: {}", error),
+        }
+    }
+    if !arguments.noconfirm && !confirm("Proceed with the installation?") {
+        tracing::warn!("The installation was not confirmed. Nothing was changed.");
+        return Ok(());
+    }
+
+    // Without root, the transactions are made by the elevated helper, except
+    // in the test mode, which only writes below a scratch root
+    let is_elevated = output_mode != app::LoggingMode::Test && actions::is_elevation_required();
+    let mut results = Vec::new();
+    for plan in plans {
+        let outcome = match is_elevated {
+            true => actions::execute_transaction_elevated(
+                &plan,
+                &backend_settings,
+                &transaction_root,
+                &progress,
+                &cancellation_token,
+            ),
+            false => actions::execute_transaction(
+                &plan,
+                backend.as_ref(),
+                command_runner.as_ref(),
+                &transaction_root,
+                &progress,
+                &cancellation_token,
+            ),
+        };
+        progress.finish();
+        let outcome = outcome
+            .context(actions::CouldNotInstallSnafu {})
+            .context(aldm::ActionsSnafu {})?;
+        match outcome {
+            actions::TransactionOutcome::Succeeded => tracing::info!(
+                "{} Installed {} for {}.",
+                console::Emoji("✅", ""),
+                plan.driver_id,
+                plan.slot
+            ),
+            actions::TransactionOutcome::RebootRequired => tracing::warn!(
+                "Installed {} for {}. Reboot for the driver to take effect.",
+                plan.driver_id,
+                plan.slot
+            ),
+            actions::TransactionOutcome::Cancelled => tracing::warn!(
+                "Installing {} for {} was cancelled.",
+                plan.driver_id,
+                plan.slot
+            ),
+        }
+        tracing::info!(target:"PLAIN", "{} {} {:?}", plan.slot, plan.driver_id, outcome);
+        let is_cancelled = outcome == actions::TransactionOutcome::Cancelled;
+        results.push(serde_json::json!({ "plan": plan, "outcome": outcome }));
+        if is_cancelled {
+            break;
+        }
+    }
+    tracing::info!(target:"JSON", "{}", serde_json::json!(results));
+
+    Ok(())
+}
+
+// Roll back the transaction with the given ID, or else the last one
+fn rollback(arguments: &RollbackActionArguments, session: &ui::Session) -> Result<(), aldm::Error> {
+    let mut backend_settings = backend::BackendSettings::from_config(&session.config);
+    backend_settings.enable_aur |= arguments.enable_aur;
+    let output_mode = session
+        .logging_handle
+        .output_mode();
+    let root = transaction_root(&session.config, output_mode);
+
+    let journal = actions::plan_rollback(
+        &root,
+        arguments
+            .id
+            .as_deref(),
+    )
+    .context(actions::CouldNotRollBackSnafu {})
+    .context(aldm::ActionsSnafu {})?;
+    let backend = backend(&backend_settings, output_mode, &root)
+        .context(actions::PackageBackendSnafu {})
+        .context(actions::CouldNotRollBackSnafu {})
+        .context(aldm::ActionsSnafu {})?;
+    let command_runner = command_runner(output_mode, &root);
+
+    tracing::info!("Rolling back:");
+    for line in journal
+        .to_string()
+        .lines()
+    {
+        tracing::info!("{}", line);
+    }
+    if !arguments.noconfirm && !confirm("Proceed with the rollback?") {
+        tracing::warn!("The rollback was not confirmed. Nothing was changed.");
+        return Ok(());
+    }
+
+    // Like the installation, the rollback goes through the elevated helper
+    // without root
+    let progress = CliProgress::new(output_mode);
+    let outcome = match output_mode != app::LoggingMode::Test && actions::is_elevation_required() {
+        true => actions::execute_rollback_elevated(&journal, &backend_settings, &root, &progress),
+        false => actions::execute_rollback(
+            &journal,
+            backend.as_ref(),
+            command_runner.as_ref(),
+            &root,
+            &progress,
+        ),
+    };
+    progress.finish();
+    let outcome = outcome
+        .context(actions::CouldNotRollBackSnafu {})
+        .context(aldm::ActionsSnafu {})?;
+    match outcome {
+        actions::TransactionOutcome::RebootRequired => tracing::warn!(
+            "Rolled back the transaction {}. Reboot for the change to take effect.",
+            journal.id
+        ),
+        _ => tracing::info!(
+            "{} Rolled back the transaction {}.",
+            console::Emoji("✅", ""),
+            journal.id
+        ),
+    }
+    tracing::info!(target:"PLAIN", "{} {:?}", journal.id, outcome);
+    tracing::info!(
+        target:"JSON", "{}",
+        serde_json::json!({ "transaction": journal, "outcome": outcome })
+    );
+
+    Ok(())
+}
+
+// The transactions that aldm made on the system, the latest first
+fn history(session: &ui::Session) -> Result<(), aldm::Error> {
+    let entries = actions::history(&transaction_root(
+        &session.config,
+        session
+            .logging_handle
+            .output_mode(),
+    ))
+    .context(actions::CouldNotShowHistorySnafu {})
+    .context(aldm::ActionsSnafu {})?;
+
+    if entries.is_empty() {
+        tracing::info!("No transactions were recorded.");
+    }
+    for entry in &entries {
+        let journal = &entry.journal;
+        tracing::info!(
+            "{} {} {} {}",
+            entry
+                .timestamp
+                .bold(),
+            journal.kind,
+            journal.driver_id,
+            format!("({})", journal.id).dimmed()
+        );
+        tracing::info!("  {}     {}", "User:".dimmed(), journal.user);
+        tracing::info!("  {} {}", "Hardware:".dimmed(), journal.device);
+        let status = match entry.status {
+            actions::TransactionStatus::Succeeded => entry
+                .status
+                .green()
+                .to_string(),
+            actions::TransactionStatus::Cancelled | actions::TransactionStatus::RolledBack => entry
+                .status
+                .yellow()
+                .to_string(),
+            actions::TransactionStatus::Failed | actions::TransactionStatus::Interrupted => entry
+                .status
+                .red()
+                .to_string(),
+        };
+        tracing::info!(
+            "  {}   {}, {}",
+            "Result:".dimmed(),
+            status,
+            entry.reboot_status
+        );
+        if let Some(system::JournalResult::Failed { error, .. }) = &journal.result {
+            tracing::info!("  {}    {}", "Error:".dimmed(), error);
+        }
+        if let Some(id) = &journal.rolled_back_by {
+            tracing::info!("  {}  rolled back by {}", "Rollback:".dimmed(), id);
+        }
+        if let Some(id) = &journal.rollback_of {
+            tracing::info!("  {}  of {}", "Rollback:".dimmed(), id);
+        }
+        tracing::info!(
+            target:"PLAIN", "{} {} {} {} {} {:?} {:?}",
+            journal.id,
+            journal.user,
+            journal.kind,
+            journal.slot,
+            journal.driver_id.replace(", ", ","),
+            entry.status,
+            entry.reboot_status
+        );
+    }
+    tracing::info!(target:"JSON", "{}", serde_json::json!(entries));
+
+    Ok(())
+}
+
+// Make the changes that the CLI or the GUI requested without root, as root.
+// The request, the progress and the result go through stdin and stdout.
+fn elevated_helper() -> Result<(), aldm::Error> {
+    actions::serve_elevated_request()
+        .context(actions::CouldNotServeElevatedSnafu {})
+        .context(aldm::ActionsSnafu {})
+}
+
+// The root that the transactions change. The test mode never changes the
+// system itself: unless a scratch root is configured, its changes go below a
+// directory of its own in the temporary directory, where the rollback and the
+// history of the test mode find them as well.
+fn transaction_root(config: &app::Config, output_mode: app::LoggingMode) -> PathBuf {
+    let root = system::configured_root(config);
+    match output_mode == app::LoggingMode::Test && root == probe::default_root() {
+        true => env::temp_dir().join(TEST_ROOT_DIRNAME),
+        false => root,
+    }
+}
+
+const TEST_ROOT_DIRNAME: &str = "aldm-test-root";
+
+// The test mode is a dry run, in which the fake backend stands in for the
+// package manager
+fn backend(
+    backend_settings: &backend::BackendSettings,
+    output_mode: app::LoggingMode,
+    root: &std::path::Path,
+) -> Result<Box<dyn backend::PackageBackend>, backend::Error> {
+    match output_mode {
+        app::LoggingMode::Test => {
+            tracing::info!(
+                "Running in the test mode. No packages will be changed, but files will be written below {:?}.",
+                root
+            );
+            Ok(match backend_settings.enable_aur {
+                true => Box::new(backend::FakeBackend::new().with_aur_support()),
+                false => Box::new(backend::FakeBackend::new()),
+            })
+        }
+        _ => backend_settings.backend(),
+    }
+}
+
+// The same goes for the fake runner and the commands
+fn command_runner(
+    output_mode: app::LoggingMode,
+    root: &std::path::Path,
+) -> Box<dyn system::CommandRunner> {
+    match output_mode {
+        app::LoggingMode::Test => Box::new(system::FakeCommandRunner::new()),
+        _ => Box::new(system::SystemCommandRunner::new(root)),
+    }
+}
+
+// Ask on the terminal, defaulting to yes. Without a terminal to ask on, the
+// answer is no, so that nothing is changed without `--noconfirm`.
+fn confirm(question: &str) -> bool {
+    let term = console::Term::stderr();
+    if !term.is_term() {
+        tracing::warn!(
+            "Cannot ask for confirmation without a terminal. Pass --noconfirm to proceed anyway."
+        );
+        return false;
+    }
+    if term
+        .write_str(&format!("{} {} ", question, "[Y/n]".bold()))
+        .is_err()
+    {
+        return false;
+    }
+    match term.read_line() {
+        Ok(answer) => matches!(
+            answer
+                .trim()
+                .to_lowercase()
+                .as_str(),
+            "" | "y" | "yes"
+        ),
+        Err(_) => false,
+    }
+}
+
+// Tags given on the command line replace the preferred tags of the
+// configuration, while the excluded tags only come from the configuration
+fn driver_filter(
+    hardware_kind: Option<data::HardwareKind>,
+    tags: &[String],
+    driver_id: &Option<String>,
+    config: &app::Config,
+) -> actions::DriverFilter {
+    actions::DriverFilter {
+        hardware_kind,
+        preferred_tags: match tags.is_empty() {
+            true => config
+                .preferred_tags
+                .clone()
+                .unwrap_or_default(),
+            false => tags.to_vec(),
+        },
+        excluded_tags: config
+            .excluded_tags
+            .clone()
+            .unwrap_or_default(),
+        driver_id: driver_id.clone(),
+    }
+}
+
+fn format_device(device_drivers: &actions::DeviceDrivers) -> String {
+    let hardware_kinds = device_drivers
+        .hardware_kinds
+        .iter()
+        .map(|hardware_kind| hardware_kind.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "{} {}",
+        hardware_kinds.bold(),
+        device_drivers.device_label()
+    )
+}
+
+fn format_choice(choice: &actions::DriverChoice) -> String {
+    match choice.packages == [choice.id.clone()] {
+        true => choice.id.clone(),
+        false => format!(
+            "{} ({})",
+            choice.id,
+            choice
+                .packages
+                .join(", ")
+        ),
+    }
+}
+
+// One line per driver in the plain mode, as `<slot> <id> <status>`, and the
+// whole result at once in the JSON mode.
+fn print_plain_and_json(devices_drivers: &[actions::DeviceDrivers]) {
+    for device_drivers in devices_drivers {
+        for choice in device_drivers.choices() {
+            let status = match (
+                choice.is_installed,
+                device_drivers
+                    .recommended
+                    .as_ref()
+                    == Some(choice),
+            ) {
+                (true, _) => "installed",
+                (false, true) => "recommended",
+                (false, false) => "alternative",
+            };
+            tracing::info!(target:"PLAIN", "{} {} {}", device_drivers.device.slot, choice.id, status);
+        }
+    }
+    tracing::info!(target:"JSON", "{}", serde_json::json!(devices_drivers));
+}
+
+fn generate_database(arguments: &GenerateDatabaseActionArguments) -> Result<(), aldm::Error> {
+    let database_filepath = arguments
+        .database_file
+        .clone()
+        .unwrap_or_else(data::default_database_filepath);
+
+    let database = actions::generate_db(&arguments.input_file, &database_filepath)
+        .context(actions::CouldNotGenerateDatabaseSnafu {})
+        .context(aldm::ActionsSnafu {})?;
+
+    tracing::info!(
+        "{} The database (version {}) was written to {:?}.",
+        console::Emoji("🗃️", ""),
+        database.version,
+        database_filepath
+    );
+    tracing::info!(target:"PLAIN", "{}", database_filepath.display());
+    tracing::info!(
+        target:"JSON", "{}",
+        serde_json::json!({
+            "database_filepath": database_filepath,
+            "version": database.version,
+        })
+    );
+
+    Ok(())
+}
+
+fn report_bug(
+    arguments: &ReportBugActionArguments,
+    session: &ui::Session,
+) -> Result<(), aldm::Error> {
+    let bug_report_filepath = arguments
+        .output_file
+        .clone()
+        .unwrap_or_else(actions::default_bug_report_filepath);
+    let database_filepath = arguments
+        .database_file
+        .clone()
+        .unwrap_or_else(data::default_database_filepath);
+
+    actions::report_bug(
+        &bug_report_filepath,
+        &session.log_dirpath,
+        &session.log_filepath,
+        &session.config,
+        &session.config_filepaths,
+        &database_filepath,
+    )
+    .context(actions::CouldNotReportBugSnafu {})
+    .context(aldm::ActionsSnafu {})?;
+
+    tracing::info!(
+        "{} The bug report was written to {:?}. Please attach it when reporting the issue.",
+        console::Emoji("📦", ""),
+        bug_report_filepath
+    );
+    tracing::info!(target:"PLAIN", "{}", bug_report_filepath.display());
+    tracing::info!(
+        target:"JSON", "{}",
+        serde_json::json!({ "bug_report_filepath": bug_report_filepath })
+    );
+
     Ok(())
 }
 
@@ -19,14 +689,18 @@ pub enum Error {
 }
 
 // region: IMPORTS
-use aldm::ui;
+use std::{env, path::PathBuf};
+
+use aldm::{actions, app, backend, data, probe, system, ui};
 use owo_colors::OwoColorize;
-use snafu::Snafu;
+use snafu::{ResultExt, Snafu};
 
 // endregion: IMPORTS
 
 // region: MODULES
 
+mod progress;
+
 mod cli_template {
     #[derive(Parser, Debug)]
     #[command(version, author, about, args_conflicts_with_subcommands = true)]
@@ -98,13 +772,39 @@ mod cli_template {
         #[clap(name = "install", about = "Install Drivers.", display_order = 3)]
         Install(InstallActionArguments),
 
+        #[clap(
+            name = "rollback",
+            about = "Roll back the last transaction, or the one with the given ID.",
+            display_order = 4
+        )]
+        Rollback(RollbackActionArguments),
+
+        #[clap(
+            name = "history",
+            about = "List the past transactions of aldm on this system.",
+            display_order = 5
+        )]
+        History(HistoryActionArguments),
+
         #[clap(
             name = "generate-database",
             aliases = ["generate-db", "gen-db", "gendb"],
             about = "Generate database from input file.", 
-            display_order = 4
+            display_order = 6
         )]
         GenerateDatabase(GenerateDatabaseActionArguments),
+
+        #[clap(
+            name = "report-bug",
+            about = "Bundle logs, configuration and a hardware snapshot for a bug report.",
+            display_order = 7
+        )]
+        ReportBug(ReportBugActionArguments),
+
+        // Run by aldm itself through pkexec, to make the changes of a
+        // transaction as root
+        #[clap(name = actions::HELPER_COMMAND, hide = true)]
+        ElevatedHelper(ElevatedHelperActionArguments),
     }
 
     #[derive(Debug, Args)]
@@ -221,8 +921,49 @@ mod cli_template {
             display_order = 5
         )]
         pub database_file: Option<PathBuf>,
+
+        #[clap(
+            long = "noconfirm",
+            alias = "yes",
+            short = 'y',
+            help = "Install without asking for confirmation.",
+            display_order = 6
+        )]
+        pub noconfirm: bool,
+    }
+
+    #[derive(Debug, Args)]
+    pub struct RollbackActionArguments {
+        #[clap(
+            help = "ID of the transaction to roll back, as in /var/lib/aldm/transactions.",
+            display_order = 1
+        )]
+        pub id: Option<String>,
+
+        #[clap(
+            long = "enable-aur",
+            alias = "aur",
+            help = "Enable reinstalling from the Arch User Repository (AUR).",
+            display_order = 2
+        )]
+        pub enable_aur: bool,
+
+        #[clap(
+            long = "noconfirm",
+            alias = "yes",
+            short = 'y',
+            help = "Roll back without asking for confirmation.",
+            display_order = 3
+        )]
+        pub noconfirm: bool,
     }
 
+    #[derive(Debug, Args)]
+    pub struct HistoryActionArguments {}
+
+    #[derive(Debug, Args)]
+    pub struct ElevatedHelperActionArguments {}
+
     #[derive(Debug, Args)]
     pub struct GenerateDatabaseActionArguments {
         #[clap(
@@ -235,9 +976,29 @@ mod cli_template {
         pub database_file: Option<PathBuf>,
     }
 
+    #[derive(Debug, Args)]
+    pub struct ReportBugActionArguments {
+        #[clap(
+            long = "output",
+            short = 'o',
+            help = "Path to the bug report (a .tar.gz file) to write.",
+            display_order = 1
+        )]
+        pub output_file: Option<PathBuf>,
+
+        #[clap(
+            long = "database",
+            alias = "db",
+            help = "Path to the database file to include the version of.",
+            display_order = 2
+        )]
+        pub database_file: Option<PathBuf>,
+    }
+
     // region: IMPORTS
 
     use aldm::{
+        actions,
         data::HardwareKind,
         ui::{self, GlobalArgs},
     };
@@ -252,5 +1013,6 @@ mod cli_template {
 // region: RE-EXPORTS
 
 pub use cli_template::*;
+pub use progress::*;
 
 // endregion: RE-EXPORTS
@@ -0,0 +1,151 @@
+// Renders the progress of an action on the command line. In the regular mode,
+// a status line with a spinner or a progress bar is redrawn on the terminal
+// for every event. In the JSON mode, every event is written out as one line of
+// JSON (NDJSON). The plain mode only shows the results.
+pub struct CliProgress {
+    logging_mode: LoggingMode,
+    term: Term,
+    status: Mutex<Status>,
+}
+
+#[derive(Default)]
+struct Status {
+    frame: usize,
+    line: Option<String>,
+}
+
+impl CliProgress {
+    pub fn new(logging_mode: LoggingMode) -> Self {
+        CliProgress {
+            logging_mode,
+            term: Term::stderr(),
+            status: Mutex::new(Status::default()),
+        }
+    }
+
+    // Remove the status line, so that the results are printed on a clean line
+    pub fn finish(&self) {
+        let Ok(mut status) = self.status.lock() else {
+            return;
+        };
+        if status
+            .line
+            .take()
+            .is_some()
+        {
+            self.term
+                .clear_line()
+                .ok();
+        }
+    }
+
+    fn draw(&self, event: &ProgressEvent) {
+        let Ok(mut status) = self.status.lock() else {
+            return;
+        };
+        status.frame = (status.frame + 1) % SPINNER_FRAMES.len();
+        let spinner = SPINNER_FRAMES[status.frame];
+
+        match event {
+            ProgressEvent::Phase { phase } => {
+                status.line = Some(format!("{} {}...", spinner.cyan(), phase));
+            }
+            ProgressEvent::Step {
+                phase,
+                current,
+                total: Some(total),
+                message,
+            } => {
+                let filled = (BAR_WIDTH as u64 * current)
+                    .checked_div(*total)
+                    .unwrap_or(0)
+                    .min(BAR_WIDTH as u64) as usize;
+                status.line = Some(format!(
+                    "{} [{}{}] {}/{} {}: {}",
+                    spinner.cyan(),
+                    "=".repeat(filled),
+                    " ".repeat(BAR_WIDTH - filled),
+                    current,
+                    total,
+                    phase,
+                    message
+                ));
+            }
+            ProgressEvent::Step {
+                phase,
+                message,
+                total: None,
+                ..
+            } => {
+                status.line = Some(format!("{} {}: {}", spinner.cyan(), phase, message));
+            }
+            // Log lines scroll by above the status line
+            ProgressEvent::Log { line } => {
+                self.term
+                    .clear_line()
+                    .ok();
+                self.term
+                    .write_line(line)
+                    .ok();
+            }
+        }
+
+        if let Some(line) = &status.line {
+            self.term
+                .clear_line()
+                .ok();
+            self.term
+                .write_str(&console::truncate_str(
+                    line,
+                    self.term.size().1 as usize,
+                    "…",
+                ))
+                .ok();
+        }
+    }
+}
+
+impl ProgressSink for CliProgress {
+    fn report(&self, event: ProgressEvent) {
+        match self.logging_mode {
+            LoggingMode::Json => {
+                tracing::info!(target:"JSON", "{}", serde_json::json!(event));
+            }
+            LoggingMode::Plain => {}
+            LoggingMode::Regular | LoggingMode::Test => match self.term.is_term() {
+                true => self.draw(&event),
+                false => match event {
+                    ProgressEvent::Phase { phase } => tracing::info!("{}...", phase),
+                    ProgressEvent::Step {
+                        phase,
+                        current,
+                        total,
+                        message,
+                    } => match total {
+                        Some(total) => {
+                            tracing::info!("{} ({}/{}): {}", phase, current, total, message)
+                        }
+                        None => tracing::info!("{}: {}", phase, message),
+                    },
+                    ProgressEvent::Log { line } => tracing::info!("{}", line),
+                },
+            },
+        }
+    }
+}
+
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const BAR_WIDTH: usize = 20;
+
+// region: IMPORTS
+
+use std::sync::Mutex;
+
+use aldm::{
+    actions::{ProgressEvent, ProgressSink},
+    app::LoggingMode,
+};
+use console::Term;
+use owo_colors::OwoColorize;
+
+// endregion: IMPORTS
@@ -0,0 +1,207 @@
+// A detected device along with its drivers, and a drop-down to pick the driver
+// to use for it.
+#[derive(Debug)]
+pub struct DeviceRow {
+    device_drivers: DeviceDrivers,
+    choice_ids: Vec<String>,
+    choice_labels: Vec<String>,
+    selected_position: usize,
+}
+
+#[derive(Debug)]
+pub enum DeviceRowInput {
+    SelectDriver(usize),
+    Install,
+    Remove,
+}
+
+#[derive(Debug)]
+pub enum DeviceRowOutput {
+    DriverSelected { slot: String, driver_id: String },
+    InstallRequested { slot: String, driver_id: String },
+    RemoveRequested { slot: String },
+}
+
+#[relm4::factory(pub)]
+impl FactoryComponent for DeviceRow {
+    type Init = DeviceDrivers;
+    type Input = DeviceRowInput;
+    type Output = DeviceRowOutput;
+    type CommandOutput = ();
+    type ParentInput = WindowInput;
+    type ParentWidget = gtk::ListBox;
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+            set_spacing: 4,
+            set_margin_all: 8,
+
+            gtk::Label {
+                set_halign: gtk::Align::Start,
+                set_markup: &format!(
+                    "<b>{}</b>",
+                    glib::markup_escape_text(&self.device_drivers.device_label())
+                ),
+            },
+
+            gtk::Label {
+                set_halign: gtk::Align::Start,
+                set_label: &format!("Installed: {}", self.installed_label()),
+            },
+
+            gtk::Label {
+                set_halign: gtk::Align::Start,
+                set_label: &format!("Recommended: {}", self.recommended_label()),
+            },
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Horizontal,
+                set_spacing: 8,
+
+                gtk::Label {
+                    set_label: "Driver:",
+                },
+
+                gtk::DropDown::from_strings(
+                    &self
+                        .choice_labels
+                        .iter()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>()
+                ) {
+                    set_hexpand: true,
+                    connect_selected_notify[sender] => move |drop_down| {
+                        sender.input(DeviceRowInput::SelectDriver(drop_down.selected() as usize))
+                    },
+                },
+
+                gtk::Button {
+                    set_label: "Install",
+                    set_tooltip_text: Some("Switch the device to the selected driver"),
+                    connect_clicked => DeviceRowInput::Install,
+                },
+
+                gtk::Button {
+                    set_label: "Remove",
+                    set_tooltip_text: Some("Remove the installed driver of the device"),
+                    set_sensitive: !self.device_drivers.installed.is_empty(),
+                    connect_clicked => DeviceRowInput::Remove,
+                },
+            },
+        }
+    }
+
+    fn init_model(
+        device_drivers: Self::Init,
+        _index: &DynamicIndex,
+        _sender: FactorySender<Self>,
+    ) -> Self {
+        let choice_ids = device_drivers
+            .choices()
+            .map(|choice| choice.id.clone())
+            .collect();
+        let choice_labels = device_drivers
+            .choices()
+            .enumerate()
+            .map(|(position, choice)| match (position, choice.is_installed) {
+                (_, true) => format!("{} (installed)", choice.id),
+                (0, false) => format!("{} (recommended)", choice.id),
+                _ => choice.id.clone(),
+            })
+            .collect();
+        DeviceRow {
+            device_drivers,
+            choice_ids,
+            choice_labels,
+            selected_position: 0,
+        }
+    }
+
+    fn update(&mut self, input: Self::Input, sender: FactorySender<Self>) {
+        let slot = self
+            .device_drivers
+            .device
+            .slot
+            .clone();
+        match input {
+            DeviceRowInput::SelectDriver(position) => {
+                self.selected_position = position;
+                if let Some(driver_id) = self.selected_driver_id() {
+                    sender.output(DeviceRowOutput::DriverSelected { slot, driver_id });
+                }
+            }
+            DeviceRowInput::Install => {
+                if let Some(driver_id) = self.selected_driver_id() {
+                    sender.output(DeviceRowOutput::InstallRequested { slot, driver_id });
+                }
+            }
+            DeviceRowInput::Remove => sender.output(DeviceRowOutput::RemoveRequested { slot }),
+        }
+    }
+
+    fn forward_to_parent(output: Self::Output) -> Option<WindowInput> {
+        Some(match output {
+            DeviceRowOutput::DriverSelected { slot, driver_id } => {
+                WindowInput::DriverSelected { slot, driver_id }
+            }
+            DeviceRowOutput::InstallRequested { slot, driver_id } => {
+                WindowInput::InstallRequested { slot, driver_id }
+            }
+            DeviceRowOutput::RemoveRequested { slot } => WindowInput::RemoveRequested { slot },
+        })
+    }
+}
+
+impl DeviceRow {
+    pub fn device_drivers(&self) -> &DeviceDrivers {
+        &self.device_drivers
+    }
+
+    fn selected_driver_id(&self) -> Option<String> {
+        self.choice_ids
+            .get(self.selected_position)
+            .cloned()
+    }
+
+    fn installed_label(&self) -> String {
+        match self
+            .device_drivers
+            .installed
+            .is_empty()
+        {
+            true => String::from("none"),
+            false => self
+                .device_drivers
+                .installed
+                .iter()
+                .map(|choice| choice.id.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+
+    fn recommended_label(&self) -> String {
+        self.device_drivers
+            .recommended
+            .as_ref()
+            .map(|choice| choice.id.clone())
+            .unwrap_or_else(|| String::from("none"))
+    }
+}
+
+// region: IMPORTS
+
+use aldm::actions::DeviceDrivers;
+use relm4::{
+    factory::{DynamicIndex, FactoryComponent, FactorySender},
+    gtk::{
+        self, glib,
+        prelude::{BoxExt, ButtonExt, OrientableExt, WidgetExt},
+    },
+    RelmWidgetExt,
+};
+
+use crate::WindowInput;
+
+// endregion: IMPORTS
@@ -1,5 +1,5 @@
 pub fn run_gui() -> Result<(), aldm::Error> {
-    let (_cli_input, _worker_guards) = ui::run_common::<CliTemplate>()?;
+    let (cli_input, session) = ui::run_common::<CliTemplate>()?;
 
     tracing::debug!(
         "Running in {} mode... {}",
@@ -7,9 +7,24 @@ pub fn run_gui() -> Result<(), aldm::Error> {
         console::Emoji("📊", "")
     );
 
+    let database_filepath = cli_input
+        .database_file
+        .unwrap_or_else(data::default_database_filepath);
+    // GTK would otherwise try to parse the command line arguments, which were
+    // already parsed above
+    RelmApp::new(APP_ID)
+        .with_args(vec![])
+        .run::<Window>(WindowInit {
+            database_filepath,
+            config: session.config,
+            log_filepath: session.log_filepath,
+        });
+
     Ok(())
 }
 
+const APP_ID: &str = "io.github.shivanandvp.aldm";
+
 #[derive(Debug, Snafu)]
 #[non_exhaustive]
 pub enum Error {
@@ -20,20 +35,34 @@ pub enum Error {
 
 // region: IMPORTS
 
-use aldm::ui;
+use aldm::{data, ui};
 use owo_colors::OwoColorize;
+use relm4::RelmApp;
 use snafu::Snafu;
 
 // endregion: IMPORTS
 
 // region: MODULES
 
+mod device_row;
+mod log_page;
+mod settings_page;
+mod transaction_dialog;
+mod window;
+
 mod gui_cli_template {
     #[derive(Parser, Debug)]
     #[command(version, author, about, args_conflicts_with_subcommands = true)]
     pub struct CliTemplate {
         #[clap(flatten)]
         pub global_args: ui::GlobalArgs<clap_verbosity_flag::InfoLevel>,
+
+        #[clap(
+            long = "database",
+            alias = "db",
+            help = "Path to the database file to use for recognizing drivers."
+        )]
+        pub database_file: Option<PathBuf>,
     }
 
     impl ui::GlobalArguments for CliTemplate {
@@ -90,6 +119,11 @@ mod gui_cli_template {
 
 // region: RE-EXPORTS
 
+pub use device_row::*;
 pub use gui_cli_template::*;
+pub use log_page::*;
+pub use settings_page::*;
+pub use transaction_dialog::*;
+pub use window::*;
 
 // endregion: RE-EXPORTS
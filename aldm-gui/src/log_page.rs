@@ -0,0 +1,262 @@
+// Follows the current log file, filtered by level, with buttons to copy the
+// shown lines or export them to a file for a support request.
+pub struct LogPage {
+    // The log file that was current at startup, shown until the tail finds
+    // the newest one
+    log_filepath: PathBuf,
+    log_tail: LogTail,
+    lines: VecDeque<(Option<Level>, String)>,
+    level_filter: Option<Level>,
+    text_view: gtk::TextView,
+    status: String,
+}
+
+#[derive(Debug)]
+pub enum LogPageInput {
+    Poll,
+    SetLevelFilter(u32),
+    Copy,
+    Export,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for LogPage {
+    type Init = PathBuf;
+    type Input = LogPageInput;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+            set_spacing: 8,
+            set_margin_all: 12,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Horizontal,
+                set_spacing: 8,
+
+                gtk::Label {
+                    set_halign: gtk::Align::Start,
+                    set_hexpand: true,
+                    set_wrap: true,
+                    set_selectable: true,
+                    #[watch]
+                    set_label: &format!(
+                        "Log file: {}",
+                        model
+                            .log_tail
+                            .current_log_filepath()
+                            .unwrap_or(&model.log_filepath)
+                            .display()
+                    ),
+                },
+
+                gtk::DropDown::from_strings(&LEVEL_FILTERS) {
+                    set_tooltip_text: Some("Show lines up to this level"),
+                    connect_selected_notify[sender] => move |drop_down| {
+                        sender.input(LogPageInput::SetLevelFilter(drop_down.selected()))
+                    },
+                },
+
+                gtk::Button {
+                    set_label: "Copy",
+                    set_tooltip_text: Some("Copy the shown lines to the clipboard"),
+                    connect_clicked => LogPageInput::Copy,
+                },
+
+                gtk::Button {
+                    set_label: "Export",
+                    set_tooltip_text: Some("Save the shown lines to a file in the home directory"),
+                    connect_clicked => LogPageInput::Export,
+                },
+            },
+
+            gtk::ScrolledWindow {
+                set_vexpand: true,
+
+                #[local_ref]
+                text_view -> gtk::TextView {
+                    set_editable: false,
+                    set_cursor_visible: false,
+                    set_monospace: true,
+                },
+            },
+
+            gtk::Label {
+                set_halign: gtk::Align::Start,
+                set_wrap: true,
+                set_selectable: true,
+                #[watch]
+                set_label: &model.status,
+            },
+        }
+    }
+
+    fn init(
+        log_filepath: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let mut model = LogPage {
+            log_tail: LogTail::new(
+                log_filepath
+                    .parent()
+                    .map(Path::to_path_buf)
+                    .unwrap_or_default(),
+                format!("{}.log", *app::APP_NAME),
+            ),
+            log_filepath,
+            lines: VecDeque::new(),
+            level_filter: None,
+            text_view: gtk::TextView::new(),
+            status: String::new(),
+        };
+        model.poll();
+
+        let poll_sender = sender.clone();
+        glib::timeout_add_local(POLL_INTERVAL, move || {
+            poll_sender.input(LogPageInput::Poll);
+            glib::Continue(true)
+        });
+
+        let text_view = &model.text_view;
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, input: Self::Input, _sender: ComponentSender<Self>) {
+        match input {
+            LogPageInput::Poll => self.poll(),
+            LogPageInput::SetLevelFilter(position) => {
+                self.level_filter = LEVEL_FILTERS
+                    .get(position as usize)
+                    .and_then(|name| name.parse().ok());
+                self.show_lines();
+            }
+            LogPageInput::Copy => {
+                self.text_view
+                    .clipboard()
+                    .set_text(&self.shown_text());
+                self.status = String::from("Copied the shown lines to the clipboard.");
+            }
+            LogPageInput::Export => {
+                let export_filepath = env::var_os("HOME")
+                    .map(PathBuf::from)
+                    .unwrap_or_default()
+                    .join(format!(
+                        "{}-log-{}.txt",
+                        *app::APP_NAME,
+                        app::filename_timestamp()
+                    ));
+                self.status = match fs::write(&export_filepath, self.shown_text()) {
+                    Ok(()) => format!("Exported the shown lines to {}", export_filepath.display()),
+                    Err(error) => format!(
+                        "Could not export the logs to {}: {error}",
+                        export_filepath.display()
+                    ),
+                };
+            }
+        }
+    }
+}
+
+impl LogPage {
+    fn poll(&mut self) {
+        let new_lines = match self
+            .log_tail
+            .read_new_lines()
+        {
+            Ok(new_lines) => new_lines,
+            Err(error) => {
+                self.status = format!("{error}");
+                return;
+            }
+        };
+        if new_lines.is_empty() {
+            return;
+        }
+
+        let buffer = self
+            .text_view
+            .buffer();
+        for line in new_lines {
+            // Lines that continue a record belong to the level of the record
+            let level = app::log_line_level(&line).or_else(|| {
+                self.lines
+                    .back()
+                    .and_then(|(level, _)| *level)
+            });
+            if self.is_shown(level) {
+                buffer.insert(&mut buffer.end_iter(), &format!("{line}\n"));
+            }
+            self.lines
+                .push_back((level, line));
+        }
+
+        if self.lines.len() > MAX_LINES {
+            self.lines
+                .drain(..self.lines.len() - MAX_LINES);
+            self.show_lines();
+        }
+        self.scroll_to_end();
+    }
+
+    fn is_shown(&self, level: Option<Level>) -> bool {
+        match (self.level_filter, level) {
+            (Some(level_filter), Some(level)) => level <= level_filter,
+            _ => true,
+        }
+    }
+
+    fn shown_text(&self) -> String {
+        self.lines
+            .iter()
+            .filter(|(level, _)| self.is_shown(*level))
+            .map(|(_, line)| format!("{line}\n"))
+            .collect()
+    }
+
+    fn show_lines(&self) {
+        self.text_view
+            .buffer()
+            .set_text(&self.shown_text());
+        self.scroll_to_end();
+    }
+
+    fn scroll_to_end(&self) {
+        let buffer = self
+            .text_view
+            .buffer();
+        let end_mark = buffer.create_mark(None, &buffer.end_iter(), false);
+        self.text_view
+            .scroll_mark_onscreen(&end_mark);
+        buffer.delete_mark(&end_mark);
+    }
+}
+
+// "All" does not parse as a level, which shows every line
+const LEVEL_FILTERS: [&str; 6] = ["All", "Error", "Warn", "Info", "Debug", "Trace"];
+const MAX_LINES: usize = 10_000;
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// region: IMPORTS
+
+use std::{
+    collections::VecDeque,
+    env, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use aldm::app::{self, LogTail};
+use relm4::{
+    gtk::{
+        self, glib,
+        prelude::{BoxExt, ButtonExt, OrientableExt, TextBufferExt, TextViewExt, WidgetExt},
+    },
+    ComponentParts, ComponentSender, RelmWidgetExt, SimpleComponent,
+};
+use tracing::Level;
+
+// endregion: IMPORTS
@@ -0,0 +1,348 @@
+// Edits the config file of the current user. The form starts out with the
+// effective configuration, and saving writes every value in the form to the
+// user's config file while keeping any other values already in it.
+pub struct SettingsPage {
+    config: Config,
+    user_config_filepath: Option<PathBuf>,
+    system_config_note: String,
+    status: String,
+}
+
+#[derive(Debug)]
+pub enum SettingsPageInput {
+    SetLogDirectory(String),
+    SetLogLevelFilter(u32),
+    SetNoColor(bool),
+    SetPreferredTags(String),
+    SetExcludedTags(String),
+    SetEnableAur(bool),
+    SetAurHelper(String),
+    Save,
+}
+
+#[derive(Debug)]
+pub enum SettingsPageOutput {
+    Saved(Config),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for SettingsPage {
+    type Init = Config;
+    type Input = SettingsPageInput;
+    type Output = SettingsPageOutput;
+
+    view! {
+        gtk::ScrolledWindow {
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 8,
+                set_margin_all: 12,
+
+                gtk::Label {
+                    set_halign: gtk::Align::Start,
+                    set_wrap: true,
+                    set_selectable: true,
+                    set_label: &match &model.user_config_filepath {
+                        Some(user_config_filepath) => format!("Settings are saved to {}", user_config_filepath.display()),
+                        None => String::from("The config file of the user could not be found, so settings cannot be saved."),
+                    },
+                },
+
+                gtk::Label {
+                    set_halign: gtk::Align::Start,
+                    set_wrap: true,
+                    set_label: &model.system_config_note,
+                },
+
+                gtk::Grid {
+                    set_row_spacing: 8,
+                    set_column_spacing: 12,
+
+                    attach[0, 0, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_label: "Log directory",
+                    },
+                    attach[1, 0, 1, 1] = &gtk::Entry {
+                        set_hexpand: true,
+                        set_placeholder_text: Some("Default"),
+                        set_text: model.config.log_directory.as_deref().unwrap_or_default(),
+                        connect_changed[sender] => move |entry| {
+                            sender.input(SettingsPageInput::SetLogDirectory(entry.text().to_string()))
+                        },
+                    },
+
+                    attach[0, 1, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_label: "Log level",
+                    },
+                    attach[1, 1, 1, 1] = &gtk::DropDown::from_strings(&LOG_LEVEL_FILTERS) {
+                        set_selected: model
+                            .config
+                            .log_level_filter
+                            .and_then(|log_level_filter| {
+                                LOG_LEVEL_FILTERS
+                                    .iter()
+                                    .position(|name| name.eq_ignore_ascii_case(log_level_filter.as_str()))
+                            })
+                            .unwrap_or(3) as u32,
+                        connect_selected_notify[sender] => move |drop_down| {
+                            sender.input(SettingsPageInput::SetLogLevelFilter(drop_down.selected()))
+                        },
+                    },
+
+                    attach[0, 2, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_label: "Disable colors",
+                    },
+                    attach[1, 2, 1, 1] = &gtk::Switch {
+                        set_halign: gtk::Align::Start,
+                        set_active: model.config.no_color.unwrap_or(false),
+                        connect_state_set[sender] => move |_, state| {
+                            sender.input(SettingsPageInput::SetNoColor(state));
+                            gtk::Inhibit(false)
+                        },
+                    },
+
+                    attach[0, 3, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_label: "Preferred tags",
+                    },
+                    attach[1, 3, 1, 1] = &gtk::Entry {
+                        set_hexpand: true,
+                        set_placeholder_text: Some("For example: open_source, dkms"),
+                        set_text: &join_tags(&model.config.preferred_tags),
+                        connect_changed[sender] => move |entry| {
+                            sender.input(SettingsPageInput::SetPreferredTags(entry.text().to_string()))
+                        },
+                    },
+
+                    attach[0, 4, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_label: "Excluded tags",
+                    },
+                    attach[1, 4, 1, 1] = &gtk::Entry {
+                        set_hexpand: true,
+                        set_placeholder_text: Some("For example: proprietary"),
+                        set_text: &join_tags(&model.config.excluded_tags),
+                        connect_changed[sender] => move |entry| {
+                            sender.input(SettingsPageInput::SetExcludedTags(entry.text().to_string()))
+                        },
+                    },
+
+                    attach[0, 5, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_label: "Enable the AUR",
+                    },
+                    attach[1, 5, 1, 1] = &gtk::Switch {
+                        set_halign: gtk::Align::Start,
+                        set_active: model.config.enable_aur.unwrap_or(false),
+                        connect_state_set[sender] => move |_, state| {
+                            sender.input(SettingsPageInput::SetEnableAur(state));
+                            gtk::Inhibit(false)
+                        },
+                    },
+
+                    attach[0, 6, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_label: "AUR helper",
+                    },
+                    attach[1, 6, 1, 1] = &gtk::Entry {
+                        set_hexpand: true,
+                        set_placeholder_text: Some("Detect paru or yay"),
+                        set_text: model.config.aur_helper.as_deref().unwrap_or_default(),
+                        connect_changed[sender] => move |entry| {
+                            sender.input(SettingsPageInput::SetAurHelper(entry.text().to_string()))
+                        },
+                    },
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 8,
+
+                    gtk::Button {
+                        set_label: "Save",
+                        add_css_class: "suggested-action",
+                        set_sensitive: model.user_config_filepath.is_some(),
+                        connect_clicked => SettingsPageInput::Save,
+                    },
+
+                    gtk::Label {
+                        set_wrap: true,
+                        #[watch]
+                        set_label: &model.status,
+                    },
+                },
+            },
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let user_config_filepath = match config::user_config_filepath() {
+            Ok(user_config_filepath) => Some(user_config_filepath),
+            Err(error) => {
+                tracing::error!("Could not find the config file of the user: {}", error);
+                None
+            }
+        };
+        let model = SettingsPage {
+            config,
+            user_config_filepath,
+            system_config_note: system_config_note(),
+            status: String::new(),
+        };
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, input: Self::Input, sender: ComponentSender<Self>) {
+        match input {
+            SettingsPageInput::SetLogDirectory(log_directory) => {
+                self.config
+                    .log_directory = Some(log_directory).filter(|log_directory| {
+                    !log_directory
+                        .trim()
+                        .is_empty()
+                });
+            }
+            SettingsPageInput::SetLogLevelFilter(position) => {
+                self.config
+                    .log_level_filter = LOG_LEVEL_FILTERS
+                    .get(position as usize)
+                    .and_then(|name| name.parse().ok());
+            }
+            SettingsPageInput::SetNoColor(no_color) => self.config.no_color = Some(no_color),
+            SettingsPageInput::SetPreferredTags(tags) => {
+                self.config
+                    .preferred_tags = Some(split_tags(&tags))
+            }
+            SettingsPageInput::SetExcludedTags(tags) => {
+                self.config
+                    .excluded_tags = Some(split_tags(&tags))
+            }
+            SettingsPageInput::SetEnableAur(enable_aur) => {
+                self.config
+                    .enable_aur = Some(enable_aur)
+            }
+            SettingsPageInput::SetAurHelper(aur_helper) => {
+                self.config
+                    .aur_helper = Some(aur_helper).filter(|aur_helper| {
+                    !aur_helper
+                        .trim()
+                        .is_empty()
+                });
+            }
+            SettingsPageInput::Save => {
+                let Some(user_config_filepath) = &self.user_config_filepath else {
+                    return;
+                };
+                match save(&self.config, user_config_filepath) {
+                    Ok(()) => {
+                        tracing::info!("Saved the settings to {:?}", user_config_filepath);
+                        self.status = String::from(
+                            "Saved. The log settings take effect the next time the application starts.",
+                        );
+                        sender
+                            .output(SettingsPageOutput::Saved(self.config.clone()))
+                            .ok();
+                    }
+                    Err(error) => {
+                        tracing::error!("Could not save the settings: {}", error);
+                        self.status = format!("Could not save the settings: {error}");
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Update the values in the form within the user's config file, leaving the
+// rest of the file alone
+fn save(form_config: &Config, user_config_filepath: &PathBuf) -> Result<(), config::Error> {
+    let mut config = match user_config_filepath.exists() {
+        true => config::read_config_file(user_config_filepath)?,
+        false => Config::new(),
+    };
+    config.log_directory = form_config
+        .log_directory
+        .clone();
+    config.log_level_filter = form_config.log_level_filter;
+    config.no_color = form_config.no_color;
+    config.preferred_tags = form_config
+        .preferred_tags
+        .clone();
+    config.excluded_tags = form_config
+        .excluded_tags
+        .clone();
+    config.enable_aur = form_config.enable_aur;
+    config.aur_helper = form_config
+        .aur_helper
+        .clone();
+    config::create_config_file(&config, user_config_filepath)
+}
+
+// Which values the system-wide config files set. The user's config file takes
+// precedence over them, so they only apply to values the user leaves unset.
+fn system_config_note() -> String {
+    let notes = config::system_config_filepaths()
+        .into_iter()
+        .filter(|system_config_filepath| system_config_filepath.exists())
+        .map(
+            |system_config_filepath| match config::read_config_file(&system_config_filepath) {
+                Ok(system_config) => {
+                    let assigned_keys = system_config.assigned_keys();
+                    match assigned_keys.is_empty() {
+                        true => format!("{} sets no values.", system_config_filepath.display()),
+                        false => format!(
+                            "{} sets {}. Values saved here override these.",
+                            system_config_filepath.display(),
+                            assigned_keys.join(", ")
+                        ),
+                    }
+                }
+                Err(error) => format!("{error}"),
+            },
+        )
+        .collect::<Vec<_>>();
+    match notes.is_empty() {
+        true => String::from("There are no system-wide config files."),
+        false => notes.join("\n"),
+    }
+}
+
+fn join_tags(tags: &Option<Vec<String>>) -> String {
+    tags.as_deref()
+        .unwrap_or_default()
+        .join(", ")
+}
+
+fn split_tags(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+const LOG_LEVEL_FILTERS: [&str; 6] = ["Off", "Error", "Warn", "Info", "Debug", "Trace"];
+
+// region: IMPORTS
+
+use std::path::PathBuf;
+
+use aldm::app::{config, Config};
+use relm4::{
+    gtk::{
+        self,
+        prelude::{BoxExt, ButtonExt, EditableExt, EntryExt, GridExt, OrientableExt, WidgetExt},
+    },
+    ComponentParts, ComponentSender, RelmWidgetExt, SimpleComponent,
+};
+
+// endregion: IMPORTS
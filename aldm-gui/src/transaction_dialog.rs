@@ -0,0 +1,344 @@
+// Shows a transaction plan for confirmation, then runs it while streaming
+// pacman's output and the current phase, and ends with the outcome of the transaction.
+pub struct TransactionDialog {
+    plan: Option<TransactionPlan>,
+    backend_settings: BackendSettings,
+    root: PathBuf,
+    state: TransactionState,
+    output_buffer: gtk::TextBuffer,
+    progress_text: String,
+    progress_fraction: f64,
+    cancellation_token: CancellationToken,
+    is_visible: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum TransactionState {
+    Confirming,
+    Running,
+    Cancelling,
+    Succeeded,
+    RebootRequired,
+    Cancelled,
+    Failed(String),
+}
+
+impl TransactionState {
+    fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            TransactionState::Succeeded
+                | TransactionState::RebootRequired
+                | TransactionState::Cancelled
+                | TransactionState::Failed(_)
+        )
+    }
+
+    fn is_running(&self) -> bool {
+        matches!(
+            self,
+            TransactionState::Running | TransactionState::Cancelling
+        )
+    }
+
+    fn description(&self) -> String {
+        match self {
+            TransactionState::Confirming => String::from("The following changes will be made:"),
+            TransactionState::Running => String::from("Applying the changes..."),
+            TransactionState::Cancelling => String::from(
+                "Cancelling... Changes that pacman has already started committing are completed first.",
+            ),
+            TransactionState::Succeeded => String::from("The changes were applied successfully."),
+            TransactionState::RebootRequired => String::from(
+                "The changes were applied successfully. Reboot for them to take effect.",
+            ),
+            TransactionState::Cancelled => String::from("The transaction was cancelled."),
+            TransactionState::Failed(error) => format!("The transaction failed: {error}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum TransactionDialogInput {
+    Show(Box<TransactionPlan>, BackendSettings, PathBuf),
+    Confirm,
+    Cancel,
+    Close,
+    Progress(ProgressEvent),
+    Finished(Result<TransactionOutcome, String>),
+}
+
+#[derive(Debug)]
+pub enum TransactionDialogOutput {
+    Finished,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for TransactionDialog {
+    type Init = ();
+    type Input = TransactionDialogInput;
+    type Output = TransactionDialogOutput;
+
+    view! {
+        gtk::Window {
+            set_title: Some("Driver Transaction"),
+            set_modal: true,
+            set_default_size: (560, 420),
+            #[watch]
+            set_visible: model.is_visible,
+            connect_close_request[sender] => move |_| {
+                sender.input(TransactionDialogInput::Close);
+                gtk::Inhibit(true)
+            },
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 8,
+                set_margin_all: 12,
+
+                gtk::Label {
+                    set_halign: gtk::Align::Start,
+                    set_wrap: true,
+                    #[watch]
+                    set_markup: &format!(
+                        "<b>{}</b>",
+                        glib::markup_escape_text(&model.state.description())
+                    ),
+                },
+
+                gtk::Label {
+                    set_halign: gtk::Align::Start,
+                    set_wrap: true,
+                    set_selectable: true,
+                    #[watch]
+                    set_label: &model
+                        .plan
+                        .as_ref()
+                        .map(|plan| plan.to_string())
+                        .unwrap_or_default(),
+                },
+
+                gtk::ProgressBar {
+                    set_show_text: true,
+                    #[watch]
+                    set_visible: model.state != TransactionState::Confirming,
+                    #[watch]
+                    set_text: Some(&model.progress_text),
+                    #[watch]
+                    set_fraction: model.progress_fraction,
+                },
+
+                gtk::ScrolledWindow {
+                    set_vexpand: true,
+                    #[watch]
+                    set_visible: model.state != TransactionState::Confirming,
+
+                    gtk::TextView {
+                        set_buffer: Some(&model.output_buffer),
+                        set_editable: false,
+                        set_cursor_visible: false,
+                        set_monospace: true,
+                    },
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_halign: gtk::Align::End,
+                    set_spacing: 8,
+
+                    gtk::Button {
+                        set_label: "Cancel",
+                        #[watch]
+                        set_visible: !model.state.is_finished(),
+                        #[watch]
+                        set_sensitive: model.state != TransactionState::Cancelling,
+                        connect_clicked => TransactionDialogInput::Cancel,
+                    },
+
+                    gtk::Button {
+                        set_label: "Apply",
+                        add_css_class: "suggested-action",
+                        #[watch]
+                        set_visible: model.state == TransactionState::Confirming,
+                        connect_clicked => TransactionDialogInput::Confirm,
+                    },
+
+                    gtk::Button {
+                        set_label: "Close",
+                        #[watch]
+                        set_visible: model.state.is_finished(),
+                        connect_clicked => TransactionDialogInput::Close,
+                    },
+                },
+            },
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = TransactionDialog {
+            plan: None,
+            backend_settings: BackendSettings::default(),
+            root: probe::default_root(),
+            state: TransactionState::Confirming,
+            output_buffer: gtk::TextBuffer::new(None),
+            progress_text: String::new(),
+            progress_fraction: 0.0,
+            cancellation_token: CancellationToken::new(),
+            is_visible: false,
+        };
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, input: Self::Input, sender: ComponentSender<Self>) {
+        match input {
+            TransactionDialogInput::Show(plan, backend_settings, root) => {
+                self.plan = Some(*plan);
+                self.backend_settings = backend_settings;
+                self.root = root;
+                self.state = TransactionState::Confirming;
+                self.output_buffer
+                    .set_text("");
+                self.progress_text
+                    .clear();
+                self.progress_fraction = 0.0;
+                self.cancellation_token = CancellationToken::new();
+                self.is_visible = true;
+            }
+            TransactionDialogInput::Confirm => {
+                let Some(plan) = self.plan.clone() else {
+                    return;
+                };
+                self.state = TransactionState::Running;
+                let backend_settings = self
+                    .backend_settings
+                    .clone();
+                let root = self.root.clone();
+                let cancellation_token = self
+                    .cancellation_token
+                    .clone();
+                thread::spawn(move || {
+                    let progress = |event| sender.input(TransactionDialogInput::Progress(event));
+                    // The GUI usually runs without root, in which case the
+                    // transaction is made by the elevated helper after pkexec
+                    // asks for authentication
+                    let result = match actions::is_elevation_required() {
+                        true => actions::execute_transaction_elevated(
+                            &plan,
+                            &backend_settings,
+                            &root,
+                            &progress,
+                            &cancellation_token,
+                        ),
+                        false => backend_settings
+                            .backend()
+                            .context(actions::BackendSnafu {})
+                            .and_then(|backend| {
+                                actions::execute_transaction(
+                                    &plan,
+                                    backend.as_ref(),
+                                    &SystemCommandRunner::new(&root),
+                                    &root,
+                                    &progress,
+                                    &cancellation_token,
+                                )
+                            }),
+                    }
+                    .map_err(|error| error.to_string());
+                    sender.input(TransactionDialogInput::Finished(result));
+                });
+            }
+            TransactionDialogInput::Cancel => match self.state {
+                TransactionState::Confirming => self.is_visible = false,
+                TransactionState::Running => {
+                    self.cancellation_token
+                        .cancel();
+                    self.state = TransactionState::Cancelling;
+                }
+                _ => {}
+            },
+            TransactionDialogInput::Close => {
+                // The transaction keeps running without the dialog otherwise
+                if !self
+                    .state
+                    .is_running()
+                {
+                    self.is_visible = false;
+                }
+            }
+            TransactionDialogInput::Progress(ProgressEvent::Phase { phase }) => {
+                self.progress_text = format!("{phase}...");
+                self.progress_fraction = 0.0;
+            }
+            TransactionDialogInput::Progress(ProgressEvent::Step {
+                phase,
+                current,
+                total,
+                message,
+            }) => {
+                self.progress_text = format!("{phase}: {message}");
+                if let Some(total) = total.filter(|total| *total > 0) {
+                    self.progress_fraction = (current as f64 / total as f64).min(1.0);
+                }
+            }
+            TransactionDialogInput::Progress(ProgressEvent::Log { line }) => {
+                self.output_buffer
+                    .insert(
+                        &mut self
+                            .output_buffer
+                            .end_iter(),
+                        &format!("{line}\n"),
+                    );
+            }
+            TransactionDialogInput::Finished(result) => {
+                self.state = match result {
+                    Ok(TransactionOutcome::Succeeded) => TransactionState::Succeeded,
+                    Ok(TransactionOutcome::RebootRequired) => TransactionState::RebootRequired,
+                    Ok(TransactionOutcome::Cancelled) => TransactionState::Cancelled,
+                    Err(error) => {
+                        tracing::error!("The transaction failed: {}", error);
+                        TransactionState::Failed(error)
+                    }
+                };
+                if matches!(
+                    self.state,
+                    TransactionState::Succeeded | TransactionState::RebootRequired
+                ) {
+                    self.progress_fraction = 1.0;
+                }
+                sender
+                    .output(TransactionDialogOutput::Finished)
+                    .ok();
+            }
+        }
+    }
+}
+
+// region: IMPORTS
+
+use std::{path::PathBuf, thread};
+
+use aldm::{
+    actions::{self, CancellationToken, ProgressEvent, TransactionOutcome, TransactionPlan},
+    backend::BackendSettings,
+    probe,
+    system::SystemCommandRunner,
+};
+use relm4::{
+    gtk::{
+        self, glib,
+        prelude::{
+            BoxExt, ButtonExt, GtkWindowExt, OrientableExt, TextBufferExt, TextViewExt, WidgetExt,
+        },
+    },
+    ComponentParts, ComponentSender, RelmWidgetExt, SimpleComponent,
+};
+use snafu::ResultExt;
+
+// endregion: IMPORTS
@@ -0,0 +1,336 @@
+pub struct WindowInit {
+    pub database_filepath: PathBuf,
+    pub config: Config,
+    pub log_filepath: PathBuf,
+}
+
+// The main window. Detected devices are grouped into one page per kind of
+// hardware, with a page for devices whose kind is not known, followed by the
+// settings and the logs.
+pub struct Window {
+    database_filepath: PathBuf,
+    hwdata_dirpath: PathBuf,
+    root: PathBuf,
+    driver_filter: actions::DriverFilter,
+    backend_settings: BackendSettings,
+    devices_drivers: Vec<DeviceDrivers>,
+    device_lists: Vec<(Option<HardwareKind>, FactoryVecDeque<DeviceRow>)>,
+    transaction_dialog: Controller<TransactionDialog>,
+    // Only kept so that the pages live as long as the window
+    _settings_page: Controller<SettingsPage>,
+    _log_page: Controller<LogPage>,
+    selected_drivers: BTreeMap<String, String>,
+    status: String,
+}
+
+#[derive(Debug)]
+pub enum WindowInput {
+    Refresh,
+    DriverSelected { slot: String, driver_id: String },
+    InstallRequested { slot: String, driver_id: String },
+    RemoveRequested { slot: String },
+    SettingsSaved(Config),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for Window {
+    type Init = WindowInit;
+    type Input = WindowInput;
+    type Output = ();
+
+    view! {
+        gtk::ApplicationWindow {
+            set_title: Some(*app::APP_NAME),
+            set_default_size: (720, 540),
+
+            #[wrap(Some)]
+            set_titlebar = &gtk::HeaderBar {
+                pack_start = &gtk::Button {
+                    set_icon_name: "view-refresh-symbolic",
+                    set_tooltip_text: Some("Detect the hardware again"),
+                    connect_clicked => WindowInput::Refresh,
+                },
+
+                #[wrap(Some)]
+                set_title_widget = &gtk::StackSwitcher {
+                    set_stack: Some(&stack),
+                },
+            },
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+
+                #[local_ref]
+                stack -> gtk::Stack {
+                    set_vexpand: true,
+                },
+
+                gtk::Label {
+                    set_halign: gtk::Align::Start,
+                    set_margin_all: 8,
+                    set_wrap: true,
+                    #[watch]
+                    set_label: &model.status,
+                },
+            },
+        }
+    }
+
+    fn init(
+        window_init: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let stack = gtk::Stack::new();
+        let device_lists = HARDWARE_KINDS
+            .iter()
+            .copied()
+            .map(Some)
+            .chain(iter::once(None))
+            .map(|hardware_kind| {
+                let device_list = FactoryVecDeque::new(
+                    gtk::ListBox::builder()
+                        .selection_mode(gtk::SelectionMode::None)
+                        .build(),
+                    sender.input_sender(),
+                );
+                let scrolled_window = gtk::ScrolledWindow::builder()
+                    .child(device_list.widget())
+                    .build();
+                let title = hardware_kind
+                    .map(|hardware_kind| hardware_kind.to_string())
+                    .unwrap_or_else(|| String::from("Other"));
+                stack.add_titled(&scrolled_window, Some(&title), &title);
+                (hardware_kind, device_list)
+            })
+            .collect();
+
+        let transaction_dialog = TransactionDialog::builder()
+            .transient_for(root)
+            .launch(())
+            .forward(sender.input_sender(), |output| match output {
+                // Show what the transaction changed
+                TransactionDialogOutput::Finished => WindowInput::Refresh,
+            });
+
+        let settings_page = SettingsPage::builder()
+            .launch(
+                window_init
+                    .config
+                    .clone(),
+            )
+            .forward(sender.input_sender(), |output| match output {
+                SettingsPageOutput::Saved(config) => WindowInput::SettingsSaved(config),
+            });
+        stack.add_titled(settings_page.widget(), Some("Settings"), "Settings");
+
+        let log_page = LogPage::builder()
+            .launch(
+                window_init
+                    .log_filepath
+                    .clone(),
+            )
+            .detach();
+        stack.add_titled(log_page.widget(), Some("Logs"), "Logs");
+
+        let mut model = Window {
+            database_filepath: window_init.database_filepath,
+            hwdata_dirpath: data::configured_hwdata_dirpath(&window_init.config),
+            root: system::configured_root(&window_init.config),
+            driver_filter: driver_filter(&window_init.config),
+            backend_settings: BackendSettings::from_config(&window_init.config),
+            devices_drivers: vec![],
+            device_lists,
+            transaction_dialog,
+            _settings_page: settings_page,
+            _log_page: log_page,
+            selected_drivers: BTreeMap::new(),
+            status: String::new(),
+        };
+        model.refresh();
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, input: Self::Input, _sender: ComponentSender<Self>) {
+        match input {
+            WindowInput::Refresh => self.refresh(),
+            WindowInput::DriverSelected { slot, driver_id } => {
+                tracing::debug!("Selected the driver {} for {}", driver_id, slot);
+                self.selected_drivers
+                    .insert(slot, driver_id);
+            }
+            WindowInput::InstallRequested { slot, driver_id } => self
+                .confirm_transaction(&slot, |device_drivers| {
+                    actions::plan_install(device_drivers, &driver_id)
+                }),
+            WindowInput::RemoveRequested { slot } => {
+                self.confirm_transaction(&slot, actions::plan_remove)
+            }
+            WindowInput::SettingsSaved(config) => {
+                self.driver_filter = driver_filter(&config);
+                self.hwdata_dirpath = data::configured_hwdata_dirpath(&config);
+                self.root = system::configured_root(&config);
+                self.backend_settings = BackendSettings::from_config(&config);
+                self.refresh();
+            }
+        }
+    }
+}
+
+impl Window {
+    // Search for drivers with the same action as the CLI, and regroup the
+    // devices into their pages
+    fn refresh(&mut self) {
+        for (_, device_list) in &mut self.device_lists {
+            device_list
+                .guard()
+                .clear();
+        }
+        self.selected_drivers
+            .clear();
+        self.devices_drivers
+            .clear();
+
+        let devices_drivers = match actions::search_system(
+            &self.database_filepath,
+            &self.hwdata_dirpath,
+            &self.root,
+            &self.driver_filter,
+            &actions::NoProgress,
+            &actions::CancellationToken::new(),
+        ) {
+            Ok(devices_drivers) => devices_drivers,
+            Err(error) => {
+                tracing::error!("Could not search for drivers: {}", error);
+                self.status = format!("Could not search for drivers: {error}");
+                return;
+            }
+        };
+
+        for device_drivers in &devices_drivers {
+            if let Some(choice) = &device_drivers.recommended {
+                self.selected_drivers
+                    .insert(
+                        device_drivers
+                            .device
+                            .slot
+                            .clone(),
+                        choice.id.clone(),
+                    );
+            }
+        }
+        self.status = match devices_drivers.len() {
+            0 => String::from("No drivers were found for the detected hardware."),
+            count => format!("Found drivers for {count} device(s)."),
+        };
+
+        self.devices_drivers = devices_drivers.clone();
+        for device_drivers in devices_drivers {
+            let hardware_kind = device_drivers
+                .hardware_kinds
+                .iter()
+                .next()
+                .copied();
+            if let Some((_, device_list)) = self
+                .device_lists
+                .iter_mut()
+                .find(|(kind, _)| *kind == hardware_kind)
+            {
+                device_list
+                    .guard()
+                    .push_back(device_drivers);
+            }
+        }
+    }
+}
+
+impl Window {
+    // Plan the transaction for the device and hand it over to the transaction
+    // dialog for confirmation
+    fn confirm_transaction<F>(&mut self, slot: &str, plan: F)
+    where
+        F: Fn(&DeviceDrivers) -> Result<TransactionPlan, actions::install::Error>,
+    {
+        let Some(device_drivers) = self
+            .devices_drivers
+            .iter()
+            .find(|device_drivers| {
+                device_drivers
+                    .device
+                    .slot
+                    == slot
+            })
+        else {
+            return;
+        };
+        match plan(device_drivers) {
+            Ok(plan) if plan.is_empty() => {
+                self.status = format!("The driver {} is already installed.", plan.driver_id);
+            }
+            Ok(plan) => self
+                .transaction_dialog
+                .emit(TransactionDialogInput::Show(
+                    Box::new(plan),
+                    self.backend_settings
+                        .clone(),
+                    self.root.clone(),
+                )),
+            Err(error) => {
+                tracing::error!("Could not plan the transaction: {}", error);
+                self.status = format!("Could not plan the transaction: {error}");
+            }
+        }
+    }
+}
+
+fn driver_filter(config: &Config) -> actions::DriverFilter {
+    actions::DriverFilter {
+        preferred_tags: config
+            .preferred_tags
+            .clone()
+            .unwrap_or_default(),
+        excluded_tags: config
+            .excluded_tags
+            .clone()
+            .unwrap_or_default(),
+        ..Default::default()
+    }
+}
+
+const HARDWARE_KINDS: [HardwareKind; 4] = [
+    HardwareKind::Graphics,
+    HardwareKind::Ethernet,
+    HardwareKind::Wireless,
+    HardwareKind::Audio,
+];
+
+// region: IMPORTS
+
+use std::{collections::BTreeMap, iter, path::PathBuf};
+
+use aldm::{
+    actions::{self, DeviceDrivers, TransactionPlan},
+    app::{self, Config},
+    backend::BackendSettings,
+    data::{self, HardwareKind},
+    system,
+};
+use relm4::{
+    factory::FactoryVecDeque,
+    gtk::{
+        self,
+        prelude::{ButtonExt, GtkWindowExt, OrientableExt, WidgetExt},
+    },
+    Component, ComponentController, ComponentParts, ComponentSender, Controller, RelmWidgetExt,
+    SimpleComponent,
+};
+
+use crate::{
+    DeviceRow, LogPage, SettingsPage, SettingsPageOutput, TransactionDialog,
+    TransactionDialogInput, TransactionDialogOutput,
+};
+
+// endregion: IMPORTS
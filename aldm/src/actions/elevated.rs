@@ -0,0 +1,503 @@
+// Without root, aldm can neither change packages nor write its files, records
+// and journals. A transaction or a rollback is then handed over to the
+// elevated helper, which is `aldm-cli` run as root through pkexec, so that
+// everything it changes is changed as root behind a single authentication. The
+// helper reads the request as a JSON line on stdin and reports back with one
+// JSON line per message on stdout, while `cancel` lines on stdin cancel the
+// transaction. The request comes from an unprivileged process, so the helper
+// does not take its plan as it is, but plans the transaction again from the
+// installed database.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub enum ElevatedRequest {
+    Transaction {
+        plan: Box<TransactionPlan>,
+        backend_settings: BackendSettings,
+        root: PathBuf,
+    },
+    Rollback {
+        id: String,
+        backend_settings: BackendSettings,
+        root: PathBuf,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "message", rename_all = "snake_case")]
+enum ElevatedMessage {
+    Progress {
+        event: ProgressEvent,
+    },
+    Finished {
+        result: Result<TransactionOutcome, String>,
+    },
+}
+
+// Whether changing the system has to go through the elevated helper
+pub fn is_elevation_required() -> bool {
+    !Uid::effective().is_root()
+}
+
+// Execute the transaction in the elevated helper, with the same progress and
+// cancellation as `execute_transaction`
+pub fn execute_transaction_elevated(
+    plan: &TransactionPlan,
+    backend_settings: &BackendSettings,
+    root: &Path,
+    progress: &dyn ProgressSink,
+    cancellation_token: &CancellationToken,
+) -> Result<TransactionOutcome, install::Error> {
+    execute_elevated(
+        &ElevatedRequest::Transaction {
+            plan: Box::new(plan.clone()),
+            backend_settings: backend_settings.clone(),
+            root: root.to_owned(),
+        },
+        progress,
+        cancellation_token,
+    )
+    .context(install::ElevatedTransactionSnafu {})
+}
+
+// Roll back the transaction of the journal in the elevated helper, like
+// `execute_rollback`
+pub fn execute_rollback_elevated(
+    journal: &Journal,
+    backend_settings: &BackendSettings,
+    root: &Path,
+    progress: &dyn ProgressSink,
+) -> Result<TransactionOutcome, rollback::Error> {
+    execute_elevated(
+        &ElevatedRequest::Rollback {
+            id: journal.id.clone(),
+            backend_settings: backend_settings.clone(),
+            root: root.to_owned(),
+        },
+        progress,
+        &CancellationToken::new(),
+    )
+    .context(rollback::ElevatedRollbackSnafu {})
+}
+
+fn execute_elevated(
+    request: &ElevatedRequest,
+    progress: &dyn ProgressSink,
+    cancellation_token: &CancellationToken,
+) -> Result<TransactionOutcome, Error> {
+    let helper_filepath = helper_filepath().context(HelperNotFoundSnafu {})?;
+    tracing::info!(
+        "Making the changes as root through {} {}",
+        helper_filepath.display(),
+        HELPER_COMMAND
+    );
+    let mut child = Command::new("pkexec")
+        .arg(&helper_filepath)
+        .arg(HELPER_COMMAND)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .context(SpawnHelperSnafu {})?;
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| io::Error::from(io::ErrorKind::BrokenPipe))
+        .context(CommunicateWithHelperSnafu {})?;
+    let request = serde_json::to_string(request).context(SerializeMessageSnafu {})?;
+    writeln!(stdin, "{request}")
+        .and_then(|_| stdin.flush())
+        .context(CommunicateWithHelperSnafu {})?;
+
+    // Read stdout on its own thread, so that the cancellation token can still
+    // be checked while the helper is quiet
+    let (line_sender, line_receiver) = mpsc::channel();
+    let reader = child
+        .stdout
+        .take()
+        .map(|stdout| {
+            thread::spawn(move || {
+                for line in BufReader::new(stdout)
+                    .lines()
+                    .map_while(Result::ok)
+                {
+                    if line_sender
+                        .send(line)
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            })
+        });
+
+    let mut result = None;
+    let mut is_cancel_sent = false;
+    loop {
+        match line_receiver.recv_timeout(Duration::from_millis(100)) {
+            // Anything else that the helper prints is not meant for us
+            Ok(line) => match serde_json::from_str(&line) {
+                Ok(ElevatedMessage::Progress { event }) => progress.report(event),
+                Ok(ElevatedMessage::Finished {
+                    result: helper_result,
+                }) => result = Some(helper_result),
+                Err(_) => tracing::debug!("The elevated helper printed: {}", line),
+            },
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if !is_cancel_sent && cancellation_token.is_cancelled() {
+            is_cancel_sent = true;
+            if let Err(error) = writeln!(stdin, "{CANCEL_LINE}").and_then(|_| stdin.flush()) {
+                tracing::warn!(
+                    "Could not pass the cancellation on to the helper: {}",
+                    error
+                );
+            }
+        }
+    }
+    if let Some(reader) = reader {
+        reader.join().ok();
+    }
+    drop(stdin);
+
+    let exit_status = child
+        .wait()
+        .context(WaitHelperSnafu {})?;
+    match (result, exit_status.code()) {
+        (Some(Ok(outcome)), _) => Ok(outcome),
+        (Some(Err(message)), _) => ElevatedActionSnafu { message }.fail(),
+        (None, Some(PKEXEC_DISMISSED_CODE)) => AuthenticationDismissedSnafu {}.fail(),
+        (None, Some(PKEXEC_NOT_AUTHORIZED_CODE)) => NotAuthorizedSnafu {}.fail(),
+        (None, _) => HelperFailedSnafu { exit_status }.fail(),
+    }
+}
+
+// Serve a request of `execute_elevated`, as the elevated helper. The request
+// is read from stdin, and the progress and the result are written to stdout.
+// A failure of the transaction itself is reported as its result.
+pub fn serve_elevated_request() -> Result<(), Error> {
+    ensure!(!is_elevation_required(), HelperNotRootSnafu {});
+    let mut stdin = BufReader::new(io::stdin());
+    let mut request = String::new();
+    stdin
+        .read_line(&mut request)
+        .context(ReadRequestSnafu {})?;
+    let request: ElevatedRequest = serde_json::from_str(&request).context(ParseRequestSnafu {})?;
+
+    // The caller going away, which closes stdin, cancels as well
+    let cancellation_token = CancellationToken::new();
+    let cancellation_sender = cancellation_token.clone();
+    thread::spawn(move || {
+        for line in stdin
+            .lines()
+            .map_while(Result::ok)
+        {
+            if line.trim() == CANCEL_LINE {
+                break;
+            }
+        }
+        cancellation_sender.cancel();
+    });
+
+    let progress = HelperProgress;
+    let result = match &request {
+        ElevatedRequest::Transaction {
+            plan,
+            backend_settings,
+            root,
+        } => check_request(backend_settings, root)
+            .and_then(|()| replan(plan, root))
+            .map_err(|error| error.to_string())
+            .and_then(|plan| {
+                backend_settings
+                    .backend()
+                    .context(install::BackendSnafu {})
+                    .and_then(|backend| {
+                        install::execute_transaction(
+                            &plan,
+                            backend.as_ref(),
+                            &SystemCommandRunner::new(root),
+                            root,
+                            &progress,
+                            &cancellation_token,
+                        )
+                    })
+                    .map_err(|error| error.to_string())
+            }),
+        ElevatedRequest::Rollback {
+            id,
+            backend_settings,
+            root,
+        } => check_request(backend_settings, root)
+            .map_err(|error| error.to_string())
+            .and_then(|()| {
+                rollback::plan_rollback(root, Some(id)).map_err(|error| error.to_string())
+            })
+            .and_then(|journal| {
+                backend_settings
+                    .backend()
+                    .context(rollback::PackageBackendSnafu {})
+                    .and_then(|backend| {
+                        rollback::execute_rollback(
+                            &journal,
+                            backend.as_ref(),
+                            &SystemCommandRunner::new(root),
+                            root,
+                            &progress,
+                        )
+                    })
+                    .map_err(|error| error.to_string())
+            }),
+    };
+    if let Err(error) = &result {
+        tracing::error!("The elevated transaction failed: {}", error);
+    }
+    write_message(&ElevatedMessage::Finished { result })
+}
+
+// Only the system itself is changed, with the package manager that the
+// configuration may name, so that the caller cannot make the helper run other
+// programs or write below other directories
+fn check_request(backend_settings: &BackendSettings, root: &Path) -> Result<(), Error> {
+    ensure!(root == probe::default_root(), UnexpectedRootSnafu { root });
+    if let Some(aur_helper) = &backend_settings.aur_helper {
+        let program = aur_helper
+            .split_whitespace()
+            .next()
+            .unwrap_or_default();
+        ensure!(
+            backend::KNOWN_AUR_HELPERS.contains(&program),
+            UnknownAurHelperSnafu { aur_helper }
+        );
+    }
+    Ok(())
+}
+
+// Plan the transaction again from the installed database and the system as it
+// is, and accept it only if the caller asked for the same
+fn replan(plan: &TransactionPlan, root: &Path) -> Result<TransactionPlan, Error> {
+    let all_device_drivers = search::search_system(
+        &data::default_database_filepath(),
+        &data::default_hwdata_dirpath(),
+        root,
+        &DriverFilter::default(),
+        &NoProgress,
+        &CancellationToken::new(),
+    )
+    .context(ReplanSearchSnafu {})?;
+    let device_drivers = all_device_drivers
+        .iter()
+        .find(|device_drivers| {
+            device_drivers
+                .device
+                .slot
+                == plan.slot
+        })
+        .context(UnknownDeviceSnafu {
+            slot: plan.slot.clone(),
+        })?;
+    let mut rebuilt_plan = match plan.kind {
+        TransactionKind::Install => install::plan_install(device_drivers, &plan.driver_id),
+        TransactionKind::Remove => install::plan_remove(device_drivers),
+    }
+    .map_err(Box::new)
+    .context(ReplanSnafu {})?;
+    // The name of the device is only shown, and depends on the hardware
+    // database that the caller was configured with
+    rebuilt_plan.device = plan.device.clone();
+    ensure!(rebuilt_plan == *plan, PlanChangedSnafu {});
+    Ok(rebuilt_plan)
+}
+
+// Passes the progress of the helper on to the caller
+struct HelperProgress;
+
+impl ProgressSink for HelperProgress {
+    fn report(&self, event: ProgressEvent) {
+        if let Err(error) = write_message(&ElevatedMessage::Progress { event }) {
+            tracing::warn!("Could not report the progress: {}", error);
+        }
+    }
+}
+
+fn write_message(message: &ElevatedMessage) -> Result<(), Error> {
+    let message = serde_json::to_string(message).context(SerializeMessageSnafu {})?;
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{message}")
+        .and_then(|_| stdout.flush())
+        .context(WriteMessageSnafu {})
+}
+
+// The helper is the CLI, which is installed next to the GUI. In a build
+// directory, it is next to the current executable as well.
+fn helper_filepath() -> Option<PathBuf> {
+    env::current_exe()
+        .ok()
+        .and_then(|exe_filepath| {
+            exe_filepath
+                .parent()
+                .map(|dirpath| dirpath.join(HELPER_NAME))
+        })
+        .filter(|helper_filepath| helper_filepath.is_file())
+        .or_else(|| {
+            env::split_paths(&env::var_os("PATH")?)
+                .map(|dirpath| dirpath.join(HELPER_NAME))
+                .find(|helper_filepath| helper_filepath.is_file())
+        })
+}
+
+const HELPER_NAME: &str = "aldm-cli";
+pub const HELPER_COMMAND: &str = "elevated-helper";
+const CANCEL_LINE: &str = "cancel";
+
+// The exit codes of pkexec when the authentication dialog was dismissed, and
+// when the user could not be authorized
+const PKEXEC_DISMISSED_CODE: i32 = 126;
+const PKEXEC_NOT_AUTHORIZED_CODE: i32 = 127;
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(
+        display(
+            "{HELPER_NAME} was not found to make the changes as root; run aldm as root instead"
+        ),
+        visibility(pub)
+    )]
+    HelperNotFound {},
+
+    #[non_exhaustive]
+    #[snafu(display("could not run pkexec: {source}"), visibility(pub))]
+    SpawnHelper { source: std::io::Error },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not send the request to the elevated helper: {source}"),
+        visibility(pub)
+    )]
+    CommunicateWithHelper { source: std::io::Error },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not wait for the elevated helper: {source}"),
+        visibility(pub)
+    )]
+    WaitHelper { source: std::io::Error },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the authentication was dismissed. Nothing was changed."),
+        visibility(pub)
+    )]
+    AuthenticationDismissed {},
+
+    #[non_exhaustive]
+    #[snafu(
+        display("not authorized to make the changes as root. Nothing was changed."),
+        visibility(pub)
+    )]
+    NotAuthorized {},
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the elevated helper stopped without a result ({exit_status})"),
+        visibility(pub)
+    )]
+    HelperFailed { exit_status: ExitStatus },
+
+    #[non_exhaustive]
+    #[snafu(display("{message}"), visibility(pub))]
+    ElevatedAction { message: String },
+
+    #[non_exhaustive]
+    #[snafu(display("the elevated helper has to run as root"), visibility(pub))]
+    HelperNotRoot {},
+
+    #[non_exhaustive]
+    #[snafu(
+        display(
+            "the elevated helper only changes the system itself, not {}; run aldm as root to \
+             change another root",
+            root.display()
+        ),
+        visibility(pub)
+    )]
+    UnexpectedRoot { root: PathBuf },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("{aur_helper} is not a known AUR helper; use paru or yay"),
+        visibility(pub)
+    )]
+    UnknownAurHelper { aur_helper: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not plan the transaction again: {source}"),
+        visibility(pub)
+    )]
+    ReplanSearch { source: search::Error },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not plan the transaction again: {source}"),
+        visibility(pub)
+    )]
+    Replan { source: Box<install::Error> },
+
+    #[non_exhaustive]
+    #[snafu(display("the device {slot} was not found"), visibility(pub))]
+    UnknownDevice { slot: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the transaction differs from the one that the installed database plans"),
+        visibility(pub)
+    )]
+    PlanChanged {},
+
+    #[non_exhaustive]
+    #[snafu(display("could not read the request: {source}"), visibility(pub))]
+    ReadRequest { source: std::io::Error },
+
+    #[non_exhaustive]
+    #[snafu(display("could not parse the request: {source}"), visibility(pub))]
+    ParseRequest { source: serde_json::Error },
+
+    #[non_exhaustive]
+    #[snafu(display("could not serialize a message: {source}"), visibility(pub))]
+    SerializeMessage { source: serde_json::Error },
+
+    #[non_exhaustive]
+    #[snafu(display("could not write a message: {source}"), visibility(pub))]
+    WriteMessage { source: std::io::Error },
+}
+
+// region: IMPORTS
+
+use std::{
+    env,
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Stdio},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+use nix::unistd::Uid;
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+
+use crate::{
+    actions::{
+        install, rollback, search, CancellationToken, DriverFilter, NoProgress, ProgressEvent,
+        ProgressSink, TransactionKind, TransactionOutcome, TransactionPlan,
+    },
+    backend::{self, BackendSettings},
+    data, probe,
+    system::{Journal, SystemCommandRunner},
+};
+
+// endregion: IMPORTS
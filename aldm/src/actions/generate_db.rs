@@ -1,13 +1,42 @@
+pub fn generate_db(input_filepath: &Path, database_filepath: &Path) -> Result<Database, Error> {
+    let input_file = InputFile::read_from_file(input_filepath).context(InputFileSnafu {})?;
+    let database = input_file
+        .compile()
+        .context(InputFileSnafu {})?;
+    database
+        .write_to_file(database_filepath)
+        .context(DatabaseSnafu {})?;
+    tracing::debug!(
+        "Compiled {} driver option(s) and {} named condition(s) into {:?}",
+        database
+            .driver_options
+            .len(),
+        database
+            .named_conditions
+            .len(),
+        database_filepath
+    );
+    Ok(database)
+}
+
 #[derive(Debug, Snafu)]
 #[non_exhaustive]
 pub enum Error {
     #[non_exhaustive]
-    #[snafu(display(""))]
-    Dummy {},
+    #[snafu(display("{source}"), visibility(pub))]
+    InputFile { source: input_file::Error },
+
+    #[non_exhaustive]
+    #[snafu(display("{source}"), visibility(pub))]
+    Database { source: database::Error },
 }
 
 // region: IMPORTS
 
-use snafu::Snafu;
+use std::path::Path;
+
+use snafu::{ResultExt, Snafu};
+
+use crate::data::{database, input_file, Database, InputFile};
 
 // endregion: IMPORTS
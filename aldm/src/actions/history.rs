@@ -0,0 +1,153 @@
+// A past transaction as the history shows it, with what became of it
+#[derive(Clone, Debug, Serialize)]
+pub struct HistoryEntry {
+    #[serde(flatten)]
+    pub journal: Journal,
+    pub timestamp: String,
+    pub status: TransactionStatus,
+    pub reboot_status: RebootStatus,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStatus {
+    Succeeded,
+    Cancelled,
+    Failed,
+    // Undone, either right away when it failed or later by a rollback
+    RolledBack,
+    // Stopped without finishing, such as by a crash or a power cut
+    Interrupted,
+}
+
+impl fmt::Display for TransactionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TransactionStatus::Succeeded => write!(f, "succeeded"),
+            TransactionStatus::Cancelled => write!(f, "cancelled"),
+            TransactionStatus::Failed => write!(f, "failed"),
+            TransactionStatus::RolledBack => write!(f, "rolled back"),
+            TransactionStatus::Interrupted => write!(f, "interrupted"),
+        }
+    }
+}
+
+// Whether the transaction needed a reboot to take effect, and if so whether
+// the system was booted since
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RebootStatus {
+    NotRequired,
+    Pending,
+    Rebooted,
+}
+
+impl fmt::Display for RebootStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RebootStatus::NotRequired => write!(f, "no reboot required"),
+            RebootStatus::Pending => write!(f, "reboot pending"),
+            RebootStatus::Rebooted => write!(f, "rebooted since"),
+        }
+    }
+}
+
+// The transactions that aldm made on the system, the latest first, read from
+// their journals
+pub fn history(root: &Path) -> Result<Vec<HistoryEntry>, Error> {
+    let boot_time = boot_time(root);
+    let mut entries = system::read_journals(root)
+        .context(HistoryJournalSnafu {})?
+        .into_iter()
+        .map(|journal| {
+            let status = transaction_status(&journal);
+            let is_changed = match status {
+                TransactionStatus::Succeeded => true,
+                TransactionStatus::Failed | TransactionStatus::Interrupted => !journal
+                    .changes
+                    .is_empty(),
+                TransactionStatus::Cancelled | TransactionStatus::RolledBack => false,
+            };
+            let reboot_status = match (journal.is_reboot_required && is_changed, boot_time) {
+                (false, _) => RebootStatus::NotRequired,
+                (true, Some(boot_time)) if boot_time > journal.started_at => RebootStatus::Rebooted,
+                (true, _) => RebootStatus::Pending,
+            };
+            HistoryEntry {
+                timestamp: format_timestamp(journal.started_at),
+                journal,
+                status,
+                reboot_status,
+            }
+        })
+        .collect::<Vec<_>>();
+    entries.reverse();
+    Ok(entries)
+}
+
+fn transaction_status(journal: &Journal) -> TransactionStatus {
+    if journal.is_rolled_back() {
+        return TransactionStatus::RolledBack;
+    }
+    match journal.result {
+        Some(JournalResult::Succeeded | JournalResult::RebootRequired) => {
+            TransactionStatus::Succeeded
+        }
+        Some(JournalResult::Cancelled) => TransactionStatus::Cancelled,
+        Some(JournalResult::Failed { .. }) => TransactionStatus::Failed,
+        None => TransactionStatus::Interrupted,
+    }
+}
+
+// The time the system was booted, in seconds since the Unix epoch, from the
+// `btime` line of /proc/stat
+fn boot_time(root: &Path) -> Option<i64> {
+    let stat = fs::read_to_string(system::path_below_root(root, Path::new("/proc/stat"))).ok()?;
+    stat.lines()
+        .find_map(|line| line.strip_prefix("btime "))
+        .and_then(|boot_time| {
+            boot_time
+                .trim()
+                .parse()
+                .ok()
+        })
+}
+
+// Like `2024-03-01 12:00:00 UTC`
+fn format_timestamp(unix_timestamp: i64) -> String {
+    match OffsetDateTime::from_unix_timestamp(unix_timestamp) {
+        Ok(time) => format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+            time.year(),
+            u8::from(time.month()),
+            time.day(),
+            time.hour(),
+            time.minute(),
+            time.second()
+        ),
+        Err(_) => unix_timestamp.to_string(),
+    }
+}
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(display("in reading the journals: {source}"), visibility(pub))]
+    HistoryJournal {
+        #[snafu(backtrace)]
+        source: system::journal::Error,
+    },
+}
+
+// region: IMPORTS
+
+use std::{fmt, fs, path::Path};
+
+use serde::Serialize;
+use snafu::{ResultExt, Snafu};
+use time::OffsetDateTime;
+
+use crate::system::{self, Journal, JournalResult};
+
+// endregion: IMPORTS
@@ -1,13 +1,1052 @@
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransactionKind {
+    Install,
+    Remove,
+}
+
+// What a transaction is going to do, computed before anything is changed so
+// that it can be shown for confirmation.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionPlan {
+    pub kind: TransactionKind,
+    pub slot: String,
+    // The device as shown to the user, with its name
+    pub device: String,
+    pub driver_id: String,
+    pub packages_to_remove: Vec<String>,
+    pub packages_to_install: Vec<String>,
+    // The packages to install that come from the AUR
+    pub aur_packages: Vec<String>,
+    // The modprobe.d file of the new driver, and those of the drivers that are
+    // removed
+    pub modprobe_file: Option<ModprobeFile>,
+    pub modprobe_files_to_remove: Vec<PathBuf>,
+    // The same for the mkinitcpio drop-ins that load the modules early
+    pub mkinitcpio_file: Option<MkinitcpioFile>,
+    pub mkinitcpio_files_to_remove: Vec<PathBuf>,
+    // Whether the initramfs is rebuilt, which it has to be for any of the
+    // files to take effect at boot. The modconf hook of mkinitcpio copies the
+    // modprobe.d files into the initramfs.
+    pub is_initramfs_regenerated: bool,
+    // The kernel parameters of the new driver, and those of the drivers that
+    // are removed. Only the parameters that aldm added are removed.
+    pub kernel_parameters_to_add: Vec<String>,
+    pub kernel_parameters_to_remove: Vec<String>,
+    // The same for the files and the systemd units of the drivers
+    pub files_to_write: Vec<DriverFile>,
+    pub files_to_remove: Vec<String>,
+    pub services_to_enable: Vec<String>,
+    pub services_to_disable: Vec<String>,
+    pub is_reboot_required: bool,
+}
+
+impl TransactionPlan {
+    // Only the packages count, since the files go along with them
+    pub fn is_empty(&self) -> bool {
+        self.packages_to_remove
+            .is_empty()
+            && self
+                .packages_to_install
+                .is_empty()
+    }
+
+    // The modules that the plan blacklists but the `kms` hook of mkinitcpio
+    // adds to the initramfs anyway, so that the hook has to be removed
+    pub fn kms_hook_conflicts(&self, root: &Path) -> Result<Vec<String>, system::Error> {
+        match &self.modprobe_file {
+            Some(modprobe_file) => system::kms_hook_conflicts(root, &modprobe_file.blacklist)
+                .context(system::MkinitcpioSnafu {}),
+            None => Ok(vec![]),
+        }
+    }
+
+    // The packages that the plan removes or installs
+    fn packages(&self) -> impl Iterator<Item = &String> {
+        self.packages_to_remove
+            .iter()
+            .chain(&self.packages_to_install)
+    }
+
+    fn changes_system(&self) -> bool {
+        self.changes_initramfs_files()
+            || !self
+                .kernel_parameters_to_add
+                .is_empty()
+            || !self
+                .kernel_parameters_to_remove
+                .is_empty()
+            || !self
+                .files_to_write
+                .is_empty()
+            || !self
+                .files_to_remove
+                .is_empty()
+            || !self
+                .services_to_enable
+                .is_empty()
+            || !self
+                .services_to_disable
+                .is_empty()
+    }
+
+    fn changes_initramfs_files(&self) -> bool {
+        self.modprobe_file
+            .is_some()
+            || !self
+                .modprobe_files_to_remove
+                .is_empty()
+            || self
+                .mkinitcpio_file
+                .is_some()
+            || !self
+                .mkinitcpio_files_to_remove
+                .is_empty()
+    }
+}
+
+impl fmt::Display for TransactionPlan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |packages: &Vec<String>| match packages.is_empty() {
+            true => String::from("none"),
+            false => packages.join(", "),
+        };
+        write!(
+            f,
+            "Driver: {}\nDevice: {}\nPackages to remove: {}\nPackages to install: {}",
+            self.driver_id,
+            self.slot,
+            join(&self.packages_to_remove),
+            join(&self.packages_to_install)
+        )?;
+        if !self
+            .aur_packages
+            .is_empty()
+        {
+            write!(f, "\nFrom the AUR: {}", join(&self.aur_packages))?;
+        }
+        if let Some(modprobe_file) = &self.modprobe_file {
+            write!(
+                f,
+                "\nFile to write: {}",
+                modprobe_file
+                    .path
+                    .display()
+            )?;
+        }
+        if let Some(mkinitcpio_file) = &self.mkinitcpio_file {
+            write!(
+                f,
+                "\nFile to write: {}",
+                mkinitcpio_file
+                    .path
+                    .display()
+            )?;
+        }
+        for driver_file in &self.files_to_write {
+            write!(f, "\nFile to write: {}", driver_file.path)?;
+        }
+        for path in self
+            .modprobe_files_to_remove
+            .iter()
+            .chain(&self.mkinitcpio_files_to_remove)
+        {
+            write!(f, "\nFile to remove: {}", path.display())?;
+        }
+        for path in &self.files_to_remove {
+            write!(f, "\nFile to remove: {path}")?;
+        }
+        if !self
+            .services_to_enable
+            .is_empty()
+        {
+            write!(
+                f,
+                "\nServices to enable: {}",
+                self.services_to_enable
+                    .join(", ")
+            )?;
+        }
+        if !self
+            .services_to_disable
+            .is_empty()
+        {
+            write!(
+                f,
+                "\nServices to disable: {}",
+                self.services_to_disable
+                    .join(", ")
+            )?;
+        }
+        if !self
+            .kernel_parameters_to_add
+            .is_empty()
+        {
+            write!(
+                f,
+                "\nKernel parameters to add: {}",
+                self.kernel_parameters_to_add
+                    .join(" ")
+            )?;
+        }
+        if !self
+            .kernel_parameters_to_remove
+            .is_empty()
+        {
+            write!(
+                f,
+                "\nKernel parameters to remove: {}",
+                self.kernel_parameters_to_remove
+                    .join(" ")
+            )?;
+        }
+        if self.is_initramfs_regenerated {
+            write!(f, "\nThe initramfs will be regenerated.")?;
+        }
+        if self.is_reboot_required {
+            write!(f, "\nA reboot will be required.")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransactionOutcome {
+    Succeeded,
+    RebootRequired,
+    Cancelled,
+}
+
+// Plan switching the device to the given driver. The packages of any other
+// installed driver for the device are removed first, since they usually
+// conflict with the new driver.
+pub fn plan_install(
+    device_drivers: &DeviceDrivers,
+    driver_id: &str,
+) -> Result<TransactionPlan, Error> {
+    let choice = device_drivers
+        .choices()
+        .find(|choice| choice.id == driver_id)
+        .context(UnknownDriverSnafu {
+            driver_id,
+            slot: device_drivers
+                .device
+                .slot
+                .clone(),
+        })?;
+
+    let packages_to_remove = device_drivers
+        .installed
+        .iter()
+        .filter(|installed_choice| installed_choice.id != choice.id)
+        .flat_map(|installed_choice| {
+            installed_choice
+                .packages
+                .iter()
+        })
+        .filter(|package| {
+            !choice
+                .packages
+                .contains(package)
+        })
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let packages_to_install: Vec<String> = match choice.is_installed {
+        true => vec![],
+        false => choice
+            .packages
+            .clone(),
+    };
+    let aur_packages = packages_to_install
+        .iter()
+        .filter(|package| {
+            choice
+                .aur_packages
+                .contains(package)
+        })
+        .cloned()
+        .collect();
+    let modprobe_file = match choice
+        .modprobe
+        .is_empty()
+    {
+        true => None,
+        false => Some(ModprobeFile::new(&choice.id, &choice.modprobe)),
+    };
+    let modprobe_files_to_remove = modprobe_filepaths(
+        device_drivers
+            .installed
+            .iter()
+            .filter(|installed_choice| installed_choice.id != choice.id),
+    );
+    let mkinitcpio_file = match choice
+        .early_kms_modules
+        .is_empty()
+    {
+        true => None,
+        false => Some(MkinitcpioFile::new(&choice.id, &choice.early_kms_modules)),
+    };
+    let mkinitcpio_files_to_remove = mkinitcpio_filepaths(
+        device_drivers
+            .installed
+            .iter()
+            .filter(|installed_choice| installed_choice.id != choice.id),
+    );
+    let kernel_parameters_to_remove = kernel_parameters(
+        device_drivers
+            .installed
+            .iter()
+            .filter(|installed_choice| installed_choice.id != choice.id),
+    )
+    .into_iter()
+    .filter(|parameter| {
+        !choice
+            .kernel_parameters
+            .contains(parameter)
+    })
+    .collect();
+    let files_to_remove = file_paths(
+        device_drivers
+            .installed
+            .iter()
+            .filter(|installed_choice| installed_choice.id != choice.id),
+    )
+    .into_iter()
+    .filter(|path| {
+        !choice
+            .files
+            .iter()
+            .any(|driver_file| driver_file.path == *path)
+    })
+    .collect();
+    let services_to_disable = services(
+        device_drivers
+            .installed
+            .iter()
+            .filter(|installed_choice| installed_choice.id != choice.id),
+    )
+    .into_iter()
+    .filter(|unit| {
+        !choice
+            .services
+            .contains(unit)
+    })
+    .collect();
+
+    let mut plan = TransactionPlan {
+        kind: TransactionKind::Install,
+        slot: device_drivers
+            .device
+            .slot
+            .clone(),
+        device: device_drivers.device_label(),
+        driver_id: choice.id.clone(),
+        packages_to_remove,
+        packages_to_install,
+        aur_packages,
+        modprobe_file,
+        modprobe_files_to_remove,
+        mkinitcpio_file,
+        mkinitcpio_files_to_remove,
+        is_initramfs_regenerated: false,
+        kernel_parameters_to_add: choice
+            .kernel_parameters
+            .clone(),
+        kernel_parameters_to_remove,
+        files_to_write: choice.files.clone(),
+        files_to_remove,
+        services_to_enable: choice
+            .services
+            .clone(),
+        services_to_disable,
+        is_reboot_required: false,
+    };
+    plan.is_initramfs_regenerated = !plan.is_empty() && plan.changes_initramfs_files();
+    plan.is_reboot_required = is_reboot_required(device_drivers, &plan);
+    Ok(plan)
+}
+
+// Plan removing the installed drivers of the device
+pub fn plan_remove(device_drivers: &DeviceDrivers) -> Result<TransactionPlan, Error> {
+    let slot = device_drivers
+        .device
+        .slot
+        .clone();
+    if device_drivers
+        .installed
+        .is_empty()
+    {
+        return NothingToRemoveSnafu { slot }.fail();
+    }
+
+    let mut plan = TransactionPlan {
+        kind: TransactionKind::Remove,
+        slot,
+        device: device_drivers.device_label(),
+        driver_id: device_drivers
+            .installed
+            .iter()
+            .map(|choice| choice.id.as_str())
+            .collect::<Vec<_>>()
+            .join(", "),
+        packages_to_remove: device_drivers
+            .installed
+            .iter()
+            .flat_map(|choice| {
+                choice
+                    .packages
+                    .iter()
+                    .cloned()
+            })
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect(),
+        packages_to_install: vec![],
+        aur_packages: vec![],
+        modprobe_file: None,
+        modprobe_files_to_remove: modprobe_filepaths(
+            device_drivers
+                .installed
+                .iter(),
+        ),
+        mkinitcpio_file: None,
+        mkinitcpio_files_to_remove: mkinitcpio_filepaths(
+            device_drivers
+                .installed
+                .iter(),
+        ),
+        is_initramfs_regenerated: false,
+        kernel_parameters_to_add: vec![],
+        kernel_parameters_to_remove: kernel_parameters(
+            device_drivers
+                .installed
+                .iter(),
+        ),
+        files_to_write: vec![],
+        files_to_remove: file_paths(
+            device_drivers
+                .installed
+                .iter(),
+        ),
+        services_to_enable: vec![],
+        services_to_disable: services(
+            device_drivers
+                .installed
+                .iter(),
+        ),
+        is_reboot_required: false,
+    };
+    plan.is_initramfs_regenerated = plan.changes_initramfs_files();
+    plan.is_reboot_required = is_reboot_required(device_drivers, &plan);
+    Ok(plan)
+}
+
+// The modprobe.d files that aldm wrote for the drivers
+fn modprobe_filepaths<'a>(choices: impl Iterator<Item = &'a DriverChoice>) -> Vec<PathBuf> {
+    choices
+        .filter(|choice| {
+            !choice
+                .modprobe
+                .is_empty()
+        })
+        .map(|choice| system::modprobe_filepath(&choice.id))
+        .collect()
+}
+
+// The mkinitcpio drop-ins that aldm wrote for the drivers
+fn mkinitcpio_filepaths<'a>(choices: impl Iterator<Item = &'a DriverChoice>) -> Vec<PathBuf> {
+    choices
+        .filter(|choice| {
+            !choice
+                .early_kms_modules
+                .is_empty()
+        })
+        .map(|choice| system::mkinitcpio_filepath(&choice.id))
+        .collect()
+}
+
+// The kernel parameters of the drivers, without duplicates
+fn kernel_parameters<'a>(choices: impl Iterator<Item = &'a DriverChoice>) -> Vec<String> {
+    let mut parameters = Vec::<String>::new();
+    for parameter in choices.flat_map(|choice| {
+        choice
+            .kernel_parameters
+            .iter()
+    }) {
+        if !parameters.contains(parameter) {
+            parameters.push(parameter.clone());
+        }
+    }
+    parameters
+}
+
+// The paths of the files of the drivers, without duplicates
+fn file_paths<'a>(choices: impl Iterator<Item = &'a DriverChoice>) -> Vec<String> {
+    let mut paths = Vec::<String>::new();
+    for driver_file in choices.flat_map(|choice| choice.files.iter()) {
+        if !paths.contains(&driver_file.path) {
+            paths.push(
+                driver_file
+                    .path
+                    .clone(),
+            );
+        }
+    }
+    paths
+}
+
+// The systemd units of the drivers, without duplicates
+fn services<'a>(choices: impl Iterator<Item = &'a DriverChoice>) -> Vec<String> {
+    let mut units = Vec::<String>::new();
+    for unit in choices.flat_map(|choice| {
+        choice
+            .services
+            .iter()
+    }) {
+        if !units.contains(unit) {
+            units.push(unit.clone());
+        }
+    }
+    units
+}
+
+// Graphics drivers are kernel modules that are in use as long as the display
+// is, so changing them only takes effect after a reboot.
+fn is_reboot_required(device_drivers: &DeviceDrivers, plan: &TransactionPlan) -> bool {
+    !plan.is_empty()
+        && device_drivers
+            .hardware_kinds
+            .contains(&HardwareKind::Graphics)
+}
+
+// Run the plan with the given backend, which has to support the AUR if the
+// plan installs packages from there. The files of the plan are changed below
+// the given root once the packages are, along with the kernel parameters, and
+// then the initramfs is rebuilt with the command runner. Cancelling stops the
+// transaction, but only until the backend starts committing changes to the
+// system. Once the packages of the old driver are removed, the new driver is
+// installed regardless, so that the device is not left without one.
+//
+// Every change is recorded in the journal of the transaction before it is
+// made. If a step fails after something may have been changed, the changes
+// made so far are rolled back, so that the system is left with the old driver
+// rather than half of the new one.
+pub fn execute_transaction(
+    plan: &TransactionPlan,
+    backend: &dyn PackageBackend,
+    command_runner: &dyn CommandRunner,
+    root: &Path,
+    progress: &dyn ProgressSink,
+    cancellation_token: &CancellationToken,
+) -> Result<TransactionOutcome, Error> {
+    ensure!(
+        plan.aur_packages
+            .is_empty()
+            || backend.supports_aur(),
+        AurNotEnabledSnafu {
+            packages: plan
+                .aur_packages
+                .clone()
+        }
+    );
+
+    let packages_before =
+        installed_versions(backend, plan.packages()).map_err(transaction_error)?;
+    let mut journal = Journal::begin(
+        root,
+        plan.kind.into(),
+        &plan.slot,
+        &plan.device,
+        &plan.driver_id,
+        plan.is_reboot_required,
+    )
+    .context(JournalSnafu {})?;
+    journal.packages_before = packages_before;
+
+    let result = run_transaction(
+        plan,
+        backend,
+        command_runner,
+        root,
+        progress,
+        cancellation_token,
+        &mut journal,
+    );
+    let (result, journal_result) = match result {
+        Ok(outcome) => (Ok(outcome), outcome.into()),
+        Err(error)
+            if journal
+                .changes
+                .is_empty() =>
+        {
+            let journal_result = JournalResult::Failed {
+                error: error.to_string(),
+                is_rolled_back: false,
+            };
+            (Err(error), journal_result)
+        }
+        Err(error) => {
+            progress.log(&format!(
+                "The transaction failed, so its changes are rolled back: {error}"
+            ));
+            let error_message = error.to_string();
+            let id = journal.id.clone();
+            match rollback::undo_changes(&journal, backend, command_runner, root, progress) {
+                Ok(()) => (
+                    Err(Box::new(error)).context(RolledBackSnafu { id }),
+                    JournalResult::Failed {
+                        error: error_message,
+                        is_rolled_back: true,
+                    },
+                ),
+                Err(rollback_error) => (
+                    Err(Box::new(error)).context(NotRolledBackSnafu {
+                        id,
+                        rollback_error: rollback_error.to_string(),
+                    }),
+                    JournalResult::Failed {
+                        error: error_message,
+                        is_rolled_back: false,
+                    },
+                ),
+            }
+        }
+    };
+
+    match installed_versions(backend, plan.packages()) {
+        Ok(versions) => journal.packages_after = versions,
+        Err(error) => tracing::warn!("Could not query the installed packages: {}", error),
+    }
+    if let Err(error) = journal.finish(root, journal_result) {
+        tracing::warn!("Could not finish the journal: {}", error);
+    }
+    result
+}
+
+fn run_transaction(
+    plan: &TransactionPlan,
+    backend: &dyn PackageBackend,
+    command_runner: &dyn CommandRunner,
+    root: &Path,
+    progress: &dyn ProgressSink,
+    cancellation_token: &CancellationToken,
+    journal: &mut Journal,
+) -> Result<TransactionOutcome, Error> {
+    let mut steps = Vec::new();
+    if !plan
+        .packages_to_remove
+        .is_empty()
+    {
+        steps.push((Phase::Removing, &plan.packages_to_remove));
+    }
+    if !plan
+        .packages_to_install
+        .is_empty()
+    {
+        steps.push((Phase::Installing, &plan.packages_to_install));
+    }
+
+    let uncancellable_token = CancellationToken::new();
+    let mut cancellation_token = cancellation_token;
+    let step_count = steps.len() as u64;
+    for (step_index, (phase, packages)) in steps
+        .into_iter()
+        .enumerate()
+    {
+        progress.phase(phase);
+        progress.step(
+            phase,
+            step_index as u64 + 1,
+            Some(step_count),
+            &format!("{} {}", backend.name(), packages.join(", ")),
+        );
+        // The packages are recorded first, so that a step that the package
+        // manager did not finish is rolled back as well
+        let change = match phase {
+            Phase::Removing => JournalChange::PackagesRemoved {
+                packages: packages.clone(),
+                is_done: false,
+            },
+            _ => JournalChange::PackagesInstalled {
+                packages: packages.clone(),
+                is_done: false,
+            },
+        };
+        journal
+            .record(root, change)
+            .context(JournalSnafu {})?;
+        let outcome = match phase {
+            Phase::Removing => backend.remove(packages, progress, cancellation_token),
+            _ => backend.install(packages, progress, cancellation_token),
+        }
+        .map_err(transaction_error)?;
+        match outcome {
+            StepOutcome::Cancelled => {
+                journal
+                    .discard_packages(root)
+                    .context(JournalSnafu {})?;
+                return Ok(TransactionOutcome::Cancelled);
+            }
+            StepOutcome::Completed => cancellation_token = &uncancellable_token,
+        }
+        journal
+            .complete_packages(root)
+            .context(JournalSnafu {})?;
+    }
+
+    if plan.changes_system() {
+        progress.phase(Phase::Configuring);
+        configure_system(plan, command_runner, root, progress, journal).context(SystemSnafu {})?;
+    }
+
+    Ok(match plan.is_reboot_required {
+        true => TransactionOutcome::RebootRequired,
+        false => TransactionOutcome::Succeeded,
+    })
+}
+
+// Change the files, kernel parameters and services of the plan, removing
+// those of the old drivers first, and rebuild the initramfs with them. Every
+// file is backed up in the journal before it is changed.
+fn configure_system(
+    plan: &TransactionPlan,
+    command_runner: &dyn CommandRunner,
+    root: &Path,
+    progress: &dyn ProgressSink,
+    journal: &mut Journal,
+) -> Result<(), system::Error> {
+    journal
+        .back_up_records(root)
+        .context(system::JournalSnafu {})?;
+    for path in plan
+        .modprobe_files_to_remove
+        .iter()
+        .chain(&plan.mkinitcpio_files_to_remove)
+        .chain(
+            plan.modprobe_file
+                .iter()
+                .map(|modprobe_file| &modprobe_file.path),
+        )
+        .chain(
+            plan.mkinitcpio_file
+                .iter()
+                .map(|mkinitcpio_file| &mkinitcpio_file.path),
+        )
+    {
+        journal
+            .back_up_file(root, path)
+            .context(system::JournalSnafu {})?;
+    }
+    for path in plan
+        .files_to_write
+        .iter()
+        .map(|driver_file| &driver_file.path)
+        .chain(&plan.files_to_remove)
+    {
+        journal
+            .back_up_file(root, Path::new(path))
+            .context(system::JournalSnafu {})?;
+    }
+
+    for path in &plan.modprobe_files_to_remove {
+        progress.log(&format!("Removing {}", path.display()));
+        system::remove_modprobe_file(root, path).context(system::ModprobeSnafu {})?;
+    }
+    for path in &plan.mkinitcpio_files_to_remove {
+        progress.log(&format!("Removing {}", path.display()));
+        system::remove_mkinitcpio_file(root, path).context(system::MkinitcpioSnafu {})?;
+    }
+    for path in &plan.files_to_remove {
+        system::remove_driver_file(root, path, progress).context(system::FilesSnafu {})?;
+    }
+    if let Some(modprobe_file) = &plan.modprobe_file {
+        progress.log(&format!(
+            "Writing {}",
+            modprobe_file
+                .path
+                .display()
+        ));
+        modprobe_file
+            .write(root)
+            .context(system::ModprobeSnafu {})?;
+    }
+    if let Some(mkinitcpio_file) = &plan.mkinitcpio_file {
+        progress.log(&format!(
+            "Writing {}",
+            mkinitcpio_file
+                .path
+                .display()
+        ));
+        mkinitcpio_file
+            .write(root)
+            .context(system::MkinitcpioSnafu {})?;
+    }
+
+    for driver_file in &plan.files_to_write {
+        system::write_driver_file(root, driver_file, progress).context(system::FilesSnafu {})?;
+    }
+
+    for module in plan.kms_hook_conflicts(root)? {
+        progress.log(&format!(
+            "Warning: remove the kms hook from HOOKS in /etc/mkinitcpio.conf, since it adds the blacklisted {module} to the initramfs"
+        ));
+    }
+
+    if !plan
+        .kernel_parameters_to_add
+        .is_empty()
+        || !plan
+            .kernel_parameters_to_remove
+            .is_empty()
+    {
+        if let Some(bootloader) = system::detect_bootloader(root) {
+            for path in system::bootloader_config_filepaths(root, bootloader)
+                .context(system::BootloaderSnafu {})?
+            {
+                journal
+                    .back_up_file(root, &path)
+                    .context(system::JournalSnafu {})?;
+            }
+        }
+    }
+    let kernel_parameter_changes = system::update_kernel_parameters(
+        root,
+        &plan.kernel_parameters_to_add,
+        &plan.kernel_parameters_to_remove,
+        command_runner,
+        progress,
+    )
+    .context(system::BootloaderSnafu {})?;
+    for parameter in &kernel_parameter_changes.added {
+        progress.log(&format!("Added the kernel parameter {parameter}"));
+    }
+    for parameter in &kernel_parameter_changes.removed {
+        progress.log(&format!("Removed the kernel parameter {parameter}"));
+    }
+
+    let units = system::disable_services(root, &plan.services_to_disable, command_runner, progress)
+        .context(system::ServicesSnafu {})?;
+    for unit in &units {
+        progress.log(&format!("Disabled {unit}"));
+    }
+    if !units.is_empty() {
+        journal
+            .record(root, JournalChange::ServicesDisabled { units })
+            .context(system::JournalSnafu {})?;
+    }
+    let units = system::enable_services(root, &plan.services_to_enable, command_runner, progress)
+        .context(system::ServicesSnafu {})?;
+    for unit in &units {
+        progress.log(&format!("Enabled {unit}"));
+    }
+    if !units.is_empty() {
+        journal
+            .record(root, JournalChange::ServicesEnabled { units })
+            .context(system::JournalSnafu {})?;
+    }
+
+    if plan.is_initramfs_regenerated {
+        match system::is_mkinitcpio_used(root) {
+            true => {
+                progress.step(Phase::Configuring, 1, Some(1), "Regenerating the initramfs");
+                system::regenerate_initramfs(command_runner, progress)
+                    .context(system::MkinitcpioSnafu {})?;
+            }
+            false => tracing::info!(
+                "Not regenerating the initramfs, since the system does not use mkinitcpio"
+            ),
+        }
+    }
+    Ok(())
+}
+
+// The installed versions of the packages, for the journal
+pub(crate) fn installed_versions<'a>(
+    backend: &dyn PackageBackend,
+    packages: impl Iterator<Item = &'a String>,
+) -> Result<BTreeMap<String, Version>, backend::Error> {
+    let installed_packages = backend.query_installed()?;
+    Ok(packages
+        .filter_map(|package| {
+            installed_packages
+                .get(package)
+                .map(|version| (package.clone(), version.clone()))
+        })
+        .collect())
+}
+
+// Tell the failures of pacman that the user can do something about apart from
+// the other failures of the backend
+fn transaction_error(error: backend::Error) -> Error {
+    match error {
+        backend::Error::PacmanFailed { failure, .. } => match failure {
+            PacmanFailure::DatabaseLocked => DatabaseLockedSnafu {}.build(),
+            PacmanFailure::InvalidSignature { package } => {
+                InvalidSignatureSnafu { package }.build()
+            }
+            PacmanFailure::PackageNotFound { package } => PackageNotFoundSnafu { package }.build(),
+            PacmanFailure::ConflictingPackages => ConflictingPackagesSnafu {}.build(),
+            PacmanFailure::DownloadFailed => DownloadFailedSnafu {}.build(),
+            PacmanFailure::NotEnoughDiskSpace => NotEnoughDiskSpaceSnafu {}.build(),
+            PacmanFailure::NotRoot => NotRootSnafu {}.build(),
+        },
+        source => Error::Backend { source },
+    }
+}
+
 #[derive(Debug, Snafu)]
 #[non_exhaustive]
 pub enum Error {
     #[non_exhaustive]
-    #[snafu(display(""))]
-    Dummy {},
+    #[snafu(
+        display("the device {slot} has no driver with the ID '{driver_id}'"),
+        visibility(pub)
+    )]
+    UnknownDriver { driver_id: String, slot: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the device {slot} has no installed driver to remove"),
+        visibility(pub)
+    )]
+    NothingToRemove { slot: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display(
+            "the packages {} are from the AUR, which is not enabled; enable it with --enable-aur or `enable_aur: true` in the config file",
+            packages.join(", ")
+        ),
+        visibility(pub)
+    )]
+    AurNotEnabled { packages: Vec<String> },
+
+    #[non_exhaustive]
+    #[snafu(
+        display(
+            "the package database is locked by another package manager; wait for it to finish, or remove /var/lib/pacman/db.lck if none is running"
+        ),
+        visibility(pub)
+    )]
+    DatabaseLocked {},
+
+    #[non_exhaustive]
+    #[snafu(
+        display(
+            "the signature of {} is invalid; updating archlinux-keyring usually fixes this",
+            package.as_deref().unwrap_or("a package")
+        ),
+        visibility(pub)
+    )]
+    InvalidSignature { package: Option<String> },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the package {package} was not found in the enabled repositories"),
+        visibility(pub)
+    )]
+    PackageNotFound { package: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the packages to install are in conflict with installed packages"),
+        visibility(pub)
+    )]
+    ConflictingPackages {},
+
+    #[non_exhaustive]
+    #[snafu(
+        display(
+            "the packages could not be downloaded; check the network connection and the mirrors"
+        ),
+        visibility(pub)
+    )]
+    DownloadFailed {},
+
+    #[non_exhaustive]
+    #[snafu(display("there is not enough free disk space"), visibility(pub))]
+    NotEnoughDiskSpace {},
+
+    #[non_exhaustive]
+    #[snafu(display("the package manager has to run as root"), visibility(pub))]
+    NotRoot {},
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the packages were changed, but not the system configuration: {source}"),
+        visibility(pub)
+    )]
+    System {
+        #[snafu(backtrace)]
+        source: system::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not keep the journal of the transaction: {source}"),
+        visibility(pub)
+    )]
+    Journal {
+        #[snafu(backtrace)]
+        source: system::journal::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("{source}\n  The changes of the transaction {id} were rolled back."),
+        visibility(pub)
+    )]
+    RolledBack { id: String, source: Box<Error> },
+
+    #[non_exhaustive]
+    #[snafu(
+        display(
+            "{source}\n  Rolling back the transaction {id} failed too: {rollback_error}\n  Run `aldm rollback {id}` to try again."
+        ),
+        visibility(pub)
+    )]
+    NotRolledBack {
+        id: String,
+        rollback_error: String,
+        source: Box<Error>,
+    },
+
+    #[non_exhaustive]
+    #[snafu(display("in the package backend: {source}"), visibility(pub))]
+    Backend {
+        #[snafu(backtrace)]
+        source: backend::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(display("{source}"), visibility(pub))]
+    ElevatedTransaction {
+        #[snafu(backtrace)]
+        source: elevated::Error,
+    },
 }
 
 // region: IMPORTS
 
-use snafu::Snafu;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+
+use crate::{
+    actions::{
+        elevated, rollback, CancellationToken, DeviceDrivers, DriverChoice, Phase, ProgressSink,
+    },
+    backend::{self, PackageBackend, PacmanFailure, StepOutcome},
+    data::{DriverFile, HardwareKind, Version},
+    system::{
+        self, CommandRunner, Journal, JournalChange, JournalResult, MkinitcpioFile, ModprobeFile,
+    },
+};
 
 // endregion: IMPORTS
@@ -1,13 +1,44 @@
+// The devices that have at least one of their drivers installed
+pub fn list(
+    database_filepath: &Path,
+    hwdata_dirpath: &Path,
+    root: &Path,
+    driver_filter: &DriverFilter,
+    progress: &dyn ProgressSink,
+    cancellation_token: &CancellationToken,
+) -> Result<Vec<DeviceDrivers>, Error> {
+    Ok(search::search_system(
+        database_filepath,
+        hwdata_dirpath,
+        root,
+        driver_filter,
+        progress,
+        cancellation_token,
+    )
+    .context(SearchSnafu {})?
+    .into_iter()
+    .filter(|device_drivers| {
+        !device_drivers
+            .installed
+            .is_empty()
+    })
+    .collect())
+}
+
 #[derive(Debug, Snafu)]
 #[non_exhaustive]
 pub enum Error {
     #[non_exhaustive]
-    #[snafu(display(""))]
-    Dummy {},
+    #[snafu(display("{source}"), visibility(pub))]
+    Search { source: search::Error },
 }
 
 // region: IMPORTS
 
-use snafu::Snafu;
+use std::path::Path;
+
+use snafu::{ResultExt, Snafu};
+
+use crate::actions::{search, CancellationToken, DeviceDrivers, DriverFilter, ProgressSink};
 
 // endregion: IMPORTS
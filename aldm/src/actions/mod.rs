@@ -2,20 +2,39 @@
 #[non_exhaustive]
 pub enum Error {
     #[non_exhaustive]
-    #[snafu(display("Could not list:\n  {source}"))]
-    CouldNotList {source: list::Error},
+    #[snafu(display("Could not list:\n  {source}"), visibility(pub))]
+    CouldNotList { source: list::Error },
 
     #[non_exhaustive]
-    #[snafu(display("Could not search:\n  {source}"))]
-    CouldNotSearch {source: search::Error},
+    #[snafu(display("Could not search:\n  {source}"), visibility(pub))]
+    CouldNotSearch { source: search::Error },
 
     #[non_exhaustive]
-    #[snafu(display("Could not install:\n  {source}"))]
-    CouldNotInstall {source: install::Error},
+    #[snafu(display("Could not install:\n  {source}"), visibility(pub))]
+    CouldNotInstall { source: install::Error },
 
     #[non_exhaustive]
-    #[snafu(display("Could not generate database:\n  {source}"))]
-    CouldNotGenerateDatabase {source: generate_db::Error},
+    #[snafu(display("Could not roll back:\n  {source}"), visibility(pub))]
+    CouldNotRollBack { source: rollback::Error },
+
+    #[non_exhaustive]
+    #[snafu(display("Could not show the history:\n  {source}"), visibility(pub))]
+    CouldNotShowHistory { source: history::Error },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("Could not serve as the elevated helper:\n  {source}"),
+        visibility(pub)
+    )]
+    CouldNotServeElevated { source: elevated::Error },
+
+    #[non_exhaustive]
+    #[snafu(display("Could not generate database:\n  {source}"), visibility(pub))]
+    CouldNotGenerateDatabase { source: generate_db::Error },
+
+    #[non_exhaustive]
+    #[snafu(display("Could not report the bug:\n  {source}"), visibility(pub))]
+    CouldNotReportBug { source: report_bug::Error },
 }
 
 // region: IMPORTS
@@ -26,18 +45,28 @@ use snafu::Snafu;
 
 // region: MODULES
 
+pub mod elevated;
+pub mod generate_db;
+pub mod history;
+pub mod install;
 pub mod list;
+pub mod progress;
+pub mod report_bug;
+pub mod rollback;
 pub mod search;
-pub mod install;
-pub mod generate_db;
 
 // endregion: MODULES
 
 // region: RE-EXPORTS
 
+pub use elevated::*;
+pub use generate_db::*;
+pub use history::*;
+pub use install::*;
 pub use list::*;
+pub use progress::*;
+pub use report_bug::*;
+pub use rollback::*;
 pub use search::*;
-pub use install::*;
-pub use generate_db::*;
 
-// endregion: RE-EXPORTS
\ No newline at end of file
+// endregion: RE-EXPORTS
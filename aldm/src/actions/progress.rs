@@ -0,0 +1,125 @@
+// The stages of a long-running action, in the order they usually happen
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Probing,
+    Evaluating,
+    Downloading,
+    Removing,
+    Installing,
+    Configuring,
+    RollingBack,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Phase::Probing => write!(f, "Probing the hardware"),
+            Phase::Evaluating => write!(f, "Evaluating the drivers"),
+            Phase::Downloading => write!(f, "Downloading packages"),
+            Phase::Removing => write!(f, "Removing packages"),
+            Phase::Installing => write!(f, "Installing packages"),
+            Phase::Configuring => write!(f, "Configuring the system"),
+            Phase::RollingBack => write!(f, "Rolling back the transaction"),
+        }
+    }
+}
+
+// What an action reports while it runs. Serialized, each event is a JSON
+// object with an `event` field naming its kind.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    Phase {
+        phase: Phase,
+    },
+    Step {
+        phase: Phase,
+        current: u64,
+        total: Option<u64>,
+        message: String,
+    },
+    Log {
+        line: String,
+    },
+}
+
+// Receives the progress of an action. Frontends implement this to render the
+// progress in their own way.
+pub trait ProgressSink: Send + Sync {
+    fn report(&self, event: ProgressEvent);
+
+    fn phase(&self, phase: Phase) {
+        self.report(ProgressEvent::Phase { phase });
+    }
+
+    fn step(&self, phase: Phase, current: u64, total: Option<u64>, message: &str) {
+        self.report(ProgressEvent::Step {
+            phase,
+            current,
+            total,
+            message: message.to_owned(),
+        });
+    }
+
+    fn log(&self, line: &str) {
+        self.report(ProgressEvent::Log {
+            line: line.to_owned(),
+        });
+    }
+}
+
+// Discards the progress, for callers that are not interested in it
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn report(&self, _event: ProgressEvent) {}
+}
+
+impl<F> ProgressSink for F
+where
+    F: Fn(ProgressEvent) + Send + Sync,
+{
+    fn report(&self, event: ProgressEvent) {
+        self(event)
+    }
+}
+
+// Asks a running action to stop. Clones share the same state, so a frontend
+// keeps one clone to cancel with and hands another to the action, which checks
+// it between steps.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    is_cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.is_cancelled
+            .store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.is_cancelled
+            .load(Ordering::SeqCst)
+    }
+}
+
+// region: IMPORTS
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+
+// endregion: IMPORTS
@@ -0,0 +1,234 @@
+// Bundle everything that is useful for diagnosing an issue into a single
+// gzip-compressed tarball: the latest logs and crash reports, the effective
+// configuration, the database version, and a snapshot of the hardware.
+pub fn report_bug(
+    bug_report_filepath: &Path,
+    log_dirpath: &Path,
+    log_filepath: &Path,
+    config: &Config,
+    config_filepaths: &[PathBuf],
+    database_filepath: &Path,
+) -> Result<(), Error> {
+    let bug_report_file = File::create(bug_report_filepath).context(CreateBugReportSnafu {
+        path: bug_report_filepath.to_owned(),
+    })?;
+    let mut tarball = tar::Builder::new(GzEncoder::new(bug_report_file, Compression::default()));
+
+    // Summary
+    let database_version = match Database::read_from_file(database_filepath) {
+        Ok(database) => database.version,
+        Err(error) => format!("unavailable ({error})"),
+    };
+    let summary = format!(
+        "{} {}\n\
+        \n\
+        Arguments: {:?}\n\
+        Config Filepath(s): {:?}\n\
+        Log Directory: {:?}\n\
+        Current Log File: {:?}\n\
+        Database Filepath: {:?}\n\
+        Database Version: {}\n",
+        *app::APP_NAME,
+        env!("CARGO_PKG_VERSION"),
+        env::args().collect::<Vec<_>>(),
+        config_filepaths,
+        log_dirpath,
+        log_filepath,
+        database_filepath,
+        database_version,
+    );
+    append_bytes(
+        &mut tarball,
+        "summary.txt",
+        summary.as_bytes(),
+        bug_report_filepath,
+    )?;
+
+    // Effective configuration
+    let config_text = serde_yaml::to_string(config).context(SerializeConfigSnafu {})?;
+    append_bytes(
+        &mut tarball,
+        "config.yaml",
+        config_text.as_bytes(),
+        bug_report_filepath,
+    )?;
+
+    // Latest logs and crash reports
+    let log_filepaths =
+        log_rotation::list_log_filepaths(log_dirpath, &format!("{}.log", *app::APP_NAME))
+            .unwrap_or_default();
+    let crash_filepaths = crash::crash_filepaths(log_dirpath);
+    for filepath in log_filepaths
+        .iter()
+        .take(MAX_LOG_FILES)
+        .chain(
+            crash_filepaths
+                .iter()
+                .take(MAX_CRASH_FILES),
+        )
+    {
+        let Some(filename) = filepath.file_name() else {
+            continue;
+        };
+        // Read the file at once, since the current log file keeps growing
+        // while it is being archived
+        let Ok(contents) = fs::read(filepath) else {
+            continue;
+        };
+        append_bytes(
+            &mut tarball,
+            &format!("logs/{}", filename.to_string_lossy()),
+            &contents,
+            bug_report_filepath,
+        )?;
+    }
+
+    // Hardware snapshot
+    for (filename, text) in hardware_snapshot() {
+        append_bytes(
+            &mut tarball,
+            &format!("hardware/{filename}"),
+            text.as_bytes(),
+            bug_report_filepath,
+        )?;
+    }
+
+    tarball
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .context(WriteBugReportSnafu {
+            path: bug_report_filepath.to_owned(),
+        })?;
+
+    Ok(())
+}
+
+pub fn default_bug_report_filepath() -> PathBuf {
+    PathBuf::from(format!(
+        "{}-bug-report-{}.tar.gz",
+        *app::APP_NAME,
+        app::filename_timestamp()
+    ))
+}
+
+const MAX_LOG_FILES: usize = 3;
+const MAX_CRASH_FILES: usize = 3;
+
+fn append_bytes<W: Write>(
+    tarball: &mut tar::Builder<W>,
+    path: &str,
+    contents: &[u8],
+    bug_report_filepath: &Path,
+) -> Result<(), Error> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0),
+    );
+    header.set_cksum();
+    tarball
+        .append_data(&mut header, path, contents)
+        .context(WriteBugReportSnafu {
+            path: bug_report_filepath.to_owned(),
+        })
+}
+
+// A raw dump of what the kernel reports about the hardware. Missing sources
+// are noted in the snapshot instead of failing the bug report.
+fn hardware_snapshot() -> Vec<(&'static str, String)> {
+    let read_or_note = |path: &str| {
+        fs::read_to_string(path).unwrap_or_else(|error| format!("unavailable ({error})\n"))
+    };
+    vec![
+        ("pci.txt", uevents("/sys/bus/pci/devices")),
+        ("usb.txt", uevents("/sys/bus/usb/devices")),
+        (
+            "kernel.txt",
+            format!(
+                "Release: {}Command Line: {}",
+                read_or_note("/proc/sys/kernel/osrelease"),
+                read_or_note("/proc/cmdline")
+            ),
+        ),
+        ("modules.txt", read_or_note("/proc/modules")),
+    ]
+}
+
+fn uevents(devices_dirpath: &str) -> String {
+    let mut device_dirpaths = match fs::read_dir(devices_dirpath) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .collect::<Vec<_>>(),
+        Err(error) => return format!("unavailable ({error})\n"),
+    };
+    device_dirpaths.sort();
+    device_dirpaths
+        .iter()
+        .filter_map(|device_dirpath| {
+            let uevent = fs::read_to_string(device_dirpath.join("uevent")).ok()?;
+            Some(format!(
+                "[{}]\n{}\n",
+                device_dirpath
+                    .file_name()?
+                    .to_string_lossy(),
+                uevent
+            ))
+        })
+        .collect()
+}
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(
+        display("could not create the bug report at {:?}: {source}", path),
+        visibility(pub)
+    )]
+    CreateBugReport {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not write the bug report at {:?}: {source}", path),
+        visibility(pub)
+    )]
+    WriteBugReport {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not serialize the configuration: {source}"),
+        visibility(pub)
+    )]
+    SerializeConfig { source: serde_yaml::Error },
+}
+
+// region: IMPORTS
+
+use std::{
+    env,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use flate2::{write::GzEncoder, Compression};
+use snafu::{ResultExt, Snafu};
+
+use crate::{
+    app::{self, crash, log_rotation, Config},
+    data::Database,
+};
+
+// endregion: IMPORTS
@@ -0,0 +1,346 @@
+// Find the transaction to roll back, which is the one with the given ID or
+// else the last one that changed something and was not rolled back yet. A
+// transaction can only be rolled back after the ones that came after it, since
+// they were made on top of its changes.
+pub fn plan_rollback(root: &Path, id: Option<&str>) -> Result<Journal, Error> {
+    let journals = system::read_journals(root).context(TransactionJournalSnafu {})?;
+    let is_pending = |journal: &Journal| {
+        journal.kind != JournalKind::Rollback
+            && !journal
+                .changes
+                .is_empty()
+            && !journal.is_rolled_back()
+    };
+    let journal = match id {
+        Some(id) => system::read_journal(root, id).context(TransactionJournalSnafu {})?,
+        None => journals
+            .iter()
+            .rev()
+            .find(|journal| is_pending(journal))
+            .cloned()
+            .context(NothingToRollBackSnafu {})?,
+    };
+
+    ensure!(
+        journal.kind != JournalKind::Rollback,
+        RollbackOfRollbackSnafu {
+            id: journal.id.clone()
+        }
+    );
+    ensure!(
+        !journal.is_rolled_back(),
+        AlreadyRolledBackSnafu {
+            id: journal.id.clone()
+        }
+    );
+    ensure!(
+        !journal
+            .changes
+            .is_empty(),
+        NoChangesSnafu {
+            id: journal.id.clone()
+        }
+    );
+    if let Some(later_journal) = journals
+        .iter()
+        .rev()
+        .find(|later_journal| later_journal.id > journal.id && is_pending(later_journal))
+    {
+        return LaterTransactionSnafu {
+            id: journal.id.clone(),
+            later_id: later_journal
+                .id
+                .clone(),
+        }
+        .fail();
+    }
+    Ok(journal)
+}
+
+// Undo the transaction of the journal, keeping a journal of the rollback too.
+// Like any transaction of graphics drivers, it takes a reboot to take effect.
+pub fn execute_rollback(
+    journal: &Journal,
+    backend: &dyn PackageBackend,
+    command_runner: &dyn CommandRunner,
+    root: &Path,
+    progress: &dyn ProgressSink,
+) -> Result<TransactionOutcome, Error> {
+    let mut rollback_journal = Journal::begin(
+        root,
+        JournalKind::Rollback,
+        &journal.slot,
+        &journal.device,
+        &journal.driver_id,
+        journal.is_reboot_required,
+    )
+    .context(TransactionJournalSnafu {})?;
+    rollback_journal.rollback_of = Some(journal.id.clone());
+    rollback_journal.packages_before = journal
+        .packages_after
+        .clone();
+
+    let result = undo_changes(journal, backend, command_runner, root, progress);
+    match installed_versions(
+        backend,
+        journal
+            .packages_before
+            .keys()
+            .chain(
+                journal
+                    .packages_after
+                    .keys(),
+            ),
+    ) {
+        Ok(versions) => rollback_journal.packages_after = versions,
+        Err(error) => tracing::warn!("Could not query the installed packages: {}", error),
+    }
+    if let Err(error) = &result {
+        if let Err(journal_error) = rollback_journal.finish(
+            root,
+            JournalResult::Failed {
+                error: error.to_string(),
+                is_rolled_back: false,
+            },
+        ) {
+            tracing::warn!("Could not finish the journal: {}", journal_error);
+        }
+    }
+    result?;
+
+    let outcome = match journal.is_reboot_required {
+        true => TransactionOutcome::RebootRequired,
+        false => TransactionOutcome::Succeeded,
+    };
+    rollback_journal
+        .finish(root, outcome.into())
+        .context(TransactionJournalSnafu {})?;
+    let mut journal = journal.clone();
+    journal.rolled_back_by = Some(rollback_journal.id);
+    journal
+        .save(root)
+        .context(TransactionJournalSnafu {})?;
+    Ok(outcome)
+}
+
+// Undo the changes of the journal, the last first. Every change is undone
+// that can be, even if others cannot, so that as little as possible is left
+// of a broken transaction. The initramfs and the GRUB configuration are
+// rebuilt at the end if any of their files were restored.
+pub(crate) fn undo_changes(
+    journal: &Journal,
+    backend: &dyn PackageBackend,
+    command_runner: &dyn CommandRunner,
+    root: &Path,
+    progress: &dyn ProgressSink,
+) -> Result<(), Error> {
+    progress.phase(Phase::RollingBack);
+    let cancellation_token = CancellationToken::new();
+    let mut failures = Vec::new();
+    let mut is_initramfs_changed = false;
+    let mut is_grub_defaults_changed = false;
+    for change in journal
+        .changes
+        .iter()
+        .rev()
+    {
+        let result = match change {
+            JournalChange::PackagesRemoved { packages, .. } => {
+                packages_to_undo(journal, packages, backend, |was_installed, is_installed| {
+                    was_installed && !is_installed
+                })
+                .and_then(|packages| match packages.is_empty() {
+                    true => Ok(()),
+                    false => {
+                        progress.log(&format!("Reinstalling {}", packages.join(", ")));
+                        backend
+                            .install(&packages, progress, &cancellation_token)
+                            .map(|_| ())
+                    }
+                })
+                .map_err(|error| error.to_string())
+            }
+            JournalChange::PackagesInstalled { packages, .. } => {
+                packages_to_undo(journal, packages, backend, |was_installed, is_installed| {
+                    !was_installed && is_installed
+                })
+                .and_then(|packages| match packages.is_empty() {
+                    true => Ok(()),
+                    false => {
+                        progress.log(&format!("Removing {}", packages.join(", ")));
+                        backend
+                            .remove(&packages, progress, &cancellation_token)
+                            .map(|_| ())
+                    }
+                })
+                .map_err(|error| error.to_string())
+            }
+            JournalChange::FileChanged { path, backup_path } => {
+                system::restore_file(root, path, backup_path.as_deref())
+                    .map(|is_changed| {
+                        if is_changed {
+                            match backup_path {
+                                Some(_) => progress.log(&format!("Restored {}", path.display())),
+                                None => progress.log(&format!("Removed {}", path.display())),
+                            }
+                            is_initramfs_changed |= system::is_initramfs_file(path);
+                            is_grub_defaults_changed |= system::is_grub_defaults_file(path);
+                        }
+                    })
+                    .map_err(|error| error.to_string())
+            }
+            JournalChange::ServicesDisabled { units } => {
+                system::enable_services(root, units, command_runner, progress)
+                    .map(|units| {
+                        for unit in units {
+                            progress.log(&format!("Enabled {unit}"));
+                        }
+                    })
+                    .map_err(|error| error.to_string())
+            }
+            JournalChange::ServicesEnabled { units } => {
+                system::disable_services(root, units, command_runner, progress)
+                    .map(|units| {
+                        for unit in units {
+                            progress.log(&format!("Disabled {unit}"));
+                        }
+                    })
+                    .map_err(|error| error.to_string())
+            }
+        };
+        if let Err(failure) = result {
+            progress.log(&format!("Could not undo a change: {failure}"));
+            failures.push(failure);
+        }
+    }
+
+    if is_initramfs_changed && system::is_mkinitcpio_used(root) {
+        if let Err(error) = system::regenerate_initramfs(command_runner, progress) {
+            failures.push(error.to_string());
+        }
+    }
+    if is_grub_defaults_changed {
+        if let Err(error) = system::regenerate_grub_config(command_runner, progress) {
+            failures.push(error.to_string());
+        }
+    }
+    ensure!(failures.is_empty(), IncompleteRollbackSnafu { failures });
+    Ok(())
+}
+
+// The packages of a step that are still to be changed back, going by whether
+// they were installed before the transaction and whether they are now. A
+// package that was already installed is never removed, and a step that the
+// package manager did not finish is only undone as far as it got.
+fn packages_to_undo(
+    journal: &Journal,
+    packages: &[String],
+    backend: &dyn PackageBackend,
+    is_to_undo: impl Fn(bool, bool) -> bool,
+) -> Result<Vec<String>, backend::Error> {
+    let installed_packages = backend.query_installed()?;
+    Ok(packages
+        .iter()
+        .filter(|package| {
+            is_to_undo(
+                journal
+                    .packages_before
+                    .contains_key(*package),
+                installed_packages.contains_key(*package),
+            )
+        })
+        .cloned()
+        .collect())
+}
+
+impl From<TransactionOutcome> for JournalResult {
+    fn from(outcome: TransactionOutcome) -> Self {
+        match outcome {
+            TransactionOutcome::Succeeded => JournalResult::Succeeded,
+            TransactionOutcome::RebootRequired => JournalResult::RebootRequired,
+            TransactionOutcome::Cancelled => JournalResult::Cancelled,
+        }
+    }
+}
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(display("there is no transaction to roll back"), visibility(pub))]
+    NothingToRollBack {},
+
+    #[non_exhaustive]
+    #[snafu(
+        display(
+            "the transaction {id} is a rollback itself; install or remove the driver again instead"
+        ),
+        visibility(pub)
+    )]
+    RollbackOfRollback { id: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the transaction {id} was already rolled back"),
+        visibility(pub)
+    )]
+    AlreadyRolledBack { id: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the transaction {id} did not change anything"),
+        visibility(pub)
+    )]
+    NoChanges { id: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the transaction {later_id} came after {id}; roll it back first"),
+        visibility(pub)
+    )]
+    LaterTransaction { id: String, later_id: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("some changes could not be undone: {}", failures.join("; ")),
+        visibility(pub)
+    )]
+    IncompleteRollback { failures: Vec<String> },
+
+    #[non_exhaustive]
+    #[snafu(display("in the package backend: {source}"), visibility(pub))]
+    PackageBackend {
+        #[snafu(backtrace)]
+        source: backend::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(display("in the journal: {source}"), visibility(pub))]
+    TransactionJournal {
+        #[snafu(backtrace)]
+        source: system::journal::Error,
+    },
+    #[non_exhaustive]
+    #[snafu(display("{source}"), visibility(pub))]
+    ElevatedRollback {
+        #[snafu(backtrace)]
+        source: elevated::Error,
+    },
+}
+
+// region: IMPORTS
+
+use std::path::Path;
+
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+
+use crate::{
+    actions::{
+        elevated, install::installed_versions, CancellationToken, Phase, ProgressSink,
+        TransactionOutcome,
+    },
+    backend::{self, PackageBackend},
+    system::{self, CommandRunner, Journal, JournalChange, JournalKind, JournalResult},
+};
+
+// endregion: IMPORTS
@@ -1,13 +1,298 @@
+// Which drivers to consider. Preferred tags only change the order of the
+// drivers, since excluding drivers by preference could leave some hardware
+// without any driver at all. Excluded tags do remove drivers.
+#[derive(Clone, Debug, Default)]
+pub struct DriverFilter {
+    pub hardware_kind: Option<HardwareKind>,
+    pub preferred_tags: Vec<String>,
+    pub excluded_tags: Vec<String>,
+    pub driver_id: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DriverChoice {
+    pub id: String,
+    pub packages: Vec<String>,
+    pub hardware_kinds: BTreeSet<HardwareKind>,
+    pub tags: BTreeSet<String>,
+    // The packages that are installed from the AUR
+    pub aur_packages: Vec<String>,
+    pub modprobe: ModprobeConfig,
+    pub early_kms_modules: Vec<String>,
+    pub kernel_parameters: Vec<String>,
+    pub services: Vec<String>,
+    pub files: Vec<DriverFile>,
+    pub is_installed: bool,
+}
+
+// A device along with the drivers that apply to it. The recommended driver is
+// the best match for the preferred tags, and the alternatives are the rest of
+// the drivers in order of preference.
+#[derive(Clone, Debug, Serialize)]
+pub struct DeviceDrivers {
+    pub device: PciDevice,
+    pub device_name: DeviceName,
+    pub hardware_kinds: BTreeSet<HardwareKind>,
+    pub installed: Vec<DriverChoice>,
+    pub recommended: Option<DriverChoice>,
+    pub alternatives: Vec<DriverChoice>,
+}
+
+impl DeviceDrivers {
+    pub fn choices(&self) -> impl Iterator<Item = &DriverChoice> {
+        self.recommended
+            .iter()
+            .chain(
+                self.alternatives
+                    .iter(),
+            )
+    }
+
+    // The name of the device and its subsystem when known, followed by the
+    // slot and the IDs, as in `NVIDIA GeForce RTX 4090 (ASUSTeK Computer Inc.)
+    // 0000:01:00.0 [10de:2684]`
+    pub fn device_label(&self) -> String {
+        match (
+            self.device_name
+                .is_empty(),
+            &self
+                .device_name
+                .subsystem,
+        ) {
+            (true, _) => self
+                .device
+                .to_string(),
+            (false, Some(subsystem)) => {
+                format!("{} ({}) {}", self.device_name, subsystem, self.device)
+            }
+            (false, None) => format!("{} {}", self.device_name, self.device),
+        }
+    }
+}
+
+// Read the database, probe the system and search it for drivers. The names of
+// the devices are looked up in the ID files of the given hwdata directory.
+pub fn search_system(
+    database_filepath: &Path,
+    hwdata_dirpath: &Path,
+    root: &Path,
+    driver_filter: &DriverFilter,
+    progress: &dyn ProgressSink,
+    cancellation_token: &CancellationToken,
+) -> Result<Vec<DeviceDrivers>, Error> {
+    let database = Database::read_from_file(database_filepath).context(ReadDatabaseSnafu {})?;
+    ensure!(!cancellation_token.is_cancelled(), CancelledSnafu {});
+
+    progress.phase(Phase::Probing);
+    let system_snapshot = SystemSnapshot::probe(root).context(ProbeSystemSnafu {})?;
+    ensure!(!cancellation_token.is_cancelled(), CancelledSnafu {});
+
+    progress.phase(Phase::Evaluating);
+    let hwdata = HwData::load(hwdata_dirpath);
+    Ok(search(&database, &system_snapshot, &hwdata, driver_filter))
+}
+
+pub fn search(
+    database: &Database,
+    system_snapshot: &SystemSnapshot,
+    hwdata: &HwData,
+    driver_filter: &DriverFilter,
+) -> Vec<DeviceDrivers> {
+    let preferred_tags = normalize_tags(&driver_filter.preferred_tags);
+    let excluded_tags = normalize_tags(&driver_filter.excluded_tags);
+
+    system_snapshot
+        .pci_devices
+        .iter()
+        .filter_map(|pci_device| {
+            let mut choices = database
+                .driver_options_for_device(pci_device, system_snapshot)
+                .map(|driver_option| {
+                    driver_choice(database, system_snapshot, pci_device, driver_option)
+                })
+                .filter(|choice| {
+                    choice
+                        .tags
+                        .is_disjoint(&excluded_tags)
+                })
+                .filter(|choice| {
+                    driver_filter
+                        .driver_id
+                        .as_ref()
+                        .is_none_or(|driver_id| &choice.id == driver_id)
+                })
+                .collect::<Vec<_>>();
+            if choices.is_empty() {
+                return None;
+            }
+
+            // The class of the device says what it is. The kinds in the
+            // database are only a fallback for devices of unknown classes.
+            let hardware_kinds = match pci_device.hardware_kind() {
+                Some(hardware_kind) => BTreeSet::from([hardware_kind]),
+                None => choices
+                    .iter()
+                    .flat_map(|choice| {
+                        choice
+                            .hardware_kinds
+                            .iter()
+                            .copied()
+                    })
+                    .collect(),
+            };
+            if driver_filter
+                .hardware_kind
+                .is_some_and(|hardware_kind| !hardware_kinds.contains(&hardware_kind))
+            {
+                return None;
+            }
+
+            // Stable, so that the order of the database breaks ties
+            choices.sort_by_key(|choice| {
+                Reverse(
+                    choice
+                        .tags
+                        .intersection(&preferred_tags)
+                        .count(),
+                )
+            });
+            let installed = choices
+                .iter()
+                .filter(|choice| choice.is_installed)
+                .cloned()
+                .collect();
+            let mut choices = choices.into_iter();
+            Some(DeviceDrivers {
+                device: pci_device.clone(),
+                device_name: hwdata.pci_device_name(pci_device),
+                hardware_kinds,
+                installed,
+                recommended: choices.next(),
+                alternatives: choices.collect(),
+            })
+        })
+        .collect()
+}
+
+fn driver_choice(
+    database: &Database,
+    system_snapshot: &SystemSnapshot,
+    pci_device: &PciDevice,
+    driver_option: &DriverOption,
+) -> DriverChoice {
+    // The secondary GPU of a system with hybrid graphics renders on demand for
+    // the GPU that drives the display, which takes companion packages
+    let packages = match system_snapshot.is_hybrid_graphics() && !pci_device.is_boot_vga {
+        true => driver_option
+            .packages
+            .iter()
+            .chain(
+                driver_option
+                    .hybrid_graphics_packages
+                    .iter(),
+            )
+            .cloned()
+            .collect::<Vec<_>>(),
+        false => driver_option
+            .packages
+            .clone(),
+    };
+    let package_infos = packages
+        .iter()
+        .filter_map(|package| database.package_info(package))
+        .collect::<Vec<_>>();
+    let mut hardware_kinds = package_infos
+        .iter()
+        .flat_map(|package_info| {
+            package_info
+                .hardware_kinds
+                .iter()
+                .copied()
+        })
+        .collect::<BTreeSet<_>>();
+    // The database can leave out the kinds that the device class implies
+    if hardware_kinds.is_empty() {
+        hardware_kinds.extend(pci_device.hardware_kind());
+    }
+    DriverChoice {
+        id: driver_option
+            .id
+            .clone(),
+        packages: packages.clone(),
+        hardware_kinds,
+        tags: package_infos
+            .iter()
+            .flat_map(|package_info| {
+                package_info
+                    .tags
+                    .iter()
+                    .cloned()
+            })
+            .collect(),
+        aur_packages: packages
+            .iter()
+            .filter(|package| database.package_source(package) == PackageSource::Aur)
+            .cloned()
+            .collect(),
+        modprobe: driver_option
+            .modprobe
+            .clone(),
+        early_kms_modules: driver_option
+            .early_kms_modules
+            .clone(),
+        kernel_parameters: driver_option
+            .kernel_parameters
+            .clone(),
+        services: driver_option
+            .services
+            .clone(),
+        files: driver_option
+            .files
+            .clone(),
+        is_installed: packages
+            .iter()
+            .all(|package| system_snapshot.is_package_installed(package)),
+    }
+}
+
+fn normalize_tags(tags: &[String]) -> BTreeSet<String> {
+    tags.iter()
+        .flat_map(|tags| tags.split(','))
+        .map(data::normalize_tag)
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
 #[derive(Debug, Snafu)]
 #[non_exhaustive]
 pub enum Error {
     #[non_exhaustive]
-    #[snafu(display(""))]
-    Dummy {},
+    #[snafu(display("{source}"), visibility(pub))]
+    ReadDatabase { source: database::Error },
+
+    #[non_exhaustive]
+    #[snafu(display("{source}"), visibility(pub))]
+    ProbeSystem { source: probe::Error },
+
+    #[non_exhaustive]
+    #[snafu(display("the search was cancelled"), visibility(pub))]
+    Cancelled {},
 }
 
 // region: IMPORTS
 
-use snafu::Snafu;
+use std::{cmp::Reverse, collections::BTreeSet, path::Path};
+
+use serde::Serialize;
+use snafu::{ensure, ResultExt, Snafu};
+
+use crate::{
+    actions::{CancellationToken, Phase, ProgressSink},
+    data::{
+        self, database, Database, DeviceName, DriverFile, DriverOption, HardwareKind, HwData,
+        ModprobeConfig, PackageSource,
+    },
+    probe::{self, PciDevice, SystemSnapshot},
+};
 
 // endregion: IMPORTS
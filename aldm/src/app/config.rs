@@ -1,14 +1,7 @@
 pub fn init_config() -> Result<(Config, Vec<PathBuf>), Error> {
-    let config_filename = format!("{}.conf", *app::APP_NAME);
-    let xdg_app_dirs = xdg::BaseDirectories::with_prefix(*app::APP_NAME)
-        .context(RetreiveConfigUserAppBaseDirectoriesSnafu {})?;
-    let default_config_filepath = xdg_app_dirs.get_config_file(&config_filename);
-    let candidate_config_filepaths = vec![
-        default_config_filepath.clone(),
-        format!("/etc/{}/{}", *app::APP_NAME, config_filename).into(),
-        format!("/etc/{}", config_filename).into(),
-        format!("/var/tmp/{}/{}", *app::APP_NAME, config_filename).into(),
-    ];
+    let candidate_config_filepaths = iter::once(user_config_filepath()?)
+        .chain(system_config_filepaths())
+        .collect::<Vec<_>>();
 
     let mut config = Config::new();
 
@@ -42,7 +35,43 @@ pub fn init_config() -> Result<(Config, Vec<PathBuf>), Error> {
     }
 }
 
+// The config file of the current user, which takes precedence over the
+// system-wide config files
+pub fn user_config_filepath() -> Result<PathBuf, Error> {
+    let xdg_app_dirs = xdg::BaseDirectories::with_prefix(*app::APP_NAME)
+        .context(RetreiveConfigUserAppBaseDirectoriesSnafu {})?;
+    Ok(xdg_app_dirs.get_config_file(config_filename()))
+}
+
+// The system-wide config files, in the order of precedence
+pub fn system_config_filepaths() -> Vec<PathBuf> {
+    let config_filename = config_filename();
+    vec![
+        format!("/etc/{}/{}", *app::APP_NAME, config_filename).into(),
+        format!("/etc/{}", config_filename).into(),
+        format!("/var/tmp/{}/{}", *app::APP_NAME, config_filename).into(),
+    ]
+}
+
+fn config_filename() -> String {
+    format!("{}.conf", *app::APP_NAME)
+}
+
+pub fn read_config_file(config_filepath: &Path) -> Result<Config, Error> {
+    let config_file = fs::File::open(config_filepath).context(ReadConfigFileSnafu {
+        path: config_filepath.to_owned(),
+    })?;
+    serde_yaml::from_reader(BufReader::new(config_file)).context(ConfigFileFormatSnafu {
+        path: config_filepath.to_owned(),
+    })
+}
+
 pub fn create_config_file(config: &Config, config_filepath: &PathBuf) -> Result<(), Error> {
+    if let Some(config_dirpath) = config_filepath.parent() {
+        fs::create_dir_all(config_dirpath).context(CreateConfigDirectorySnafu {
+            path: config_dirpath.to_owned(),
+        })?;
+    }
     serde_yaml::to_writer(
         BufWriter::new(
             File::create(config_filepath.clone()).context(CreateConfigFileSnafu {
@@ -62,8 +91,35 @@ pub struct Config {
 
     pub log_level_filter: Option<log::LevelFilter>,
 
+    pub log_directives: Option<String>,
+
+    pub log_file_format: Option<LogFileFormat>,
+
+    pub log_file_ansi: Option<bool>,
+
+    pub log_max_files: Option<usize>,
+
+    pub log_max_age_days: Option<u64>,
+
+    pub log_max_file_size: Option<u64>,
+
     pub no_color: Option<bool>,
 
+    pub preferred_tags: Option<Vec<String>>,
+
+    pub excluded_tags: Option<Vec<String>>,
+
+    pub enable_aur: Option<bool>,
+
+    pub aur_helper: Option<String>,
+
+    // Where the pci.ids file is read from
+    pub hwdata_directory: Option<String>,
+
+    // The root of the system that is probed and configured, which is only
+    // changed for testing
+    pub system_root: Option<String>,
+
     #[serde(skip)]
     is_modified: bool,
 }
@@ -73,7 +129,19 @@ impl Config {
         Self {
             log_directory: None,
             log_level_filter: None,
+            log_directives: None,
+            log_file_format: None,
+            log_file_ansi: None,
+            log_max_files: None,
+            log_max_age_days: None,
+            log_max_file_size: None,
             no_color: None,
+            preferred_tags: None,
+            excluded_tags: None,
+            enable_aur: None,
+            aur_helper: None,
+            hwdata_directory: None,
+            system_root: None,
             is_modified: false,
         }
     }
@@ -87,24 +155,100 @@ impl Config {
             .log_level_filter
             .take()
             .or(other.log_level_filter);
+        self.log_directives = self
+            .log_directives
+            .take()
+            .or(other.log_directives);
+        self.log_file_format = self
+            .log_file_format
+            .take()
+            .or(other.log_file_format);
+        self.log_file_ansi = self
+            .log_file_ansi
+            .take()
+            .or(other.log_file_ansi);
+        self.log_max_files = self
+            .log_max_files
+            .take()
+            .or(other.log_max_files);
+        self.log_max_age_days = self
+            .log_max_age_days
+            .take()
+            .or(other.log_max_age_days);
+        self.log_max_file_size = self
+            .log_max_file_size
+            .take()
+            .or(other.log_max_file_size);
         self.no_color = self
             .no_color
             .take()
             .or(other.no_color);
+        self.preferred_tags = self
+            .preferred_tags
+            .take()
+            .or(other.preferred_tags);
+        self.excluded_tags = self
+            .excluded_tags
+            .take()
+            .or(other.excluded_tags);
+        self.enable_aur = self
+            .enable_aur
+            .take()
+            .or(other.enable_aur);
+        self.aur_helper = self
+            .aur_helper
+            .take()
+            .or(other.aur_helper);
+        self.hwdata_directory = self
+            .hwdata_directory
+            .take()
+            .or(other.hwdata_directory);
+        self.system_root = self
+            .system_root
+            .take()
+            .or(other.system_root);
         self.is_modified = true;
     }
 
     pub fn is_modified(&self) -> bool {
         self.is_modified
     }
+
+    // The keys that have a value, as they appear in the config file
+    pub fn assigned_keys(&self) -> Vec<String> {
+        match serde_yaml::to_value(self) {
+            Ok(serde_yaml::Value::Mapping(mapping)) => mapping
+                .into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .filter_map(|(key, _)| {
+                    key.as_str()
+                        .map(String::from)
+                })
+                .collect(),
+            _ => vec![],
+        }
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
+        let log_retention = LogRetention::default();
         Config {
             log_directory: None,
             log_level_filter: Some(log::LevelFilter::Info),
+            log_directives: None,
+            log_file_format: Some(LogFileFormat::Pretty),
+            log_file_ansi: Some(true),
+            log_max_files: Some(log_retention.max_files),
+            log_max_age_days: Some(log_retention.max_age_days),
+            log_max_file_size: Some(log_retention.max_file_size),
             no_color: Some(false),
+            preferred_tags: Some(vec![]),
+            excluded_tags: Some(vec![]),
+            enable_aur: Some(false),
+            aur_helper: None,
+            hwdata_directory: None,
+            system_root: None,
             is_modified: true,
         }
     }
@@ -176,10 +320,11 @@ pub enum Error {
 use std::{
     fs::{self, File},
     io::{BufReader, BufWriter},
-    path::PathBuf,
+    iter,
+    path::{Path, PathBuf},
 };
 
-use crate::app::{self, PathListPermissions};
+use crate::app::{self, LogFileFormat, LogRetention, PathListPermissions};
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
 
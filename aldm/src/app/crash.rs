@@ -0,0 +1,146 @@
+// Install a panic hook that writes a crash report next to the log files,
+// before handing over to the previously installed hook (which prints the
+// panic message).
+pub fn install_panic_hook(crash_dirpath: PathBuf, config_filepaths: Vec<PathBuf>) {
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |panic_info| {
+        let crash_report = format_crash_report(panic_info, &config_filepaths);
+        match write_crash_report(&crash_dirpath, &crash_report) {
+            Ok(crash_filepath) => {
+                tracing::error!(
+                    "{} crashed. A crash report was written to {:?}",
+                    *app::APP_NAME,
+                    crash_filepath
+                );
+                anstream::eprintln!(
+                    "{} crashed. Please attach the crash report at {:?} (or the output of `{} report-bug`) when reporting this issue.",
+                    *app::APP_NAME,
+                    crash_filepath,
+                    *app::APP_NAME,
+                );
+            }
+            Err(error) => {
+                tracing::error!(
+                    "{} crashed. The crash report could not be written to {:?}: {}",
+                    *app::APP_NAME,
+                    crash_dirpath,
+                    error
+                );
+            }
+        }
+        previous_hook(panic_info);
+    }));
+}
+
+// Write the crash report to a file of its own. Panics in the same second, such
+// as in other threads, get a suffix like `-002` rather than overwriting the
+// first report.
+fn write_crash_report(crash_dirpath: &Path, crash_report: &str) -> io::Result<PathBuf> {
+    let timestamp = app::filename_timestamp();
+    let mut crash_filepath =
+        crash_dirpath.join(format!("{}-crash-{timestamp}.txt", *app::APP_NAME));
+    for suffix in 2.. {
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&crash_filepath)
+        {
+            Ok(mut crash_file) => {
+                crash_file.write_all(crash_report.as_bytes())?;
+                break;
+            }
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+                crash_filepath = crash_dirpath.join(format!(
+                    "{}-crash-{timestamp}-{suffix:03}.txt",
+                    *app::APP_NAME
+                ));
+            }
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(crash_filepath)
+}
+
+// The crash files written by the panic hook in the given directory, newest
+// first.
+pub fn crash_filepaths(crash_dirpath: &Path) -> Vec<PathBuf> {
+    let crash_filename_prefix = format!("{}-crash-", *app::APP_NAME);
+    let mut crash_filepaths = fs::read_dir(crash_dirpath)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| {
+                    entry
+                        .file_name()
+                        .to_string_lossy()
+                        .starts_with(&crash_filename_prefix)
+                })
+                .map(|entry| entry.path())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    // The timestamps and suffixes in the filenames sort chronologically, once
+    // the extension is left out
+    crash_filepaths.sort_by(|a, b| {
+        a.file_stem()
+            .cmp(&b.file_stem())
+    });
+    crash_filepaths.reverse();
+    crash_filepaths
+}
+
+fn format_crash_report(panic_info: &PanicHookInfo<'_>, config_filepaths: &[PathBuf]) -> String {
+    let panic_message = panic_info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| {
+            panic_info
+                .payload()
+                .downcast_ref::<String>()
+                .cloned()
+        })
+        .unwrap_or_else(|| String::from("<non-string panic payload>"));
+    let panic_location = panic_info
+        .location()
+        .map(|location| location.to_string())
+        .unwrap_or_else(|| String::from("<unknown>"));
+
+    format!(
+        "{} {}\n\
+        \n\
+        Message: {}\n\
+        Location: {}\n\
+        Thread: {}\n\
+        Arguments: {:?}\n\
+        Config Filepath(s): {:?}\n\
+        \n\
+        Backtrace:\n{}\n",
+        *app::APP_NAME,
+        env!("CARGO_PKG_VERSION"),
+        panic_message,
+        panic_location,
+        thread::current()
+            .name()
+            .unwrap_or("<unnamed>"),
+        env::args().collect::<Vec<_>>(),
+        config_filepaths,
+        Backtrace::force_capture(),
+    )
+}
+
+// region: IMPORTS
+
+use std::{
+    backtrace::Backtrace,
+    env,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    panic::{self, PanicHookInfo},
+    path::{Path, PathBuf},
+    thread,
+};
+
+use crate::app;
+
+// endregion: IMPORTS
@@ -0,0 +1,373 @@
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LogRetention {
+    // The maximum number of log files to keep, including the current one. A
+    // value of 0 keeps all log files.
+    pub max_files: usize,
+
+    // The maximum age of a log file in days before it is removed. A value of 0
+    // keeps log files regardless of their age.
+    pub max_age_days: u64,
+
+    // The size in bytes after which the current log file is rotated even
+    // before the day is over. A value of 0 disables size-based rotation.
+    pub max_file_size: u64,
+}
+
+impl Default for LogRetention {
+    fn default() -> Self {
+        LogRetention {
+            max_files: 10,
+            max_age_days: 30,
+            max_file_size: 10 * 1024 * 1024,
+        }
+    }
+}
+
+// A log file appender that rotates daily, and additionally whenever the
+// current log file grows beyond the configured size. Older log files are
+// removed according to the given retention rules, both when the appender is
+// created and whenever it rotates.
+//
+// Log files are named `<prefix>.<YYYY-MM-DD>` for the first file of a day and
+// `<prefix>.<YYYY-MM-DD>.<N>` for each further file of that day.
+//
+// A failure to rotate or to remove expired log files does not lose any log
+// lines: it is reported once on stderr, and the lines keep going to the
+// current log file.
+pub struct RotatingFileAppender {
+    log_dirpath: PathBuf,
+    log_filename_prefix: String,
+    log_retention: LogRetention,
+    current_date: Date,
+    current_index: usize,
+    current_size: u64,
+    current_file: File,
+    is_rotation_error_reported: bool,
+}
+
+impl RotatingFileAppender {
+    pub fn new(
+        log_dirpath: impl Into<PathBuf>,
+        log_filename_prefix: impl Into<String>,
+        log_retention: LogRetention,
+    ) -> Result<Self, Error> {
+        let log_dirpath = log_dirpath.into();
+        let log_filename_prefix = log_filename_prefix.into();
+        let current_date = OffsetDateTime::now_utc().date();
+
+        // Continue appending to the latest log file of the day, if any
+        let current_index = list_log_files(&log_dirpath, &log_filename_prefix)?
+            .into_iter()
+            .filter(|log_file| log_file.date == current_date)
+            .map(|log_file| log_file.index)
+            .max()
+            .unwrap_or(0);
+
+        let appender = {
+            let log_filepath = log_filepath(
+                &log_dirpath,
+                &log_filename_prefix,
+                current_date,
+                current_index,
+            );
+            let current_file = open_log_file(&log_filepath)?;
+            RotatingFileAppender {
+                current_size: current_file
+                    .metadata()
+                    .map(|metadata| metadata.len())
+                    .unwrap_or(0),
+                log_dirpath,
+                log_filename_prefix,
+                log_retention,
+                current_date,
+                current_index,
+                current_file,
+                is_rotation_error_reported: false,
+            }
+        };
+        appender.report_expired_log_files_removal();
+        Ok(appender)
+    }
+
+    pub fn current_log_filepath(&self) -> PathBuf {
+        log_filepath(
+            &self.log_dirpath,
+            &self.log_filename_prefix,
+            self.current_date,
+            self.current_index,
+        )
+    }
+
+    // Switch to the next log file if the day is over or the current one is
+    // full. The current log file is kept if the next one cannot be opened.
+    fn rotate_if_needed(&mut self, incoming_len: usize) -> Result<(), Error> {
+        let today = OffsetDateTime::now_utc().date();
+        let (next_date, next_index) = if today != self.current_date {
+            (today, 0)
+        } else if self
+            .log_retention
+            .max_file_size
+            > 0
+            && self.current_size > 0
+            && self.current_size + incoming_len as u64
+                > self
+                    .log_retention
+                    .max_file_size
+        {
+            (self.current_date, self.current_index + 1)
+        } else {
+            return Ok(());
+        };
+
+        let next_file = open_log_file(&log_filepath(
+            &self.log_dirpath,
+            &self.log_filename_prefix,
+            next_date,
+            next_index,
+        ))?;
+        self.current_file
+            .flush()
+            .ok();
+        self.current_date = next_date;
+        self.current_index = next_index;
+        self.current_file = next_file;
+        self.current_size = self
+            .current_file
+            .metadata()
+            .map(|metadata| metadata.len())
+            .unwrap_or(0);
+        self.report_expired_log_files_removal();
+        Ok(())
+    }
+
+    // An expired log file that cannot be removed, such as one of another user,
+    // is no reason to stop logging
+    fn report_expired_log_files_removal(&self) {
+        // The log itself cannot report this, since it is still being set up
+        // or in the middle of a write
+        if let Err(error) = self.remove_expired_log_files() {
+            anstream::eprintln!("Could not remove the expired log files: {}", error);
+        }
+    }
+
+    fn remove_expired_log_files(&self) -> Result<(), Error> {
+        let current_log_filepath = self.current_log_filepath();
+        let mut log_files = list_log_files(&self.log_dirpath, &self.log_filename_prefix)?
+            .into_iter()
+            .filter(|log_file| log_file.path != current_log_filepath)
+            .collect::<Vec<_>>();
+
+        // Newest first
+        log_files.sort_by_key(|log_file| Reverse((log_file.date, log_file.index)));
+
+        let oldest_permitted_date = (self
+            .log_retention
+            .max_age_days
+            > 0)
+        .then(|| {
+            self.current_date
+                - time::Duration::days(
+                    self.log_retention
+                        .max_age_days
+                        .min(i64::MAX as u64) as i64,
+                )
+        });
+        // The current log file counts towards the maximum number of files
+        let max_other_files = match self
+            .log_retention
+            .max_files
+        {
+            0 => usize::MAX,
+            max_files => max_files - 1,
+        };
+
+        // The other expired files are still removed if one of them cannot be
+        let mut result = Ok(());
+        for (position, log_file) in log_files
+            .into_iter()
+            .enumerate()
+        {
+            let is_too_old = oldest_permitted_date.is_some_and(|date| log_file.date < date);
+            if is_too_old || position >= max_other_files {
+                let removal = fs::remove_file(&log_file.path).context(RemoveLogFileSnafu {
+                    path: log_file
+                        .path
+                        .clone(),
+                });
+                if result.is_ok() {
+                    result = removal;
+                }
+            }
+        }
+        result
+    }
+}
+
+impl Write for RotatingFileAppender {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // The log itself cannot report this, since it is the log that failed
+        if let Err(error) = self.rotate_if_needed(buf.len()) {
+            if !self.is_rotation_error_reported {
+                self.is_rotation_error_reported = true;
+                anstream::eprintln!(
+                    "Could not rotate the log files, so the log continues in {:?}: {}",
+                    self.current_log_filepath(),
+                    error
+                );
+            }
+        }
+        let written_len = self
+            .current_file
+            .write(buf)?;
+        self.current_size += written_len as u64;
+        Ok(written_len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.current_file
+            .flush()
+    }
+}
+
+// The log files in the log directory with the given filename prefix, newest
+// first.
+pub fn list_log_filepaths(
+    log_dirpath: &Path,
+    log_filename_prefix: &str,
+) -> Result<Vec<PathBuf>, Error> {
+    let mut log_files = list_log_files(log_dirpath, log_filename_prefix)?;
+    log_files.sort_by_key(|log_file| Reverse((log_file.date, log_file.index)));
+    Ok(log_files
+        .into_iter()
+        .map(|log_file| log_file.path)
+        .collect())
+}
+
+struct LogFile {
+    path: PathBuf,
+    date: Date,
+    index: usize,
+}
+
+fn log_filepath(
+    log_dirpath: &Path,
+    log_filename_prefix: &str,
+    date: Date,
+    index: usize,
+) -> PathBuf {
+    let date_string = format!(
+        "{:04}-{:02}-{:02}",
+        date.year(),
+        u8::from(date.month()),
+        date.day()
+    );
+    log_dirpath.join(match index {
+        0 => format!("{log_filename_prefix}.{date_string}"),
+        index => format!("{log_filename_prefix}.{date_string}.{index}"),
+    })
+}
+
+fn open_log_file(log_filepath: &Path) -> Result<File, Error> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_filepath)
+        .context(OpenLogFileSnafu {
+            path: log_filepath.to_owned(),
+        })
+}
+
+// Find the log files in the log directory that were created by this appender,
+// by parsing their filenames.
+fn list_log_files(log_dirpath: &Path, log_filename_prefix: &str) -> Result<Vec<LogFile>, Error> {
+    let entries = fs::read_dir(log_dirpath).context(ReadLogDirectorySnafu {
+        path: log_dirpath.to_owned(),
+    })?;
+
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let filename = entry
+                .file_name()
+                .into_string()
+                .ok()?;
+            let suffix = filename.strip_prefix(&format!("{log_filename_prefix}."))?;
+            let mut parts = suffix.splitn(2, '.');
+            let date = parse_date(parts.next()?)?;
+            let index = match parts.next() {
+                Some(index) => index.parse().ok()?,
+                None => 0,
+            };
+            Some(LogFile {
+                path: entry.path(),
+                date,
+                index,
+            })
+        })
+        .collect())
+}
+
+fn parse_date(date_string: &str) -> Option<Date> {
+    let mut parts = date_string.splitn(3, '-');
+    let year = parts
+        .next()?
+        .parse()
+        .ok()?;
+    let month: u8 = parts
+        .next()?
+        .parse()
+        .ok()?;
+    let day = parts
+        .next()?
+        .parse()
+        .ok()?;
+    Date::from_calendar_date(year, Month::try_from(month).ok()?, day).ok()
+}
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(
+        display("could not read the log directory at {:?}: {source}", path),
+        visibility(pub)
+    )]
+    ReadLogDirectory {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not open the log file at {:?}: {source}", path),
+        visibility(pub)
+    )]
+    OpenLogFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not remove the expired log file at {:?}: {source}", path),
+        visibility(pub)
+    )]
+    RemoveLogFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+// region: IMPORTS
+
+use std::{
+    cmp::Reverse,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use snafu::{ResultExt, Snafu};
+use time::{Date, Month, OffsetDateTime};
+
+// endregion: IMPORTS
@@ -0,0 +1,145 @@
+// Follows the newest log file in the log directory, like `tail -f`, and moves
+// on to the next log file when the log files are rotated.
+pub struct LogTail {
+    log_dirpath: PathBuf,
+    log_filename_prefix: String,
+    current_log_filepath: Option<PathBuf>,
+    offset: u64,
+}
+
+impl LogTail {
+    pub fn new(log_dirpath: impl Into<PathBuf>, log_filename_prefix: impl Into<String>) -> Self {
+        LogTail {
+            log_dirpath: log_dirpath.into(),
+            log_filename_prefix: log_filename_prefix.into(),
+            current_log_filepath: None,
+            offset: 0,
+        }
+    }
+
+    pub fn current_log_filepath(&self) -> Option<&Path> {
+        self.current_log_filepath
+            .as_deref()
+    }
+
+    // The complete lines written since the last call, without ANSI escape
+    // sequences. The first call only returns the end of the current log file.
+    pub fn read_new_lines(&mut self) -> Result<Vec<String>, Error> {
+        let Some(newest_log_filepath) =
+            log_rotation::list_log_filepaths(&self.log_dirpath, &self.log_filename_prefix)
+                .context(ListLogFilesSnafu {})?
+                .into_iter()
+                .next()
+        else {
+            return Ok(vec![]);
+        };
+
+        let mut log_file = File::open(&newest_log_filepath).context(ReadLogFileSnafu {
+            path: newest_log_filepath.clone(),
+        })?;
+        let log_file_size = log_file
+            .metadata()
+            .context(ReadLogFileSnafu {
+                path: newest_log_filepath.clone(),
+            })?
+            .len();
+        match &self.current_log_filepath {
+            // Start a rotated-in log file from the beginning
+            Some(current_log_filepath) if *current_log_filepath != newest_log_filepath => {
+                self.offset = 0
+            }
+            Some(_) => {}
+            None => self.offset = log_file_size.saturating_sub(MAX_INITIAL_BYTES),
+        }
+        // The log file was truncated or replaced
+        if log_file_size < self.offset {
+            self.offset = 0;
+        }
+        self.current_log_filepath = Some(newest_log_filepath.clone());
+
+        let mut buf = Vec::new();
+        log_file
+            .seek(SeekFrom::Start(self.offset))
+            .and_then(|_| log_file.read_to_end(&mut buf))
+            .context(ReadLogFileSnafu {
+                path: newest_log_filepath,
+            })?;
+        // Leave an incomplete last line for the next call
+        let Some(complete_len) = buf
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+            .map(|position| position + 1)
+        else {
+            return Ok(vec![]);
+        };
+        self.offset += complete_len as u64;
+
+        Ok(
+            String::from_utf8_lossy(&logging::strip_ansi(&buf[..complete_len]))
+                .lines()
+                .map(String::from)
+                .collect(),
+        )
+    }
+}
+
+// The level of a log line, for both the pretty and the JSON log file formats.
+// Lines that continue a multi-line record have no level of their own.
+pub fn log_line_level(line: &str) -> Option<Level> {
+    if let Some((_, rest)) = line.split_once("\"level\":\"") {
+        return rest
+            .split('"')
+            .next()?
+            .parse()
+            .ok();
+    }
+    // `<timestamp> <LEVEL> <target>: <message>`
+    let mut words = line.split_whitespace();
+    let timestamp = words.next()?;
+    if !timestamp
+        .chars()
+        .next()?
+        .is_ascii_digit()
+    {
+        return None;
+    }
+    words
+        .next()?
+        .parse()
+        .ok()
+}
+
+const MAX_INITIAL_BYTES: u64 = 256 * 1024;
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(display("could not list the log files: {source}"), visibility(pub))]
+    ListLogFiles { source: log_rotation::Error },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not read the log file at {:?}: {source}", path),
+        visibility(pub)
+    )]
+    ReadLogFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+// region: IMPORTS
+
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use snafu::{ResultExt, Snafu};
+use tracing::Level;
+
+use crate::app::{log_rotation, logging};
+
+// endregion: IMPORTS
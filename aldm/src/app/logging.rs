@@ -1,124 +1,99 @@
 pub fn init_log(
     preferred_log_dirpath: Option<PathBuf>,
     preferred_log_level_filter: Option<LevelFilter>,
+    log_directives: Option<&str>,
+    log_file_format: LogFileFormat,
+    is_log_file_ansi: bool,
+    log_retention: LogRetention,
 ) -> Result<(Handle, PathBuf), Error> {
     let log_filename = format!("{}.log", *app::APP_NAME);
     let log_dirpath = obtain_log_dirpath(preferred_log_dirpath)?;
-    let log_file_appender =
-        tracing_appender::rolling::daily(log_dirpath.clone(), log_filename.clone());
+    let log_file_appender = RotatingFileAppender::new(log_dirpath, log_filename, log_retention)
+        .context(CreateLogFileAppenderSnafu {})?;
+    let log_filepath = log_file_appender.current_log_filepath();
     let log_level_filter = preferred_log_level_filter.unwrap_or(LevelFilter::INFO);
+    // Per-target verbosity directives, like `aldm::probe=trace,aldm::actions=info`
+    let log_directives = log_directives
+        .map(|log_directives| {
+            log_directives
+                .parse::<Targets>()
+                .context(ParseLogDirectivesSnafu {
+                    log_directives: log_directives.to_owned(),
+                })
+        })
+        .transpose()?;
 
     // Obtain writers to various logging destinations and worker guards (for
     // keeping the streams alive)
-    let (non_blocking_file_writer, _file_writer_guard) =
-        tracing_appender::non_blocking(log_file_appender);
+    let (non_blocking_file_writer, _file_writer_guard) = if is_log_file_ansi {
+        tracing_appender::non_blocking(log_file_appender)
+    } else {
+        tracing_appender::non_blocking(StripAnsiWriter::new(log_file_appender))
+    };
     let (non_blocking_stdout_writer, _stdout_writer_guard) =
         tracing_appender::non_blocking(anstream::stdout());
     let (non_blocking_stderr_writer, _stderr_writer_guard) =
         tracing_appender::non_blocking(anstream::stderr());
 
-    // Declare filtering rules for various logging destinations
-    // In Regular mode, for stdout, permit messages of equal or lower verbosity
-    // than the given filter level, permit messages of higher verbosity than
-    // 'WARN', and omit PLAIN target, JSON target, and TEST target.
-    let filter_stdout_regular = move |metadata: &Metadata<'_>| {
-        metadata.level() <= &log_level_filter
-            && metadata.level() > &Level::WARN
-            && metadata.target() != "PLAIN"
-            && metadata.target() != "JSON"
-            && metadata.target() != "TEST"
-    };
-    // In Test mode, for stdout, permit messages of equal or lower verbosity
-    // than the given filter level, permit messages of higher verbosity than
-    // 'WARN', and permit all target messages.
-    let filter_stdout_test = move |metadata: &Metadata<'_>| {
-        metadata.level() <= &log_level_filter && metadata.level() > &Level::WARN
-    };
-    // In Plain mode, for stdout, print only 'INFO' messages, and permit only PLAIN target messages.
-    let filter_stdout_plain = move |metadata: &Metadata<'_>| {
-        metadata.level() == &Level::INFO && metadata.target() == "PLAIN"
-    };
-    // In Json mode, for stdout, print only 'INFO' messages, and permit only JSON target messages.
-    let filter_stdout_json = move |metadata: &Metadata<'_>| {
-        metadata.level() == &Level::INFO && metadata.target() == "JSON"
-    };
-    // In Regular mode, for stderr, permit messages of equal or lower verbosity
-    // than 'WARN', and permit all targets except TEST.
-    let filter_stderr_regular = move |metadata: &Metadata<'_>| {
-        metadata.level() < &Level::INFO && metadata.target() != "TEST"
-    };
-    // In Test mode, for stderr, permit messages of equal or lower verbosity
-    // than 'WARN', and permit all targets.
-    let filter_stderr_test = move |metadata: &Metadata<'_>| metadata.level() < &Level::INFO;
-    // Box the closure to allow for type match when switching between two similar closures.
-    let stdout_regular_filter: FilterFn<Box<dyn Fn(&Metadata<'_>) -> bool + Send + Sync>> =
-        filter_fn(Box::new(filter_stdout_regular));
-    // Box the closure to allow for type match when switching between two similar closures.
-    let stdout_test_filter: FilterFn<Box<dyn Fn(&Metadata<'_>) -> bool + Send + Sync>> =
-        filter_fn(Box::new(filter_stdout_test));
-    // Box the closure to allow for type match when switching between two similar closures.
-    let stdout_plain_filter: FilterFn<Box<dyn Fn(&Metadata<'_>) -> bool + Send + Sync>> =
-        filter_fn(Box::new(filter_stdout_plain));
-    // Box the closure to allow for type match when switching between two similar closures.
-    let stdout_json_filter: FilterFn<Box<dyn Fn(&Metadata<'_>) -> bool + Send + Sync>> =
-        filter_fn(Box::new(filter_stdout_json));
-    // Box the closure to allow for type match when switching between two similar closures.
-    let stderr_regular_filter: FilterFn<Box<dyn Fn(&Metadata<'_>) -> bool + Send + Sync>> =
-        filter_fn(Box::new(filter_stderr_regular));
-    // Box the closure to allow for type match when switching between two similar closures.
-    let stderr_test_filter: FilterFn<Box<dyn Fn(&Metadata<'_>) -> bool + Send + Sync>> =
-        filter_fn(Box::new(filter_stderr_test));
-    // Wrap the filter in reload::Layer and obtain handle to allow switching between filters.
-    let (stdout_filter, stdout_filter_reload_handle) = reload::Layer::new(stdout_regular_filter);
-    let (stderr_filter, stderr_filter_reload_handle) = reload::Layer::new(stderr_regular_filter);
-
-    // Closure to switch to non-standard logging for stdout, in json mode or plain mode
-    let switch_stdout = move |logging_mode: LoggingMode| match logging_mode {
-        LoggingMode::Test => stdout_filter_reload_handle
-            .modify(
-                |filter: &mut FilterFn<Box<dyn Fn(&Metadata<'_>) -> bool + Send + Sync>>| {
-                    *filter = stdout_test_filter
-                },
-            )
-            .context(SwitchToTestSnafu {}),
-        LoggingMode::Plain => stdout_filter_reload_handle
-            .modify(
-                |filter: &mut FilterFn<Box<dyn Fn(&Metadata<'_>) -> bool + Send + Sync>>| {
-                    *filter = stdout_plain_filter
-                },
-            )
-            .context(SwitchToPlainSnafu {}),
-        LoggingMode::Json => stdout_filter_reload_handle
-            .modify(
-                |filter: &mut FilterFn<Box<dyn Fn(&Metadata<'_>) -> bool + Send + Sync>>| {
-                    *filter = stdout_json_filter
-                },
-            )
-            .context(SwitchToJsonSnafu {}),
-        LoggingMode::Regular => Ok(()),
+    // Wrap the filters in reload::Layer and obtain handles to allow switching
+    // between filters any number of times.
+    let (stdout_filter, stdout_filter_reload_handle) = reload::Layer::new(obtain_stdout_filter(
+        LoggingMode::Regular,
+        obtain_log_targets(log_directives.clone(), log_level_filter),
+    ));
+    let (stderr_filter, stderr_filter_reload_handle) =
+        reload::Layer::new(obtain_stderr_filter(LoggingMode::Regular));
+
+    // Closure to switch the stdout filter to the given output mode and
+    // verbosity. The per-target directives are kept across switches.
+    let stdout_log_directives = log_directives.clone();
+    let switch_stdout = move |logging_mode: LoggingMode, log_level_filter: LevelFilter| {
+        stdout_filter_reload_handle.reload(obtain_stdout_filter(
+            logging_mode,
+            obtain_log_targets(stdout_log_directives.clone(), log_level_filter),
+        ))
     };
-    // Closure to switch to non-standard logging for stderr, in json mode or plain mode
-    let switch_stderr = move |logging_mode: LoggingMode| match logging_mode {
-        LoggingMode::Test => stderr_filter_reload_handle
-            .modify(
-                |filter: &mut FilterFn<Box<dyn Fn(&Metadata<'_>) -> bool + Send + Sync>>| {
-                    *filter = stderr_test_filter
-                },
-            )
-            .context(SwitchToTestSnafu {}),
-        LoggingMode::Regular | LoggingMode::Plain | LoggingMode::Json => Ok(()),
+    // Closure to switch the stderr filter to the given output mode. The
+    // verbosity of stderr does not depend on the level filter.
+    let switch_stderr = move |logging_mode: LoggingMode, _log_level_filter: LevelFilter| {
+        stderr_filter_reload_handle.reload(obtain_stderr_filter(logging_mode))
     };
 
-    // Declare logging formats for various logging destinations
-    let log_file_layer = fmt::Layer::new()
-        .pretty()
-        .with_ansi(true)
-        .with_file(true)
-        .with_level(true)
-        .with_line_number(true)
-        .with_target(true)
-        .with_writer(non_blocking_file_writer)
-        .with_filter(LevelFilter::TRACE);
+    // Declare logging formats for various logging destinations. Only one of
+    // the log file layers is present, depending on the log file format.
+    let log_file_targets = obtain_log_targets(log_directives, LevelFilter::TRACE);
+    let (pretty_log_file_layer, json_log_file_layer) = match log_file_format {
+        LogFileFormat::Pretty => (
+            Some(
+                fmt::Layer::new()
+                    .pretty()
+                    .with_ansi(is_log_file_ansi)
+                    .with_file(true)
+                    .with_level(true)
+                    .with_line_number(true)
+                    .with_target(true)
+                    .with_writer(non_blocking_file_writer)
+                    .with_filter(log_file_targets),
+            ),
+            None,
+        ),
+        LogFileFormat::Json => (
+            None,
+            Some(
+                fmt::Layer::new()
+                    .json()
+                    .with_current_span(true)
+                    .with_span_list(true)
+                    .with_ansi(is_log_file_ansi)
+                    .with_file(true)
+                    .with_level(true)
+                    .with_line_number(true)
+                    .with_target(true)
+                    .with_writer(non_blocking_file_writer)
+                    .with_filter(log_file_targets),
+            ),
+        ),
+    };
     let stdout_layer = fmt::Layer::new()
         .with_ansi(true)
         .with_file(false)
@@ -140,7 +115,8 @@ pub fn init_log(
 
     // Compose various filtered logging destination layers and set them to receive tracing messages
     let subscriber = tracing_subscriber::registry()
-        .with(log_file_layer)
+        .with(pretty_log_file_layer)
+        .with(json_log_file_layer)
         .with(stdout_layer)
         .with(stderr_layer);
     tracing::subscriber::set_global_default(subscriber)
@@ -148,24 +124,102 @@ pub fn init_log(
 
     Ok((
         Handle {
-            _switch_stdout_inner: Some(Box::new(switch_stdout)),
-            _switch_stderr_inner: Some(Box::new(switch_stderr)),
+            switch_stdout_inner: Box::new(switch_stdout),
+            switch_stderr_inner: Box::new(switch_stderr),
+            logging_mode: LoggingMode::Regular,
+            log_level_filter,
             worker_guards: vec![
                 _file_writer_guard,
                 _stdout_writer_guard,
                 _stderr_writer_guard,
             ],
         },
-        {
-            let mut log_filepath = PathBuf::from(log_dirpath);
-            log_filepath.push(log_filename + "*");
-            log_filepath
-        },
+        log_filepath,
     ))
 }
 
+// Combine the per-target directives, if any, with the given level filter. The
+// level filter applies to all targets not covered by the directives, unless
+// the directives specify a default level of their own.
+fn obtain_log_targets(log_directives: Option<Targets>, log_level_filter: LevelFilter) -> Targets {
+    match log_directives {
+        Some(log_directives)
+            if log_directives
+                .default_level()
+                .is_some() =>
+        {
+            log_directives
+        }
+        Some(log_directives) => log_directives.with_default(log_level_filter),
+        None => Targets::new().with_default(log_level_filter),
+    }
+}
+
+// Build the stdout filter for the given output mode and verbosity.
+fn obtain_stdout_filter(logging_mode: LoggingMode, log_targets: Targets) -> BoxedFilterFn {
+    match logging_mode {
+        // In Regular mode, for stdout, permit messages of equal or lower
+        // verbosity than the given filter level (or the matching per-target
+        // directive), permit messages of higher
+        // verbosity than 'WARN', and omit PLAIN target, JSON target, and TEST
+        // target.
+        LoggingMode::Regular => filter_fn(Box::new(move |metadata: &Metadata<'_>| {
+            log_targets.would_enable(metadata.target(), metadata.level())
+                && metadata.level() > &Level::WARN
+                && metadata.target() != "PLAIN"
+                && metadata.target() != "JSON"
+                && metadata.target() != "TEST"
+        })),
+        // In Test mode, for stdout, permit messages of equal or lower verbosity
+        // than the given filter level, permit messages of higher verbosity than
+        // 'WARN', and permit all target messages.
+        LoggingMode::Test => filter_fn(Box::new(move |metadata: &Metadata<'_>| {
+            log_targets.would_enable(metadata.target(), metadata.level())
+                && metadata.level() > &Level::WARN
+        })),
+        // In Plain mode, for stdout, print only 'INFO' messages, and permit
+        // only PLAIN target messages.
+        LoggingMode::Plain => filter_fn(Box::new(|metadata: &Metadata<'_>| {
+            metadata.level() == &Level::INFO && metadata.target() == "PLAIN"
+        })),
+        // In Json mode, for stdout, print only 'INFO' messages, and permit only
+        // JSON target messages.
+        LoggingMode::Json => filter_fn(Box::new(|metadata: &Metadata<'_>| {
+            metadata.level() == &Level::INFO && metadata.target() == "JSON"
+        })),
+    }
+}
+
+// Build the stderr filter for the given output mode.
+fn obtain_stderr_filter(logging_mode: LoggingMode) -> BoxedFilterFn {
+    match logging_mode {
+        // In Test mode, for stderr, permit messages of equal or lower verbosity
+        // than 'WARN', and permit all targets.
+        LoggingMode::Test => filter_fn(Box::new(|metadata: &Metadata<'_>| {
+            metadata.level() < &Level::INFO
+        })),
+        // In the other modes, for stderr, permit messages of equal or lower
+        // verbosity than 'WARN', and permit all targets except TEST.
+        LoggingMode::Regular | LoggingMode::Plain | LoggingMode::Json => {
+            filter_fn(Box::new(|metadata: &Metadata<'_>| {
+                metadata.level() < &Level::INFO && metadata.target() != "TEST"
+            }))
+        }
+    }
+}
+
 fn obtain_log_dirpath(preferred_log_dirpath: Option<PathBuf>) -> Result<PathBuf, Error> {
     let obtain_fallback_log_dirpath = || {
+        // Log system-wide when running as root, instead of into root's own
+        // state directory
+        if Uid::effective().is_root() {
+            let system_log_dirpath = PathBuf::from(format!("/var/log/{}", *app::APP_NAME));
+            return fs::create_dir_all(&system_log_dirpath)
+                .context(CreateLogDirectorySnafu {
+                    path: system_log_dirpath.clone(),
+                })
+                .map(|_| system_log_dirpath);
+        }
         let xdg_app_dirs = xdg::BaseDirectories::with_prefix(*app::APP_NAME)
             .context(RetreiveLoggingUserAppBaseDirectoriesSnafu {})?;
         xdg_app_dirs
@@ -190,13 +244,30 @@ fn obtain_log_dirpath(preferred_log_dirpath: Option<PathBuf>) -> Result<PathBuf,
     })
 }
 
+// A filter that is evaluated for every log message. The closure is boxed to
+// allow for type match when switching between similar closures.
+type BoxedFilterFn = FilterFn<Box<dyn Fn(&Metadata<'_>) -> bool + Send + Sync>>;
+
+// A function that reloads a filter to match the given output mode and
+// verbosity.
+type SwitchFn = Box<dyn Fn(LoggingMode, LevelFilter) -> Result<(), reload::Error> + Send + Sync>;
+
+// A handle to the running logger that allows switching the output mode and
+// the verbosity any number of times, for example from long-lived frontends
+// like the GUI.
 pub struct Handle {
-    _switch_stdout_inner: Option<Box<dyn FnOnce(LoggingMode) -> Result<(), Error>>>,
-    _switch_stderr_inner: Option<Box<dyn FnOnce(LoggingMode) -> Result<(), Error>>>,
+    switch_stdout_inner: SwitchFn,
+    switch_stderr_inner: SwitchFn,
+    logging_mode: LoggingMode,
+    log_level_filter: LevelFilter,
     pub worker_guards: Vec<WorkerGuard>,
 }
 
 impl Handle {
+    pub fn switch_to_regular(&mut self) -> Result<(), Error> {
+        self.switch_output_mode(LoggingMode::Regular)
+    }
+
     pub fn switch_to_test(&mut self) -> Result<(), Error> {
         self.switch_output_mode(LoggingMode::Test)
     }
@@ -209,22 +280,107 @@ impl Handle {
         self.switch_output_mode(LoggingMode::Json)
     }
 
-    fn switch_output_mode(&mut self, logging_mode: LoggingMode) -> Result<(), Error> {
-        _ = self
-            ._switch_stdout_inner
-            .take()
-            .map(|function_handle| function_handle(logging_mode))
-            .ok_or(Error::SwitchFnNotAssigned {})?;
-        _ = self
-            ._switch_stderr_inner
-            .take()
-            .map(|function_handle| function_handle(logging_mode))
-            .ok_or(Error::SwitchFnNotAssigned {})?;
+    pub fn switch_output_mode(&mut self, logging_mode: LoggingMode) -> Result<(), Error> {
+        self.reload(logging_mode, self.log_level_filter)
+            .map_err(|source| match logging_mode {
+                LoggingMode::Regular => Error::SwitchToRegular { source },
+                LoggingMode::Test => Error::SwitchToTest { source },
+                LoggingMode::Plain => Error::SwitchToPlain { source },
+                LoggingMode::Json => Error::SwitchToJson { source },
+            })?;
+        self.logging_mode = logging_mode;
+        Ok(())
+    }
+
+    pub fn switch_level_filter(&mut self, log_level_filter: LevelFilter) -> Result<(), Error> {
+        self.reload(self.logging_mode, log_level_filter)
+            .context(SwitchLevelFilterSnafu { log_level_filter })?;
+        self.log_level_filter = log_level_filter;
         Ok(())
     }
+
+    pub fn output_mode(&self) -> LoggingMode {
+        self.logging_mode
+    }
+
+    pub fn level_filter(&self) -> LevelFilter {
+        self.log_level_filter
+    }
+
+    fn reload(
+        &self,
+        logging_mode: LoggingMode,
+        log_level_filter: LevelFilter,
+    ) -> Result<(), reload::Error> {
+        (self.switch_stdout_inner)(logging_mode, log_level_filter)?;
+        (self.switch_stderr_inner)(logging_mode, log_level_filter)
+    }
+}
+
+// A writer that removes ANSI escape sequences before passing the output on.
+// Besides raw escape sequences, this also removes the escaped forms (`\x1b[...`
+// and `\u001b[...`) of escape sequences that were embedded in log messages,
+// since the formatters escape them instead of passing them through.
+struct StripAnsiWriter<W> {
+    inner: W,
+}
+
+impl<W> StripAnsiWriter<W> {
+    fn new(inner: W) -> Self {
+        StripAnsiWriter { inner }
+    }
+}
+
+impl<W: Write> Write for StripAnsiWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner
+            .write_all(&strip_ansi(buf))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
 }
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+// Remove ANSI escape sequences, including the escaped forms described above
+pub fn strip_ansi(buf: &[u8]) -> Vec<u8> {
+    const SEQUENCE_STARTS: [&[u8]; 3] = [b"\x1b[", b"\\x1b[", b"\\u001b["];
+    let mut stripped = Vec::with_capacity(buf.len());
+    let mut position = 0;
+    while position < buf.len() {
+        let Some(sequence_start) = SEQUENCE_STARTS
+            .iter()
+            .find(|sequence_start| buf[position..].starts_with(sequence_start))
+        else {
+            stripped.push(buf[position]);
+            position += 1;
+            continue;
+        };
+        // Skip the parameters and intermediate bytes up to and including the
+        // final byte of the control sequence
+        position += sequence_start.len();
+        while position < buf.len() && !(0x40..=0x7e).contains(&buf[position]) {
+            position += 1;
+        }
+        position += 1;
+    }
+    stripped
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LogFileFormat {
+    // Human-readable, multi-line records
+    #[default]
+    #[serde(alias = "pretty")]
+    Pretty,
+
+    // One JSON object per line, including the span context
+    #[serde(alias = "json")]
+    Json,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LoggingMode {
     Regular,
     Test,
@@ -252,6 +408,23 @@ pub enum Error {
         source: std::io::Error,
     },
 
+    #[non_exhaustive]
+    #[snafu(
+        display("could not parse the log directives '{log_directives}': {source}"),
+        visibility(pub)
+    )]
+    ParseLogDirectives {
+        log_directives: String,
+        source: tracing_subscriber::filter::ParseError,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not create the log file appender: {source}"),
+        visibility(pub)
+    )]
+    CreateLogFileAppender { source: log_rotation::Error },
+
     #[non_exhaustive]
     #[snafu(
         display("could not set the global default tracing subscriber: {source}"),
@@ -261,6 +434,15 @@ pub enum Error {
         source: tracing::subscriber::SetGlobalDefaultError,
     },
 
+    #[non_exhaustive]
+    #[snafu(
+        display("could not switch to the Regular output format: {source}"),
+        visibility(pub)
+    )]
+    SwitchToRegular {
+        source: tracing_subscriber::reload::Error,
+    },
+
     #[non_exhaustive]
     #[snafu(
         display("could not switch to the Test output format: {source}"),
@@ -290,25 +472,34 @@ pub enum Error {
 
     #[non_exhaustive]
     #[snafu(
-        display(
-            "The function/closure to switch the output mode has not been assigned. This is a bug."
-        ),
+        display("could not switch to the verbosity '{log_level_filter}': {source}"),
         visibility(pub)
     )]
-    SwitchFnNotAssigned {},
+    SwitchLevelFilter {
+        log_level_filter: LevelFilter,
+        source: tracing_subscriber::reload::Error,
+    },
 }
 
 // region: IMPORTS
 
-use std::path::PathBuf;
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
 
-use crate::app;
+use crate::app::{
+    self,
+    log_rotation::{self, LogRetention, RotatingFileAppender},
+};
+use nix::unistd::Uid;
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
 use tracing::{Level, Metadata};
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_subscriber::{
-    filter::{filter_fn, FilterFn, LevelFilter},
+    filter::{filter_fn, FilterFn, LevelFilter, Targets},
     fmt,
     layer::SubscriberExt,
     reload, Layer,
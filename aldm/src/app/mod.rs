@@ -62,6 +62,20 @@ pub fn all_writable_paths<'a>(
         .filter(|p| permissions::is_writable(p).unwrap_or(false))
 }
 
+// A UTC timestamp that is safe to use in filenames and sorts chronologically
+pub fn filename_timestamp() -> String {
+    let now = OffsetDateTime::now_utc();
+    format!(
+        "{:04}-{:02}-{:02}T{:02}-{:02}-{:02}Z",
+        now.year(),
+        u8::from(now.month()),
+        now.day(),
+        now.hour(),
+        now.minute(),
+        now.second()
+    )
+}
+
 #[derive(Debug, Snafu)]
 #[non_exhaustive]
 pub enum Error {
@@ -92,13 +106,17 @@ pub enum Error {
 use lazy_static::lazy_static;
 use snafu::Snafu;
 use std::{iter, path::Path};
+use time::OffsetDateTime;
 
 // endregion: IMPORTS
 
 // region: MODULES
 
 pub mod config;
+pub mod crash;
 pub mod i18n;
+pub mod log_rotation;
+pub mod log_tail;
 pub mod logging;
 
 // endregion: MODULES
@@ -106,7 +124,10 @@ pub mod logging;
 // region: RE-EXPORTS
 
 pub use config::*;
+pub use crash::*;
 pub use i18n::*;
+pub use log_rotation::*;
+pub use log_tail::*;
 pub use logging::*;
 
 // endregion: RE-EXPORTS
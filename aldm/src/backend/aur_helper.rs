@@ -0,0 +1,248 @@
+// Installs packages from both the repositories and the Arch User Repository
+// (AUR) with an AUR helper like paru or yay. Both take pacman's options and
+// elevate themselves for the steps that need root. AUR packages must not be
+// built as root, so when aldm runs as root, the helper is run as the user who
+// elevated aldm, in the environment of that user.
+#[derive(Clone, Debug)]
+pub struct AurHelper {
+    program: String,
+    arguments: Vec<String>,
+    build_user: Option<User>,
+}
+
+impl AurHelper {
+    // Find a known helper in the PATH, preferring paru over yay
+    pub fn detect() -> Result<Self, Error> {
+        let program = KNOWN_AUR_HELPERS
+            .iter()
+            .find(|program| is_in_path(program))
+            .context(NoAurHelperSnafu {})?;
+        AurHelper::from_command_line(program)
+    }
+
+    // A helper given as a command line, like `paru --skipreview`
+    pub fn from_command_line(command_line: &str) -> Result<Self, Error> {
+        let mut words = command_line
+            .split_whitespace()
+            .map(|word| word.to_owned());
+        let program = words
+            .next()
+            .context(InvalidAurHelperSnafu { command_line })?;
+        let build_user = match Uid::effective().is_root() {
+            true => Some(build_user()?),
+            false => None,
+        };
+        Ok(AurHelper {
+            program,
+            arguments: words.collect(),
+            build_user,
+        })
+    }
+
+    fn command(&self) -> Command {
+        let mut command = match &self.build_user {
+            // The environment of root would have the helper write its cache
+            // and clones to the home of root, which the build user cannot
+            Some(build_user) => {
+                let mut command = Command::new("runuser");
+                command
+                    .args(["-u", &build_user.name, "--"])
+                    .arg(&self.program)
+                    .env("HOME", &build_user.dir)
+                    .env("USER", &build_user.name)
+                    .env("LOGNAME", &build_user.name);
+                command
+            }
+            None => Command::new(&self.program),
+        };
+        command.args(&self.arguments);
+        // Without a terminal, sudo cannot ask for a password, so the known
+        // helpers are told to ask polkit instead. As root, aldm was
+        // authenticated already, and the helper is let through sudo without
+        // asking again.
+        if self
+            .build_user
+            .is_none()
+            && !io::stdin().is_terminal()
+            && KNOWN_AUR_HELPERS.contains(
+                &Path::new(&self.program)
+                    .file_name()
+                    .and_then(|file_name| file_name.to_str())
+                    .unwrap_or_default(),
+            )
+        {
+            command.args(["--sudo", "pkexec"]);
+        }
+        command
+    }
+}
+
+impl PackageBackend for AurHelper {
+    fn name(&self) -> &str {
+        &self.program
+    }
+
+    fn supports_aur(&self) -> bool {
+        true
+    }
+
+    fn query_installed(&self) -> Result<BTreeMap<String, Version>, Error> {
+        let mut command = self.command();
+        command.arg("-Q");
+        Ok(parse_package_versions(&run_query(command)?))
+    }
+
+    fn query_available(&self, packages: &[String]) -> Result<BTreeMap<String, Version>, Error> {
+        if packages.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+        let mut command = self.command();
+        command
+            .arg("-Si")
+            .args(packages);
+        Ok(parse_package_info(&run_query(command)?))
+    }
+
+    fn install(
+        &self,
+        packages: &[String],
+        progress: &dyn ProgressSink,
+        cancellation_token: &CancellationToken,
+    ) -> Result<StepOutcome, Error> {
+        let _pacman_grant = self
+            .build_user
+            .as_ref()
+            .map(PacmanGrant::new)
+            .transpose()?;
+        let mut command = self.command();
+        command
+            .args(["-S", "--needed", "--noconfirm"])
+            .args(packages);
+        run_streaming(command, Phase::Installing, progress, cancellation_token)
+    }
+
+    // Removing never involves the AUR, so it is left to pacman
+    fn remove(
+        &self,
+        packages: &[String],
+        progress: &dyn ProgressSink,
+        cancellation_token: &CancellationToken,
+    ) -> Result<StepOutcome, Error> {
+        Pacman::new().remove(packages, progress, cancellation_token)
+    }
+}
+
+// Lets the build user run pacman through sudo without a password while the
+// helper installs as root, so that the helper elevates itself without a
+// second authentication. The grant is taken back once the helper is done.
+struct PacmanGrant {
+    filepath: PathBuf,
+}
+
+impl PacmanGrant {
+    fn new(build_user: &User) -> Result<Self, Error> {
+        // sudo skips files with a dot in their name
+        let filepath = Path::new(SUDOERS_DIRPATH).join(format!("aldm-{}", build_user.name));
+        let rule = format!(
+            "{} ALL=(root) NOPASSWD: {PACMAN_FILEPATH}\n",
+            build_user.name
+        );
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o440)
+            .open(&filepath)
+            .and_then(|mut file| file.write_all(rule.as_bytes()))
+            .context(GrantPacmanSnafu {
+                path: filepath.clone(),
+            })?;
+        Ok(PacmanGrant { filepath })
+    }
+}
+
+impl Drop for PacmanGrant {
+    fn drop(&mut self) {
+        if let Err(error) = fs::remove_file(&self.filepath) {
+            tracing::warn!(
+                "Could not remove {}, which lets the build user run pacman as root: {}",
+                self.filepath
+                    .display(),
+                error
+            );
+        }
+    }
+}
+
+// The user to build AUR packages as, who is the user that ran aldm through
+// sudo or pkexec
+fn build_user() -> Result<User, Error> {
+    if let Some(user) = env::var("SUDO_USER")
+        .ok()
+        .filter(|sudo_user| !sudo_user.is_empty() && sudo_user != "root")
+        .and_then(|sudo_user| {
+            User::from_name(&sudo_user)
+                .ok()
+                .flatten()
+        })
+    {
+        return Ok(user);
+    }
+    env::var("PKEXEC_UID")
+        .ok()
+        .and_then(|pkexec_uid| {
+            pkexec_uid
+                .parse()
+                .ok()
+        })
+        .map(Uid::from_raw)
+        .filter(|uid| !uid.is_root())
+        .and_then(|uid| {
+            User::from_uid(uid)
+                .ok()
+                .flatten()
+        })
+        .context(NoBuildUserSnafu {})
+}
+
+fn is_in_path(program: &str) -> bool {
+    env::var_os("PATH")
+        .map(|paths| {
+            env::split_paths(&paths).any(|path| {
+                path.join(program)
+                    .is_file()
+            })
+        })
+        .unwrap_or(false)
+}
+
+pub const KNOWN_AUR_HELPERS: [&str; 2] = ["paru", "yay"];
+const SUDOERS_DIRPATH: &str = "/etc/sudoers.d";
+const PACMAN_FILEPATH: &str = "/usr/bin/pacman";
+
+// region: IMPORTS
+
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::{self, OpenOptions},
+    io::{self, IsTerminal, Write},
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use nix::unistd::{Uid, User};
+use snafu::{OptionExt, ResultExt};
+
+use crate::{
+    actions::{CancellationToken, Phase, ProgressSink},
+    backend::{
+        parse_package_info, parse_package_versions, run_query, run_streaming, Error,
+        GrantPacmanSnafu, InvalidAurHelperSnafu, NoAurHelperSnafu, NoBuildUserSnafu,
+        PackageBackend, Pacman, StepOutcome,
+    },
+    data::Version,
+};
+
+// endregion: IMPORTS
@@ -0,0 +1,243 @@
+// A backend that changes nothing on the system. It starts from the given
+// installed and available packages, answers installs and removals from a
+// script, and records every call, so that the install and remove logic can be
+// checked on any machine.
+#[derive(Debug, Default)]
+pub struct FakeBackend {
+    is_aur_supported: bool,
+    state: Mutex<FakeState>,
+}
+
+#[derive(Debug, Default)]
+struct FakeState {
+    installed: BTreeMap<String, Version>,
+    available: BTreeMap<String, Version>,
+    script: VecDeque<FakeStep>,
+    calls: Vec<BackendCall>,
+}
+
+// How the next install or removal goes. Without a scripted step, it succeeds
+// without any output.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FakeStep {
+    pub output: Vec<String>,
+    pub result: FakeResult,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum FakeResult {
+    #[default]
+    Complete,
+    Fail {
+        exit_code: i32,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub enum BackendCall {
+    QueryInstalled,
+    QueryAvailable { packages: Vec<String> },
+    Install { packages: Vec<String> },
+    Remove { packages: Vec<String> },
+}
+
+impl FakeBackend {
+    pub fn new() -> Self {
+        FakeBackend::default()
+    }
+
+    pub fn with_installed(self, package: &str, version: &str) -> Self {
+        self.lock()
+            .installed
+            .insert(package.to_owned(), Version::new(version));
+        self
+    }
+
+    pub fn with_available(self, package: &str, version: &str) -> Self {
+        self.lock()
+            .available
+            .insert(package.to_owned(), Version::new(version));
+        self
+    }
+
+    // Act like an AUR helper
+    pub fn with_aur_support(mut self) -> Self {
+        self.is_aur_supported = true;
+        self
+    }
+
+    pub fn with_step(self, step: FakeStep) -> Self {
+        self.lock()
+            .script
+            .push_back(step);
+        self
+    }
+
+    pub fn calls(&self) -> Vec<BackendCall> {
+        self.lock()
+            .calls
+            .clone()
+    }
+
+    pub fn installed(&self) -> BTreeMap<String, Version> {
+        self.lock()
+            .installed
+            .clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, FakeState> {
+        // The state stays consistent even if a panic poisoned the lock
+        self.state
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn run_step(
+        &self,
+        call: BackendCall,
+        command_line: String,
+        phase: Phase,
+        progress: &dyn ProgressSink,
+        cancellation_token: &CancellationToken,
+    ) -> Result<StepOutcome, Error> {
+        let step = {
+            let mut state = self.lock();
+            state
+                .calls
+                .push(call);
+            if cancellation_token.is_cancelled() {
+                return Ok(StepOutcome::Cancelled);
+            }
+            state
+                .script
+                .pop_front()
+                .unwrap_or_default()
+        };
+
+        // The scripted output is taken for pacman's, so that failures are
+        // recognized from it like from the real thing
+        let mut output_reporter = OutputReporter::new(phase, progress);
+        for line in &step.output {
+            output_reporter.report_line(line);
+        }
+        let exit_code = match step.result {
+            FakeResult::Complete => 0,
+            FakeResult::Fail { exit_code } => exit_code,
+        };
+        output_reporter.finish(command_line, ExitStatus::from_raw(exit_code << 8))
+    }
+}
+
+impl PackageBackend for FakeBackend {
+    fn name(&self) -> &str {
+        "fake"
+    }
+
+    fn supports_aur(&self) -> bool {
+        self.is_aur_supported
+    }
+
+    fn query_installed(&self) -> Result<BTreeMap<String, Version>, Error> {
+        let mut state = self.lock();
+        state
+            .calls
+            .push(BackendCall::QueryInstalled);
+        Ok(state
+            .installed
+            .clone())
+    }
+
+    fn query_available(&self, packages: &[String]) -> Result<BTreeMap<String, Version>, Error> {
+        let mut state = self.lock();
+        state
+            .calls
+            .push(BackendCall::QueryAvailable {
+                packages: packages.to_vec(),
+            });
+        Ok(packages
+            .iter()
+            .filter_map(|package| {
+                state
+                    .available
+                    .get(package)
+                    .map(|version| (package.clone(), version.clone()))
+            })
+            .collect())
+    }
+
+    fn install(
+        &self,
+        packages: &[String],
+        progress: &dyn ProgressSink,
+        cancellation_token: &CancellationToken,
+    ) -> Result<StepOutcome, Error> {
+        let outcome = self.run_step(
+            BackendCall::Install {
+                packages: packages.to_vec(),
+            },
+            format!("fake -S {}", packages.join(" ")),
+            Phase::Installing,
+            progress,
+            cancellation_token,
+        )?;
+        if outcome == StepOutcome::Completed {
+            let mut state = self.lock();
+            for package in packages {
+                let version = state
+                    .available
+                    .get(package)
+                    .cloned()
+                    .unwrap_or_default();
+                state
+                    .installed
+                    .insert(package.clone(), version);
+            }
+        }
+        Ok(outcome)
+    }
+
+    fn remove(
+        &self,
+        packages: &[String],
+        progress: &dyn ProgressSink,
+        cancellation_token: &CancellationToken,
+    ) -> Result<StepOutcome, Error> {
+        let outcome = self.run_step(
+            BackendCall::Remove {
+                packages: packages.to_vec(),
+            },
+            format!("fake -R {}", packages.join(" ")),
+            Phase::Removing,
+            progress,
+            cancellation_token,
+        )?;
+        if outcome == StepOutcome::Completed {
+            let mut state = self.lock();
+            for package in packages {
+                state
+                    .installed
+                    .remove(package);
+            }
+        }
+        Ok(outcome)
+    }
+}
+
+// region: IMPORTS
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    os::unix::process::ExitStatusExt,
+    process::ExitStatus,
+    sync::{Mutex, MutexGuard},
+};
+
+use serde::Serialize;
+
+use crate::{
+    actions::{CancellationToken, Phase, ProgressSink},
+    backend::{Error, OutputReporter, PackageBackend, StepOutcome},
+    data::Version,
+};
+
+// endregion: IMPORTS
@@ -0,0 +1,559 @@
+// The package managers that aldm can install drivers with. Installing and
+// removing is done by the backend, while deciding what to install and remove
+// is up to the actions, so that the logic can also be exercised against the
+// fake backend.
+pub trait PackageBackend: Send + Sync {
+    // A short name for messages, like `pacman` or `paru`
+    fn name(&self) -> &str;
+
+    // Whether packages from the Arch User Repository (AUR) can be installed
+    fn supports_aur(&self) -> bool {
+        false
+    }
+
+    // The installed packages, mapped to their versions
+    fn query_installed(&self) -> Result<BTreeMap<String, Version>, Error>;
+
+    // The given packages that can be installed, mapped to their versions.
+    // Packages that are not available are left out.
+    fn query_available(&self, packages: &[String]) -> Result<BTreeMap<String, Version>, Error>;
+
+    fn install(
+        &self,
+        packages: &[String],
+        progress: &dyn ProgressSink,
+        cancellation_token: &CancellationToken,
+    ) -> Result<StepOutcome, Error>;
+
+    fn remove(
+        &self,
+        packages: &[String],
+        progress: &dyn ProgressSink,
+        cancellation_token: &CancellationToken,
+    ) -> Result<StepOutcome, Error>;
+}
+
+// Which backend to install with. The AUR needs an AUR helper, which is the
+// configured one if any and a detected one otherwise. Without the AUR, pacman
+// is used directly.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackendSettings {
+    pub enable_aur: bool,
+    pub aur_helper: Option<String>,
+}
+
+impl BackendSettings {
+    pub fn from_config(config: &Config) -> Self {
+        BackendSettings {
+            enable_aur: config
+                .enable_aur
+                .unwrap_or(false),
+            aur_helper: config
+                .aur_helper
+                .clone()
+                .filter(|aur_helper| {
+                    !aur_helper
+                        .trim()
+                        .is_empty()
+                }),
+        }
+    }
+
+    pub fn backend(&self) -> Result<Box<dyn PackageBackend>, Error> {
+        if !self.enable_aur {
+            return Ok(Box::new(Pacman::new()));
+        }
+        let aur_helper = match &self.aur_helper {
+            Some(aur_helper) => AurHelper::from_command_line(aur_helper)?,
+            None => AurHelper::detect()?,
+        };
+        tracing::debug!("Using the AUR helper {}", aur_helper.name());
+        Ok(Box::new(aur_helper))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub enum StepOutcome {
+    Completed,
+    // Cancelled before any change was committed to the system
+    Cancelled,
+}
+
+// Run a package manager command, reporting each line of its output as soon as
+// it is printed. Cancelling kills the command, but only until it starts
+// committing changes to the system.
+pub(crate) fn run_streaming(
+    mut command: Command,
+    phase: Phase,
+    progress: &dyn ProgressSink,
+    cancellation_token: &CancellationToken,
+) -> Result<StepOutcome, Error> {
+    let command_line = command_line(&command);
+    tracing::info!("Running `{}`", command_line);
+    let mut child = command
+        .env("LC_ALL", "C")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context(SpawnPackageManagerSnafu {
+            command: command_line.clone(),
+        })?;
+
+    // Read stdout and stderr on their own threads, so that neither blocks the
+    // other and the cancellation token can still be checked in between
+    let (line_sender, line_receiver) = mpsc::channel();
+    let readers = [
+        child
+            .stdout
+            .take()
+            .map(|stdout| Box::new(stdout) as Box<dyn Read + Send>),
+        child
+            .stderr
+            .take()
+            .map(|stderr| Box::new(stderr) as Box<dyn Read + Send>),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|reader| {
+        let line_sender = line_sender.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(reader)
+                .lines()
+                .map_while(Result::ok)
+            {
+                if line_sender
+                    .send(line)
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        })
+    })
+    .collect::<Vec<_>>();
+    drop(line_sender);
+
+    // Cancelling interrupts the package manager, which pacman handles by
+    // releasing its database lock before it exits. Only one that does not
+    // exit in time is killed, which may leave the lock behind. The package
+    // manager may not be signalable at all, such as when it runs as root
+    // through pkexec. It then runs to the end and its outcome is reported as
+    // usual, since it may have changed the system.
+    let mut output_reporter = OutputReporter::new(phase, progress);
+    let mut interrupted_at = None;
+    let mut is_signalled = false;
+    let mut is_kill_attempted = false;
+    loop {
+        match line_receiver.recv_timeout(Duration::from_millis(100)) {
+            Ok(line) => output_reporter.report_line(&line),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        match interrupted_at {
+            None if !output_reporter.is_committed && cancellation_token.is_cancelled() => {
+                interrupted_at = Some(Instant::now());
+                tracing::info!("Cancelling `{}` before it commits any change", command_line);
+                match signal::kill(child_pid(&child), Signal::SIGINT) {
+                    Ok(()) => is_signalled = true,
+                    Err(error) => tracing::warn!(
+                        "Could not cancel `{}`, so it is left to finish: {}",
+                        command_line,
+                        error
+                    ),
+                }
+            }
+            Some(interrupted_at)
+                if is_signalled
+                    && !is_kill_attempted
+                    && interrupted_at.elapsed() >= INTERRUPT_TIMEOUT =>
+            {
+                is_kill_attempted = true;
+                tracing::warn!(
+                    "`{}` did not stop within {:?} of the interrupt, so it is killed",
+                    command_line,
+                    INTERRUPT_TIMEOUT
+                );
+                if let Err(error) = child.kill() {
+                    tracing::warn!("Could not kill `{}`: {}", command_line, error);
+                }
+            }
+            _ => {}
+        }
+    }
+    for reader in readers {
+        reader.join().ok();
+    }
+
+    let exit_status = child
+        .wait()
+        .context(WaitPackageManagerSnafu {
+            command: command_line.clone(),
+        })?;
+    // The package manager may have exited on its own just before the signal.
+    // pacman exits with 128 plus the number of the signal once it cleaned up.
+    let is_interrupted = exit_status
+        .signal()
+        .is_some()
+        || exit_status.code() == Some(128 + Signal::SIGINT as i32);
+    if is_signalled && is_interrupted {
+        return Ok(StepOutcome::Cancelled);
+    }
+    output_reporter.finish(command_line, exit_status)
+}
+
+fn child_pid(child: &Child) -> Pid {
+    Pid::from_raw(child.id() as i32)
+}
+
+// How long the package manager gets to clean up after an interrupt
+const INTERRUPT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Turns the output of a package manager into progress, one line at a time,
+// and tells from it why the package manager failed
+pub(crate) struct OutputReporter<'a> {
+    parser: PacmanOutputParser,
+    phase: Phase,
+    progress: &'a dyn ProgressSink,
+    download_count: u64,
+    is_committed: bool,
+}
+
+impl<'a> OutputReporter<'a> {
+    pub(crate) fn new(phase: Phase, progress: &'a dyn ProgressSink) -> Self {
+        OutputReporter {
+            parser: PacmanOutputParser::new(),
+            phase,
+            progress,
+            download_count: 0,
+            is_committed: false,
+        }
+    }
+
+    pub(crate) fn report_line(&mut self, line: &str) {
+        tracing::debug!(target: "pacman", "{}", line);
+        let event = self
+            .parser
+            .parse_line(line);
+        if let Some(event) = &event {
+            tracing::trace!("Parsed {:?}", event);
+            if !self.is_committed && event.is_commit() {
+                self.is_committed = true;
+                self.progress
+                    .phase(self.phase);
+            }
+        }
+
+        let phase = self.phase;
+        match event {
+            Some(PacmanEvent::Resolving) => {
+                self.progress
+                    .step(phase, 0, None, "Resolving dependencies")
+            }
+            Some(PacmanEvent::CheckingConflicts) => {
+                self.progress
+                    .step(phase, 0, None, "Looking for conflicting packages")
+            }
+            Some(PacmanEvent::ConflictFound {
+                package,
+                conflicting_package,
+            }) => tracing::warn!("{} is in conflict with {}", package, conflicting_package),
+            Some(PacmanEvent::DownloadSize { bytes }) => self.progress.step(
+                phase,
+                0,
+                None,
+                &format!("{:.2} MiB to download", bytes as f64 / (1024.0 * 1024.0)),
+            ),
+            Some(PacmanEvent::Retrieving) => self
+                .progress
+                .phase(Phase::Downloading),
+            Some(PacmanEvent::Downloading { package }) => {
+                self.download_count += 1;
+                self.progress
+                    .step(Phase::Downloading, self.download_count, None, &package);
+            }
+            Some(PacmanEvent::Transacting {
+                action,
+                package,
+                current,
+                total,
+            }) => self
+                .progress
+                .step(phase, current, Some(total), &format!("{action} {package}")),
+            Some(PacmanEvent::Hook {
+                description,
+                current,
+                total,
+            }) => self
+                .progress
+                .step(phase, current, Some(total), &description),
+            Some(PacmanEvent::Warning { message }) => tracing::warn!("{}", message),
+            Some(PacmanEvent::ProcessingChanges)
+            | Some(PacmanEvent::RunningHooks { .. })
+            | Some(PacmanEvent::Error { .. })
+            | None => {}
+        }
+        self.progress
+            .log(line);
+    }
+
+    pub(crate) fn finish(
+        self,
+        command_line: String,
+        exit_status: ExitStatus,
+    ) -> Result<StepOutcome, Error> {
+        if exit_status.success() {
+            return Ok(StepOutcome::Completed);
+        }
+        match self
+            .parser
+            .failure()
+        {
+            Some(failure) => PacmanFailedSnafu {
+                command: command_line,
+                failure: failure.clone(),
+            }
+            .fail(),
+            None => PackageManagerFailedSnafu {
+                command: command_line,
+                exit_status,
+            }
+            .fail(),
+        }
+    }
+}
+
+// Run a package manager command that only reads, and return what it printed.
+// Queries like `-Si` print what they found even when they fail for other
+// packages, so the exit status is only logged.
+pub(crate) fn run_query(mut command: Command) -> Result<String, Error> {
+    let command_line = command_line(&command);
+    tracing::debug!("Running `{}`", command_line);
+    let output = command
+        .env("LC_ALL", "C")
+        .stdin(Stdio::null())
+        .output()
+        .context(SpawnPackageManagerSnafu {
+            command: command_line.clone(),
+        })?;
+    if !output
+        .status
+        .success()
+    {
+        tracing::debug!(
+            "`{}` exited with {}: {}",
+            command_line,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Parse the output of `-Q`, which is one `<name> <version>` per line
+pub(crate) fn parse_package_versions(output: &str) -> BTreeMap<String, Version> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, version) = line
+                .trim()
+                .split_once(' ')?;
+            Some((name.to_owned(), Version::new(version)))
+        })
+        .collect()
+}
+
+// Parse the output of `-Si`, which consists of blocks of `Key : Value` lines
+// like:
+//
+// Repository      : extra
+// Name            : nvidia-utils
+// Version         : 550.78-1
+pub(crate) fn parse_package_info(output: &str) -> BTreeMap<String, Version> {
+    let mut packages = BTreeMap::new();
+    let mut name = None;
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once(" : ") else {
+            continue;
+        };
+        match key.trim() {
+            "Name" => {
+                name = Some(
+                    value
+                        .trim()
+                        .to_owned(),
+                )
+            }
+            "Version" => {
+                if let Some(name) = name.take() {
+                    packages.insert(name, Version::new(value));
+                }
+            }
+            _ => {}
+        }
+    }
+    packages
+}
+
+fn command_line(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|part| part.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(
+        display(
+            "no AUR helper was found; install paru or yay, or set `aur_helper` in the config file"
+        ),
+        visibility(pub)
+    )]
+    NoAurHelper {},
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the AUR helper '{command_line}' is not a command"),
+        visibility(pub)
+    )]
+    InvalidAurHelper { command_line: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("AUR packages cannot be built as root, and no other user was found to build them as; run aldm with sudo or pkexec from the account to build as"),
+        visibility(pub)
+    )]
+    NoBuildUser {},
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not let the build user run pacman through sudo at {path:?}: {source}"),
+        visibility(pub)
+    )]
+    GrantPacman {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(display("could not run `{command}`: {source}"), visibility(pub))]
+    SpawnPackageManager {
+        command: String,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(display("could not wait for `{command}`: {source}"), visibility(pub))]
+    WaitPackageManager {
+        command: String,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(display("`{command}` failed with {exit_status}"), visibility(pub))]
+    PackageManagerFailed {
+        command: String,
+        exit_status: ExitStatus,
+    },
+
+    #[non_exhaustive]
+    #[snafu(display("`{command}` failed because {failure}"), visibility(pub))]
+    PacmanFailed {
+        command: String,
+        failure: PacmanFailure,
+    },
+}
+
+// region: IMPORTS
+
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, BufReader, Read},
+    os::unix::process::ExitStatusExt,
+    path::PathBuf,
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use nix::{
+    sys::signal::{self, Signal},
+    unistd::Pid,
+};
+
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+
+use crate::{
+    actions::{CancellationToken, Phase, ProgressSink},
+    app::Config,
+    data::Version,
+};
+
+// endregion: IMPORTS
+
+// region: MODULES
+
+pub mod aur_helper;
+pub mod fake;
+pub mod pacman;
+pub mod pacman_output;
+
+// endregion: MODULES
+
+// region: RE-EXPORTS
+
+pub use aur_helper::*;
+pub use fake::*;
+pub use pacman::*;
+pub use pacman_output::*;
+
+// endregion: RE-EXPORTS
+
+#[cfg(test)]
+mod tests {
+    // A package manager that cleans up on an interrupt, like pacman, which
+    // tells that it was interrupted rather than killed
+    #[test]
+    fn cancelling_interrupts_the_package_manager() {
+        let mut command = Command::new("sh");
+        command.args([
+            "-c",
+            "trap 'echo interrupted; exit 130' INT; echo ready; while :; do sleep 0.1; done",
+        ]);
+        let cancellation_token = CancellationToken::new();
+        let lines = Mutex::new(Vec::new());
+        let progress = |event: ProgressEvent| {
+            if let ProgressEvent::Log { line } = event {
+                cancellation_token.cancel();
+                lines
+                    .lock()
+                    .unwrap()
+                    .push(line);
+            }
+        };
+
+        let outcome = run_streaming(command, Phase::Installing, &progress, &cancellation_token);
+        assert_eq!(outcome.unwrap(), StepOutcome::Cancelled);
+        assert_eq!(
+            lines
+                .into_inner()
+                .unwrap(),
+            ["ready", "interrupted"]
+        );
+    }
+
+    // region: IMPORTS
+
+    use std::{process::Command, sync::Mutex};
+
+    use super::{run_streaming, StepOutcome};
+    use crate::actions::{CancellationToken, Phase, ProgressEvent};
+
+    // endregion: IMPORTS
+}
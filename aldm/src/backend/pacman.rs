@@ -0,0 +1,89 @@
+// Installs packages from the repositories with pacman
+#[derive(Clone, Debug, Default)]
+pub struct Pacman {}
+
+impl Pacman {
+    pub fn new() -> Self {
+        Pacman {}
+    }
+}
+
+impl PackageBackend for Pacman {
+    fn name(&self) -> &str {
+        "pacman"
+    }
+
+    fn query_installed(&self) -> Result<BTreeMap<String, Version>, Error> {
+        let mut command = Command::new("pacman");
+        command.arg("-Q");
+        Ok(parse_package_versions(&run_query(command)?))
+    }
+
+    fn query_available(&self, packages: &[String]) -> Result<BTreeMap<String, Version>, Error> {
+        if packages.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+        let mut command = Command::new("pacman");
+        command
+            .arg("-Si")
+            .args(packages);
+        Ok(parse_package_info(&run_query(command)?))
+    }
+
+    fn install(
+        &self,
+        packages: &[String],
+        progress: &dyn ProgressSink,
+        cancellation_token: &CancellationToken,
+    ) -> Result<StepOutcome, Error> {
+        let mut command = elevated_pacman_command();
+        command
+            .args(["-S", "--needed", "--noconfirm"])
+            .args(packages);
+        run_streaming(command, Phase::Installing, progress, cancellation_token)
+    }
+
+    fn remove(
+        &self,
+        packages: &[String],
+        progress: &dyn ProgressSink,
+        cancellation_token: &CancellationToken,
+    ) -> Result<StepOutcome, Error> {
+        let mut command = elevated_pacman_command();
+        command
+            .args(["-R", "--noconfirm"])
+            .args(packages);
+        run_streaming(command, Phase::Removing, progress, cancellation_token)
+    }
+}
+
+// pacman has to run as root to change the system. The transactions of aldm
+// are run as root by the elevated helper when aldm itself is not, so polkit is
+// only asked to elevate pacman alone when the backend is used on its own.
+pub(crate) fn elevated_pacman_command() -> Command {
+    match Uid::effective().is_root() {
+        true => Command::new("pacman"),
+        false => {
+            let mut command = Command::new("pkexec");
+            command.arg("pacman");
+            command
+        }
+    }
+}
+
+// region: IMPORTS
+
+use std::{collections::BTreeMap, process::Command};
+
+use nix::unistd::Uid;
+
+use crate::{
+    actions::{CancellationToken, Phase, ProgressSink},
+    backend::{
+        parse_package_info, parse_package_versions, run_query, run_streaming, Error,
+        PackageBackend, StepOutcome,
+    },
+    data::Version,
+};
+
+// endregion: IMPORTS
@@ -0,0 +1,558 @@
+// What pacman reports while it runs, parsed from its output in the C locale.
+// AUR helpers hand the repository part of a transaction to pacman, so their
+// output is parsed the same way.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PacmanEvent {
+    Resolving,
+    CheckingConflicts,
+    ConflictFound {
+        package: String,
+        conflicting_package: String,
+    },
+    DownloadSize {
+        bytes: u64,
+    },
+    Retrieving,
+    Downloading {
+        package: String,
+    },
+    ProcessingChanges,
+    Transacting {
+        action: String,
+        package: String,
+        current: u64,
+        total: u64,
+    },
+    RunningHooks {
+        stage: HookStage,
+    },
+    Hook {
+        description: String,
+        current: u64,
+        total: u64,
+    },
+    Warning {
+        message: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl PacmanEvent {
+    // Whether pacman has started changing the system, after which it must
+    // not be interrupted
+    pub fn is_commit(&self) -> bool {
+        matches!(
+            self,
+            PacmanEvent::ProcessingChanges | PacmanEvent::RunningHooks { .. }
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookStage {
+    PreTransaction,
+    PostTransaction,
+}
+
+// The reason pacman failed, recognized from its error messages
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "failure", rename_all = "snake_case")]
+pub enum PacmanFailure {
+    DatabaseLocked,
+    InvalidSignature { package: Option<String> },
+    PackageNotFound { package: String },
+    ConflictingPackages,
+    DownloadFailed,
+    NotEnoughDiskSpace,
+    NotRoot,
+}
+
+impl fmt::Display for PacmanFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PacmanFailure::DatabaseLocked => write!(f, "the package database is locked"),
+            PacmanFailure::InvalidSignature {
+                package: Some(package),
+            } => write!(f, "the signature of {package} is invalid"),
+            PacmanFailure::InvalidSignature { package: None } => {
+                write!(f, "a package signature is invalid")
+            }
+            PacmanFailure::PackageNotFound { package } => {
+                write!(f, "the package {package} was not found")
+            }
+            PacmanFailure::ConflictingPackages => write!(f, "packages are in conflict"),
+            PacmanFailure::DownloadFailed => write!(f, "packages could not be downloaded"),
+            PacmanFailure::NotEnoughDiskSpace => write!(f, "there is not enough disk space"),
+            PacmanFailure::NotRoot => write!(f, "root privileges are required"),
+        }
+    }
+}
+
+// Parses pacman's output line by line. Hooks are numbered like packages, so
+// the parser keeps track of whether the hooks are running.
+#[derive(Clone, Debug, Default)]
+pub struct PacmanOutputParser {
+    is_running_hooks: bool,
+    failures: Vec<PacmanFailure>,
+}
+
+impl PacmanOutputParser {
+    pub fn new() -> Self {
+        PacmanOutputParser::default()
+    }
+
+    pub fn parse_line(&mut self, line: &str) -> Option<PacmanEvent> {
+        let line = line.trim();
+
+        if let Some(message) = line.strip_prefix("error: ") {
+            if let Some(failure) = parse_failure(message) {
+                if !self
+                    .failures
+                    .contains(&failure)
+                {
+                    self.failures
+                        .push(failure);
+                }
+            }
+            return Some(PacmanEvent::Error {
+                message: message.to_owned(),
+            });
+        }
+        if let Some(message) = line.strip_prefix("warning: ") {
+            return Some(PacmanEvent::Warning {
+                message: message.to_owned(),
+            });
+        }
+
+        match line {
+            "resolving dependencies..." | "checking dependencies..." => {
+                return Some(PacmanEvent::Resolving)
+            }
+            "looking for conflicting packages..." => return Some(PacmanEvent::CheckingConflicts),
+            ":: Retrieving packages..." => return Some(PacmanEvent::Retrieving),
+            ":: Processing package changes..." => {
+                self.is_running_hooks = false;
+                return Some(PacmanEvent::ProcessingChanges);
+            }
+            ":: Running pre-transaction hooks..." => {
+                self.is_running_hooks = true;
+                return Some(PacmanEvent::RunningHooks {
+                    stage: HookStage::PreTransaction,
+                });
+            }
+            ":: Running post-transaction hooks..." => {
+                self.is_running_hooks = true;
+                return Some(PacmanEvent::RunningHooks {
+                    stage: HookStage::PostTransaction,
+                });
+            }
+            _ => {}
+        }
+
+        // `:: nvidia-open and nvidia are in conflict. Remove nvidia? [y/N]`
+        if let Some((packages, _)) = line
+            .strip_prefix(":: ")
+            .and_then(|rest| rest.split_once(" are in conflict"))
+        {
+            let (package, conflicting_package) = packages.split_once(" and ")?;
+            return Some(PacmanEvent::ConflictFound {
+                package: package.to_owned(),
+                conflicting_package: conflicting_package.to_owned(),
+            });
+        }
+
+        // `Total Download Size:   52.54 MiB`
+        if let Some(size) = line.strip_prefix("Total Download Size:") {
+            return Some(PacmanEvent::DownloadSize {
+                bytes: parse_size(size)?,
+            });
+        }
+
+        // `nvidia-utils-550.78-1-x86_64 downloading...`
+        if let Some(package) = line.strip_suffix(" downloading...") {
+            return Some(PacmanEvent::Downloading {
+                package: package.to_owned(),
+            });
+        }
+
+        // `(1/2) installing nvidia-utils` or `(1/3) Arming ConditionNeedsUpdate...`
+        let (current, total, rest) = parse_counter(line)?;
+        match self.is_running_hooks {
+            true => Some(PacmanEvent::Hook {
+                description: rest
+                    .trim_end_matches("...")
+                    .to_owned(),
+                current,
+                total,
+            }),
+            false => {
+                let (action, package) = rest.split_once(' ')?;
+                TRANSACTION_ACTIONS
+                    .contains(&action)
+                    .then(|| PacmanEvent::Transacting {
+                        action: action.to_owned(),
+                        package: package
+                            .trim_end_matches("...")
+                            .to_owned(),
+                        current,
+                        total,
+                    })
+            }
+        }
+    }
+
+    // The most specific reason for a failure seen so far. Several errors may
+    // follow from one reason, like `failed to init transaction` from a locked
+    // database, so the first recognized one is the most telling.
+    pub fn failure(&self) -> Option<&PacmanFailure> {
+        self.failures
+            .first()
+    }
+}
+
+fn parse_failure(message: &str) -> Option<PacmanFailure> {
+    if message.contains("unable to lock database") || message.starts_with("could not lock database")
+    {
+        return Some(PacmanFailure::DatabaseLocked);
+    }
+    // `nvidia-utils: signature from "..." is invalid`
+    if message.contains("signature from") && message.ends_with("is invalid") {
+        return Some(PacmanFailure::InvalidSignature {
+            package: message
+                .split_once(':')
+                .map(|(package, _)| package.to_owned()),
+        });
+    }
+    if message.contains("(invalid or corrupted package (PGP signature))") {
+        return Some(PacmanFailure::InvalidSignature { package: None });
+    }
+    if let Some(package) = message.strip_prefix("target not found: ") {
+        return Some(PacmanFailure::PackageNotFound {
+            package: package.to_owned(),
+        });
+    }
+    if message.contains("unresolvable package conflicts detected")
+        || message.contains("(conflicting dependencies)")
+        || message.contains("(conflicting files)")
+    {
+        return Some(PacmanFailure::ConflictingPackages);
+    }
+    if message.contains("failed to retrieve some files") {
+        return Some(PacmanFailure::DownloadFailed);
+    }
+    if message.contains("not enough free disk space") || message.contains(" too full") {
+        return Some(PacmanFailure::NotEnoughDiskSpace);
+    }
+    if message.starts_with("you cannot perform this operation unless you are root") {
+        return Some(PacmanFailure::NotRoot);
+    }
+    None
+}
+
+// Parse `(<current>/<total>) <rest>`
+fn parse_counter(line: &str) -> Option<(u64, u64, &str)> {
+    let (counter, rest) = line
+        .strip_prefix('(')?
+        .split_once(") ")?;
+    let (current, total) = counter.split_once('/')?;
+    Some((
+        current
+            .trim()
+            .parse()
+            .ok()?,
+        total
+            .trim()
+            .parse()
+            .ok()?,
+        rest.trim(),
+    ))
+}
+
+// Parse a size like `52.54 MiB` into bytes
+fn parse_size(size: &str) -> Option<u64> {
+    let (value, unit) = size
+        .trim()
+        .split_once(' ')?;
+    let value: f64 = value.parse().ok()?;
+    let multiplier = match unit.trim() {
+        "B" => 1.0,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        "TiB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((value * multiplier).round() as u64)
+}
+
+const TRANSACTION_ACTIONS: [&str; 5] = [
+    "installing",
+    "upgrading",
+    "reinstalling",
+    "downgrading",
+    "removing",
+];
+
+// region: IMPORTS
+
+use std::fmt;
+
+use serde::Serialize;
+
+// endregion: IMPORTS
+
+#[cfg(test)]
+mod tests {
+    fn parse(lines: &[&str]) -> (Vec<PacmanEvent>, Option<PacmanFailure>) {
+        let mut parser = PacmanOutputParser::new();
+        let events = lines
+            .iter()
+            .filter_map(|line| parser.parse_line(line))
+            .collect();
+        (
+            events,
+            parser
+                .failure()
+                .cloned(),
+        )
+    }
+
+    fn failure(lines: &[&str]) -> Option<PacmanFailure> {
+        parse(lines).1
+    }
+
+    // The output of `pacman -S --noconfirm nvidia-open nvidia-utils`
+    #[test]
+    fn parses_an_installation() {
+        let (events, failure) = parse(&[
+            "resolving dependencies...",
+            "looking for conflicting packages...",
+            "",
+            "Packages (2) nvidia-open-550.78-1  nvidia-utils-550.78-1",
+            "",
+            "Total Download Size:    52.54 MiB",
+            "Total Installed Size:  238.08 MiB",
+            "",
+            ":: Proceed with installation? [Y/n] ",
+            ":: Retrieving packages...",
+            " nvidia-utils-550.78-1-x86_64 downloading...",
+            " nvidia-open-550.78-1-x86_64 downloading...",
+            "checking keyring...",
+            "checking package integrity...",
+            "loading package files...",
+            "checking for file conflicts...",
+            ":: Running pre-transaction hooks...",
+            "(1/1) Removing linux initcpios...",
+            ":: Processing package changes...",
+            "(1/2) installing nvidia-utils",
+            "warning: /etc/X11/xorg.conf.d/20-nvidia.conf installed as /etc/X11/xorg.conf.d/20-nvidia.conf.pacnew",
+            "(2/2) installing nvidia-open",
+            ":: Running post-transaction hooks...",
+            "(1/3) Arming ConditionNeedsUpdate...",
+            "(2/3) Updating module dependencies...",
+            "(3/3) Updating linux initcpios...",
+        ]);
+        assert_eq!(
+            events,
+            [
+                PacmanEvent::Resolving,
+                PacmanEvent::CheckingConflicts,
+                PacmanEvent::DownloadSize { bytes: 55_092_183 },
+                PacmanEvent::Retrieving,
+                PacmanEvent::Downloading {
+                    package: "nvidia-utils-550.78-1-x86_64".to_owned()
+                },
+                PacmanEvent::Downloading {
+                    package: "nvidia-open-550.78-1-x86_64".to_owned()
+                },
+                PacmanEvent::RunningHooks {
+                    stage: HookStage::PreTransaction
+                },
+                PacmanEvent::Hook {
+                    description: "Removing linux initcpios".to_owned(),
+                    current: 1,
+                    total: 1
+                },
+                PacmanEvent::ProcessingChanges,
+                PacmanEvent::Transacting {
+                    action: "installing".to_owned(),
+                    package: "nvidia-utils".to_owned(),
+                    current: 1,
+                    total: 2
+                },
+                PacmanEvent::Warning {
+                    message: "/etc/X11/xorg.conf.d/20-nvidia.conf installed as /etc/X11/xorg.conf.d/20-nvidia.conf.pacnew".to_owned()
+                },
+                PacmanEvent::Transacting {
+                    action: "installing".to_owned(),
+                    package: "nvidia-open".to_owned(),
+                    current: 2,
+                    total: 2
+                },
+                PacmanEvent::RunningHooks {
+                    stage: HookStage::PostTransaction
+                },
+                PacmanEvent::Hook {
+                    description: "Arming ConditionNeedsUpdate".to_owned(),
+                    current: 1,
+                    total: 3
+                },
+                PacmanEvent::Hook {
+                    description: "Updating module dependencies".to_owned(),
+                    current: 2,
+                    total: 3
+                },
+                PacmanEvent::Hook {
+                    description: "Updating linux initcpios".to_owned(),
+                    current: 3,
+                    total: 3
+                },
+            ]
+        );
+        assert_eq!(failure, None);
+        assert!(!events[5].is_commit());
+        assert!(events[6].is_commit());
+        assert!(events[8].is_commit());
+    }
+
+    #[test]
+    fn parses_a_removal() {
+        let (events, _) = parse(&[
+            "checking dependencies...",
+            "",
+            "Packages (1) nvidia-550.78-1",
+            ":: Processing package changes...",
+            "(1/1) removing nvidia",
+        ]);
+        assert_eq!(
+            events,
+            [
+                PacmanEvent::Resolving,
+                PacmanEvent::ProcessingChanges,
+                PacmanEvent::Transacting {
+                    action: "removing".to_owned(),
+                    package: "nvidia".to_owned(),
+                    current: 1,
+                    total: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn recognizes_a_locked_database() {
+        assert_eq!(
+            failure(&[
+                "error: failed to init transaction (unable to lock database)",
+                "error: could not lock database: File exists",
+                "  if you're sure a package manager is not already",
+                "  running, you can remove /var/lib/pacman/db.lck",
+            ]),
+            Some(PacmanFailure::DatabaseLocked)
+        );
+    }
+
+    #[test]
+    fn recognizes_an_invalid_signature() {
+        assert_eq!(
+            failure(&[
+                "error: nvidia-utils: signature from \"Jan Alexander Steffens (heftig) <heftig@archlinux.org>\" is invalid",
+                ":: File /var/cache/pacman/pkg/nvidia-utils-550.78-1-x86_64.pkg.tar.zst is corrupted (invalid or corrupted package (PGP signature)).",
+                "Do you want to delete it? [Y/n] ",
+                "error: failed to commit transaction (invalid or corrupted package (PGP signature))",
+                "Errors occurred, no packages were upgraded.",
+            ]),
+            Some(PacmanFailure::InvalidSignature {
+                package: Some("nvidia-utils".to_owned())
+            })
+        );
+        assert_eq!(
+            failure(&[
+                "error: failed to commit transaction (invalid or corrupted package (PGP signature))"
+            ]),
+            Some(PacmanFailure::InvalidSignature { package: None })
+        );
+    }
+
+    #[test]
+    fn recognizes_a_missing_package() {
+        assert_eq!(
+            failure(&["error: target not found: nvidia-dkms-beta"]),
+            Some(PacmanFailure::PackageNotFound {
+                package: "nvidia-dkms-beta".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn recognizes_conflicts() {
+        let (events, conflict_failure) = parse(&[
+            "resolving dependencies...",
+            "looking for conflicting packages...",
+            ":: nvidia-open and nvidia are in conflict. Remove nvidia? [y/N] ",
+            "error: unresolvable package conflicts detected",
+            "error: failed to prepare transaction (conflicting dependencies)",
+            ":: nvidia-open and nvidia are in conflict",
+        ]);
+        assert_eq!(
+            events[2],
+            PacmanEvent::ConflictFound {
+                package: "nvidia-open".to_owned(),
+                conflicting_package: "nvidia".to_owned()
+            }
+        );
+        assert_eq!(conflict_failure, Some(PacmanFailure::ConflictingPackages));
+        assert_eq!(
+            failure(&[
+                "error: failed to commit transaction (conflicting files)",
+                "nvidia-utils: /usr/lib/libEGL_nvidia.so.0 exists in filesystem",
+                "Errors occurred, no packages were upgraded.",
+            ]),
+            Some(PacmanFailure::ConflictingPackages)
+        );
+    }
+
+    #[test]
+    fn recognizes_a_failed_download() {
+        assert_eq!(
+            failure(&[
+                "error: failed retrieving file 'nvidia-utils-550.78-1-x86_64.pkg.tar.zst' from geo.mirror.pkgbuild.com : The requested URL returned error: 404",
+                "warning: failed to retrieve some files",
+                "error: failed to commit transaction (failed to retrieve some files)",
+                "Errors occurred, no packages were upgraded.",
+            ]),
+            Some(PacmanFailure::DownloadFailed)
+        );
+    }
+
+    #[test]
+    fn recognizes_a_full_disk() {
+        assert_eq!(
+            failure(&[
+                "error: Partition / too full: 61032 blocks needed, 1420 blocks free",
+                "error: not enough free disk space",
+                "error: failed to commit transaction (not enough free disk space)",
+                "Errors occurred, no packages were upgraded.",
+            ]),
+            Some(PacmanFailure::NotEnoughDiskSpace)
+        );
+    }
+
+    #[test]
+    fn recognizes_a_missing_root() {
+        assert_eq!(
+            failure(&["error: you cannot perform this operation unless you are root."]),
+            Some(PacmanFailure::NotRoot)
+        );
+    }
+
+    // region: IMPORTS
+
+    use super::{HookStage, PacmanEvent, PacmanFailure, PacmanOutputParser};
+
+    // endregion: IMPORTS
+}
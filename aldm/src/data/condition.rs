@@ -0,0 +1,273 @@
+// A boolean expression over named conditions, as used by driver options. For
+// example, `(nvidia_turing|nvidia_ampere)&!mainline_kernel`.
+//
+// `!` binds tighter than `&`, which binds tighter than `|`. Parentheses group
+// subexpressions.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Readable, Writable)]
+pub enum Condition {
+    Named(String),
+    Not(Box<Condition>),
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+}
+
+impl Condition {
+    pub fn evaluate<F>(&self, is_named_condition_satisfied: &F) -> bool
+    where
+        F: Fn(&str) -> bool,
+    {
+        match self {
+            Condition::Named(name) => is_named_condition_satisfied(name),
+            Condition::Not(condition) => !condition.evaluate(is_named_condition_satisfied),
+            Condition::All(conditions) => conditions
+                .iter()
+                .all(|condition| condition.evaluate(is_named_condition_satisfied)),
+            Condition::Any(conditions) => conditions
+                .iter()
+                .any(|condition| condition.evaluate(is_named_condition_satisfied)),
+        }
+    }
+
+    // All the named conditions referenced in the expression
+    pub fn named_conditions(&self) -> Vec<&str> {
+        match self {
+            Condition::Named(name) => vec![name.as_str()],
+            Condition::Not(condition) => condition.named_conditions(),
+            Condition::All(conditions) | Condition::Any(conditions) => conditions
+                .iter()
+                .flat_map(|condition| condition.named_conditions())
+                .collect(),
+        }
+    }
+
+    // The named conditions that must be satisfied (rather than unsatisfied) for
+    // the expression to hold in at least one way
+    pub fn positive_named_conditions(&self) -> Vec<&str> {
+        match self {
+            Condition::Named(name) => vec![name.as_str()],
+            Condition::Not(_) => vec![],
+            Condition::All(conditions) | Condition::Any(conditions) => conditions
+                .iter()
+                .flat_map(|condition| condition.positive_named_conditions())
+                .collect(),
+        }
+    }
+}
+
+impl FromStr for Condition {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s)?;
+        let mut parser = Parser {
+            expression: s,
+            tokens: &tokens,
+            position: 0,
+        };
+        let condition = parser.parse_any()?;
+        match parser.peek() {
+            None => Ok(condition),
+            Some(token) => UnexpectedTokenSnafu {
+                expression: s,
+                token: token.to_string(),
+            }
+            .fail(),
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |conditions: &Vec<Condition>, separator: &str| {
+            conditions
+                .iter()
+                .map(|condition| match condition {
+                    Condition::All(_) | Condition::Any(_) => format!("({condition})"),
+                    _ => condition.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(separator)
+        };
+        match self {
+            Condition::Named(name) => write!(f, "{name}"),
+            Condition::Not(condition) => match condition.as_ref() {
+                Condition::All(_) | Condition::Any(_) => write!(f, "!({condition})"),
+                _ => write!(f, "!{condition}"),
+            },
+            Condition::All(conditions) => write!(f, "{}", join(conditions, "&")),
+            Condition::Any(conditions) => write!(f, "{}", join(conditions, "|")),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Name(String),
+    Not,
+    And,
+    Or,
+    OpenParenthesis,
+    CloseParenthesis,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Name(name) => write!(f, "{name}"),
+            Token::Not => write!(f, "!"),
+            Token::And => write!(f, "&"),
+            Token::Or => write!(f, "|"),
+            Token::OpenParenthesis => write!(f, "("),
+            Token::CloseParenthesis => write!(f, ")"),
+        }
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut characters = expression
+        .chars()
+        .peekable();
+    while let Some(character) = characters.next() {
+        match character {
+            '!' => tokens.push(Token::Not),
+            '&' => tokens.push(Token::And),
+            '|' => tokens.push(Token::Or),
+            '(' => tokens.push(Token::OpenParenthesis),
+            ')' => tokens.push(Token::CloseParenthesis),
+            c if c.is_whitespace() => {}
+            c if is_name_character(c) => {
+                let mut name = String::from(c);
+                while let Some(&c) = characters.peek() {
+                    if !is_name_character(c) {
+                        break;
+                    }
+                    name.push(c);
+                    characters.next();
+                }
+                tokens.push(Token::Name(name));
+            }
+            c => {
+                return InvalidCharacterSnafu {
+                    expression,
+                    character: c,
+                }
+                .fail()
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn is_name_character(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+struct Parser<'a> {
+    expression: &'a str,
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens
+            .get(self.position)
+    }
+
+    fn next(&mut self) -> Option<&'a Token> {
+        let token = self.peek();
+        self.position += 1;
+        token
+    }
+
+    fn parse_any(&mut self) -> Result<Condition, Error> {
+        let mut conditions = vec![self.parse_all()?];
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            conditions.push(self.parse_all()?);
+        }
+        Ok(match conditions.len() {
+            1 => conditions.remove(0),
+            _ => Condition::Any(conditions),
+        })
+    }
+
+    fn parse_all(&mut self) -> Result<Condition, Error> {
+        let mut conditions = vec![self.parse_unary()?];
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            conditions.push(self.parse_unary()?);
+        }
+        Ok(match conditions.len() {
+            1 => conditions.remove(0),
+            _ => Condition::All(conditions),
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<Condition, Error> {
+        match self.next() {
+            Some(Token::Not) => Ok(Condition::Not(Box::new(self.parse_unary()?))),
+            Some(Token::Name(name)) => Ok(Condition::Named(name.clone())),
+            Some(Token::OpenParenthesis) => {
+                let condition = self.parse_any()?;
+                match self.next() {
+                    Some(Token::CloseParenthesis) => Ok(condition),
+                    Some(token) => UnexpectedTokenSnafu {
+                        expression: self.expression,
+                        token: token.to_string(),
+                    }
+                    .fail(),
+                    None => UnexpectedEndSnafu {
+                        expression: self.expression,
+                    }
+                    .fail(),
+                }
+            }
+            Some(token) => UnexpectedTokenSnafu {
+                expression: self.expression,
+                token: token.to_string(),
+            }
+            .fail(),
+            None => UnexpectedEndSnafu {
+                expression: self.expression,
+            }
+            .fail(),
+        }
+    }
+}
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(
+        display("invalid character '{character}' in the condition '{expression}'"),
+        visibility(pub)
+    )]
+    InvalidCharacter { expression: String, character: char },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("unexpected '{token}' in the condition '{expression}'"),
+        visibility(pub)
+    )]
+    UnexpectedToken { expression: String, token: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the condition '{expression}' ends unexpectedly"),
+        visibility(pub)
+    )]
+    UnexpectedEnd { expression: String },
+}
+
+// region: IMPORTS
+
+use std::{fmt, str::FromStr};
+
+use serde::Serialize;
+use snafu::Snafu;
+use speedy::{Readable, Writable};
+
+// endregion: IMPORTS
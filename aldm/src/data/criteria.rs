@@ -0,0 +1,213 @@
+// The compiled form of a named condition. The criteria all have an AND
+// relationship, while the values within a single criterion have an OR
+// relationship. An empty criterion places no restriction.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Readable, Writable)]
+pub struct Criteria {
+    pub vendor_ids: Vec<u16>,
+    pub device_ids: Vec<u16>,
+    // The kernel drivers the device is bound to, where `none` stands for a
+    // device without a driver
+    pub bound_drivers: Vec<String>,
+    // Whether the system has more than one GPU, if it matters
+    pub hybrid_graphics: Option<bool>,
+    pub kernels: Vec<PackageRequirement>,
+    pub packages: Vec<PackageRequirement>,
+    // Packages from the repositories that drivers install, which are checked
+    // against the version that would be installed rather than an installed one
+    pub driver_packages: Vec<PackageRequirement>,
+}
+
+impl Criteria {
+    // Whether the criteria identify particular devices rather than describe the
+    // system as a whole
+    pub fn is_device_specific(&self) -> bool {
+        !self
+            .vendor_ids
+            .is_empty()
+            || !self
+                .device_ids
+                .is_empty()
+            || !self
+                .bound_drivers
+                .is_empty()
+    }
+
+    // Whether the given device satisfies the device criteria, and the rest of
+    // the system satisfies the remaining criteria
+    pub fn is_satisfied_by_device(
+        &self,
+        pci_device: &PciDevice,
+        system_snapshot: &SystemSnapshot,
+    ) -> bool {
+        self.matches_device(pci_device) && self.is_satisfied_by_system(system_snapshot)
+    }
+
+    // Whether any device in the system satisfies the device criteria, and the
+    // rest of the system satisfies the remaining criteria
+    pub fn is_satisfied(&self, system_snapshot: &SystemSnapshot) -> bool {
+        (!self.is_device_specific()
+            || system_snapshot
+                .pci_devices
+                .iter()
+                .any(|pci_device| self.matches_device(pci_device)))
+            && self.is_satisfied_by_system(system_snapshot)
+    }
+
+    fn matches_device(&self, pci_device: &PciDevice) -> bool {
+        (self
+            .vendor_ids
+            .is_empty()
+            || self
+                .vendor_ids
+                .contains(&pci_device.vendor_id))
+            && (self
+                .device_ids
+                .is_empty()
+                || self
+                    .device_ids
+                    .contains(&pci_device.device_id))
+            && (self
+                .bound_drivers
+                .is_empty()
+                || self
+                    .bound_drivers
+                    .contains(
+                        &pci_device
+                            .bound_driver
+                            .as_deref()
+                            .map(normalize_name)
+                            .unwrap_or_else(|| String::from(NO_BOUND_DRIVER)),
+                    ))
+    }
+
+    fn is_satisfied_by_system(&self, system_snapshot: &SystemSnapshot) -> bool {
+        self.hybrid_graphics
+            .is_none_or(|hybrid_graphics| hybrid_graphics == system_snapshot.is_hybrid_graphics())
+        // The version of a kernel is the version of its package
+        && is_any_met(
+            &self.kernels,
+            system_snapshot
+                .installed_kernels
+                .iter()
+                .map(|kernel| (kernel.as_str(), system_snapshot.package_version(kernel))),
+        ) && is_any_met(
+            &self.packages,
+            system_snapshot
+                .installed_packages
+                .iter()
+                .map(|(package, version)| (package.as_str(), Some(version))),
+        ) && is_any_met(
+            &self.driver_packages,
+            // The version that a driver would install is the one in the
+            // repositories. Only a package that is not there, such as one from
+            // the AUR, is checked with its installed version.
+            system_snapshot
+                .available_packages
+                .iter()
+                .chain(
+                    system_snapshot
+                        .installed_packages
+                        .iter()
+                        .filter(|(package, _)| {
+                            !system_snapshot
+                                .available_packages
+                                .contains_key(*package)
+                        }),
+                )
+                .map(|(package, version)| (package.as_str(), Some(version))),
+        )
+    }
+}
+
+// Whether any of the requirements is met by any of the packages, which are
+// given with their versions if known. No requirements are always met.
+fn is_any_met<'a>(
+    requirements: &[PackageRequirement],
+    mut packages: impl Iterator<Item = (&'a str, Option<&'a Version>)>,
+) -> bool {
+    if requirements.is_empty() {
+        return true;
+    }
+    packages.any(|(package, version)| {
+        let package = normalize_name(package);
+        requirements
+            .iter()
+            .any(|requirement| {
+                requirement.name == package
+                    && match (&requirement.constraint, version) {
+                        (None, _) => true,
+                        (Some(_), Some(version)) => requirement.is_met_by(version),
+                        (Some(_), None) => false,
+                    }
+            })
+    })
+}
+
+pub const NO_BOUND_DRIVER: &str = "none";
+
+// Package, kernel and driver names are compared with hyphens replaced by
+// underscores, so that `linux_lts` in the input file matches the `linux-lts`
+// package.
+pub fn normalize_name(name: &str) -> String {
+    name.trim()
+        .replace('-', "_")
+}
+
+// region: IMPORTS
+
+use serde::Serialize;
+use speedy::{Readable, Writable};
+
+use crate::{
+    data::{PackageRequirement, Version},
+    probe::{PciDevice, SystemSnapshot},
+};
+
+// endregion: IMPORTS
+
+#[cfg(test)]
+mod tests {
+    // An installed driver package that is older than the one in the
+    // repositories must not satisfy a constraint on the version to install
+    #[test]
+    fn driver_packages_are_checked_against_the_version_to_install() {
+        let system_snapshot = SystemSnapshot {
+            installed_packages: BTreeMap::from([
+                ("nvidia-utils".to_owned(), Version::new("545.29.06-1")),
+                (
+                    "nvidia-470xx-utils".to_owned(),
+                    Version::new("470.256.02-3"),
+                ),
+            ]),
+            available_packages: BTreeMap::from([(
+                "nvidia-utils".to_owned(),
+                Version::new("550.54.14-1"),
+            )]),
+            ..Default::default()
+        };
+        let is_satisfied = |driver_package: &str| {
+            Criteria {
+                driver_packages: vec![driver_package
+                    .parse()
+                    .unwrap()],
+                ..Default::default()
+            }
+            .is_satisfied(&system_snapshot)
+        };
+
+        assert!(!is_satisfied("nvidia-utils=545.*"));
+        assert!(is_satisfied("nvidia-utils=550.*"));
+        assert!(is_satisfied("nvidia-utils>=550"));
+        // Not in the repositories, so the installed version counts
+        assert!(is_satisfied("nvidia-470xx-utils=470.*"));
+        assert!(!is_satisfied("nvidia-open"));
+    }
+
+    // region: IMPORTS
+
+    use std::collections::BTreeMap;
+
+    use super::{Criteria, SystemSnapshot, Version};
+
+    // endregion: IMPORTS
+}
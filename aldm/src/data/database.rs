@@ -1,13 +1,240 @@
+pub fn default_database_filepath() -> PathBuf {
+    PathBuf::from(format!(
+        "/usr/share/{}/{}.db",
+        *app::APP_NAME,
+        *app::APP_NAME
+    ))
+}
+
+// The compiled, binary form of the input file, which is what the actions work
+// with.
+#[derive(Clone, Debug, PartialEq, Eq, Readable, Writable)]
+pub struct Database {
+    pub version: String,
+    pub driver_options: Vec<DriverOption>,
+    pub named_conditions: BTreeMap<String, Criteria>,
+    pub packages: BTreeMap<String, PackageInfo>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Readable, Writable)]
+pub struct DriverOption {
+    pub id: String,
+    pub condition: Condition,
+    pub packages: Vec<String>,
+    pub hybrid_graphics_packages: Vec<String>,
+    pub modprobe: ModprobeConfig,
+    // The modules that are added to the initramfs for early KMS
+    pub early_kms_modules: Vec<String>,
+    pub kernel_parameters: Vec<String>,
+    // The systemd units to enable, such as `nvidia-suspend.service`
+    pub services: Vec<String>,
+    pub files: Vec<DriverFile>,
+}
+
+// A file that a driver needs, such as an Xorg snippet. It is written when the
+// driver is installed and removed along with the driver.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Readable, Writable)]
+pub struct DriverFile {
+    // The absolute path on the system
+    pub path: String,
+    pub contents: String,
+}
+
+// The kernel module settings that a driver needs, such as blacklisting nouveau
+// for the proprietary NVIDIA driver. They are written to a modprobe.d file.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Readable, Writable)]
+pub struct ModprobeConfig {
+    pub blacklist: Vec<String>,
+    // The options of each module, as in `nvidia_drm: modeset=1`
+    pub options: BTreeMap<String, String>,
+}
+
+impl ModprobeConfig {
+    pub fn is_empty(&self) -> bool {
+        self.blacklist
+            .is_empty()
+            && self
+                .options
+                .is_empty()
+    }
+}
+
+// What the driver tags in the input file say about a package
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Readable, Writable)]
+pub struct PackageInfo {
+    pub hardware_kinds: BTreeSet<HardwareKind>,
+    pub tags: BTreeSet<String>,
+    pub source: PackageSource,
+}
+
+// Where a package is installed from. Packages from the Arch User Repository
+// (AUR) are built on the machine with an AUR helper, so they are only
+// installed when the AUR is enabled.
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Readable, Writable,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum PackageSource {
+    #[default]
+    Repository,
+    Aur,
+}
+
+impl fmt::Display for PackageSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PackageSource::Repository => write!(f, "repository"),
+            PackageSource::Aur => write!(f, "AUR"),
+        }
+    }
+}
+
+impl Database {
+    pub fn read_from_file(database_filepath: &Path) -> Result<Self, Error> {
+        let database_bytes = fs::read(database_filepath).context(ReadDatabaseFileSnafu {
+            path: database_filepath.to_owned(),
+        })?;
+        Database::read_from_buffer(&database_bytes).context(DatabaseFileFormatSnafu {
+            path: database_filepath.to_owned(),
+        })
+    }
+
+    pub fn write_to_file(&self, database_filepath: &Path) -> Result<(), Error> {
+        let database_bytes = self
+            .write_to_vec()
+            .context(SerializeDatabaseSnafu {})?;
+        fs::write(database_filepath, database_bytes).context(WriteDatabaseFileSnafu {
+            path: database_filepath.to_owned(),
+        })
+    }
+
+    // Whether the named condition holds, with device criteria restricted to the
+    // given device if any. Unknown names never hold.
+    pub fn is_named_condition_satisfied(
+        &self,
+        name: &str,
+        pci_device: Option<&PciDevice>,
+        system_snapshot: &SystemSnapshot,
+    ) -> bool {
+        match (
+            self.named_conditions
+                .get(name),
+            pci_device,
+        ) {
+            (Some(criteria), Some(pci_device)) if criteria.is_device_specific() => {
+                criteria.is_satisfied_by_device(pci_device, system_snapshot)
+            }
+            (Some(criteria), _) => criteria.is_satisfied(system_snapshot),
+            (None, _) => false,
+        }
+    }
+
+    // The driver options that apply to the given device. An option applies if
+    // its condition holds for the device and mentions at least one named
+    // condition that picks out the device specifically.
+    pub fn driver_options_for_device<'a>(
+        &'a self,
+        pci_device: &'a PciDevice,
+        system_snapshot: &'a SystemSnapshot,
+    ) -> impl Iterator<Item = &'a DriverOption> {
+        self.driver_options
+            .iter()
+            .filter(move |driver_option| {
+                let is_for_device = driver_option
+                    .condition
+                    .positive_named_conditions()
+                    .into_iter()
+                    .any(|name| {
+                        self.named_conditions
+                            .get(name)
+                            .is_some_and(|criteria| criteria.is_device_specific())
+                            && self.is_named_condition_satisfied(
+                                name,
+                                Some(pci_device),
+                                system_snapshot,
+                            )
+                    });
+                is_for_device
+                    && driver_option
+                        .condition
+                        .evaluate(&|name| {
+                            self.is_named_condition_satisfied(
+                                name,
+                                Some(pci_device),
+                                system_snapshot,
+                            )
+                        })
+            })
+    }
+
+    pub fn package_info(&self, package_name: &str) -> Option<&PackageInfo> {
+        self.packages
+            .get(package_name)
+    }
+
+    // Packages the input file does not mention are taken to be in the
+    // repositories
+    pub fn package_source(&self, package_name: &str) -> PackageSource {
+        self.package_info(package_name)
+            .map(|package_info| package_info.source)
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Debug, Snafu)]
 #[non_exhaustive]
 pub enum Error {
     #[non_exhaustive]
-    #[snafu(display(""))]
-    DatabaseDummy {},
+    #[snafu(
+        display("could not read the database file at {:?}: {source}", path),
+        visibility(pub)
+    )]
+    ReadDatabaseFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("The database file at {:?} has incorrect format: {source}", path),
+        visibility(pub)
+    )]
+    DatabaseFileFormat {
+        path: PathBuf,
+        source: speedy::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(display("could not serialize the database: {source}"), visibility(pub))]
+    SerializeDatabase { source: speedy::Error },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not write the database file at {:?}: {source}", path),
+        visibility(pub)
+    )]
+    WriteDatabaseFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
 }
 
 // region: IMPORTS
 
-use snafu::Snafu;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use speedy::{Readable, Writable};
+
+use crate::{
+    app,
+    data::{Condition, Criteria, HardwareKind},
+    probe::{PciDevice, SystemSnapshot},
+};
 
 // endregion: IMPORTS
@@ -0,0 +1,319 @@
+pub fn default_hwdata_dirpath() -> PathBuf {
+    PathBuf::from("/usr/share/hwdata")
+}
+
+// The hwdata directory of the configuration, or else the default one
+pub fn configured_hwdata_dirpath(config: &Config) -> PathBuf {
+    config
+        .hwdata_directory
+        .clone()
+        .map(PathBuf::from)
+        .unwrap_or_else(default_hwdata_dirpath)
+}
+
+// The vendor, device and subsystem names of the PCI IDs, as listed by the
+// `pci.ids` file of the hwdata project
+#[derive(Clone, Debug, Default)]
+pub struct HwData {
+    pub pci: IdDatabase,
+}
+
+impl HwData {
+    // Read the ID file from the given directory. A file that cannot be read
+    // is replaced by the copy bundled with aldm, which only knows the most
+    // common vendors and devices.
+    pub fn load(hwdata_dirpath: &Path) -> Self {
+        let ids_filepath = hwdata_dirpath.join("pci.ids");
+        let pci = IdDatabase::read_from_file(&ids_filepath).unwrap_or_else(|error| {
+            tracing::debug!("Using the bundled pci.ids, since {}", error);
+            IdDatabase::parse(BUNDLED_PCI_IDS)
+        });
+        HwData { pci }
+    }
+
+    pub fn pci_device_name(&self, pci_device: &PciDevice) -> DeviceName {
+        self.pci
+            .device_name(
+                pci_device.vendor_id,
+                pci_device.device_id,
+                (
+                    pci_device.subsystem_vendor_id,
+                    pci_device.subsystem_device_id,
+                ),
+            )
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct IdDatabase {
+    vendors: HashMap<u16, VendorEntry>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct VendorEntry {
+    name: String,
+    devices: HashMap<u16, DeviceEntry>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct DeviceEntry {
+    name: String,
+    subsystems: HashMap<(u16, u16), String>,
+}
+
+impl IdDatabase {
+    pub fn read_from_file(ids_filepath: &Path) -> Result<Self, Error> {
+        let ids = fs::read(ids_filepath).context(ReadIdsFileSnafu {
+            path: ids_filepath.to_owned(),
+        })?;
+        // Some names are not valid UTF-8 in older copies
+        Ok(Self::parse(&String::from_utf8_lossy(&ids)))
+    }
+
+    // Parse the ID file format, where vendors are at the top level, their
+    // devices are indented by one tab and the subsystems of a device by two:
+    //
+    // 10de  NVIDIA Corporation
+    // 	2684  AD102 [GeForce RTX 4090]
+    // 		1043 889d  ROG Strix GeForce RTX 4090
+    //
+    // The vendor list is followed by other lists, such as the device classes,
+    // whose top level lines do not start with a vendor ID and are skipped
+    // along with everything below them.
+    pub fn parse(ids: &str) -> Self {
+        let mut vendors = HashMap::<u16, VendorEntry>::new();
+        let mut vendor_id = None;
+        let mut device_id = None;
+        for line in ids.lines() {
+            if line.starts_with('#')
+                || line
+                    .trim()
+                    .is_empty()
+            {
+                continue;
+            }
+
+            if let Some(line) = line.strip_prefix("\t\t") {
+                let Some(device) = vendor_id
+                    .and_then(|vendor_id| vendors.get_mut(&vendor_id))
+                    .zip(device_id)
+                    .and_then(|(vendor, device_id)| {
+                        vendor
+                            .devices
+                            .get_mut(&device_id)
+                    })
+                else {
+                    continue;
+                };
+                if let Some((subsystem_vendor_id, subsystem_device_id, name)) = parse_id(line)
+                    .and_then(|(subsystem_vendor_id, line)| {
+                        let (subsystem_device_id, name) = parse_id(line)?;
+                        Some((subsystem_vendor_id, subsystem_device_id, name))
+                    })
+                {
+                    device
+                        .subsystems
+                        .insert((subsystem_vendor_id, subsystem_device_id), name.to_owned());
+                }
+            } else if let Some(line) = line.strip_prefix('\t') {
+                let Some(vendor) = vendor_id.and_then(|vendor_id| vendors.get_mut(&vendor_id))
+                else {
+                    continue;
+                };
+                device_id = parse_id(line).map(|(id, name)| {
+                    vendor
+                        .devices
+                        .insert(
+                            id,
+                            DeviceEntry {
+                                name: name.to_owned(),
+                                ..Default::default()
+                            },
+                        );
+                    id
+                });
+            } else {
+                device_id = None;
+                vendor_id = parse_id(line).map(|(id, name)| {
+                    vendors.insert(
+                        id,
+                        VendorEntry {
+                            name: name.to_owned(),
+                            ..Default::default()
+                        },
+                    );
+                    id
+                });
+            }
+        }
+        IdDatabase { vendors }
+    }
+
+    pub fn vendor_name(&self, vendor_id: u16) -> Option<&str> {
+        self.vendors
+            .get(&vendor_id)
+            .map(|vendor| vendor.name.as_str())
+    }
+
+    pub fn device_name(
+        &self,
+        vendor_id: u16,
+        device_id: u16,
+        subsystem_ids: (u16, u16),
+    ) -> DeviceName {
+        let device = self
+            .vendors
+            .get(&vendor_id)
+            .and_then(|vendor| {
+                vendor
+                    .devices
+                    .get(&device_id)
+            });
+        let subsystem = Some(subsystem_ids)
+            .filter(|(subsystem_vendor_id, _)| *subsystem_vendor_id != 0)
+            .and_then(|(subsystem_vendor_id, subsystem_device_id)| {
+                let subsystem_name = device.and_then(|device| {
+                    device
+                        .subsystems
+                        .get(&(subsystem_vendor_id, subsystem_device_id))
+                });
+                match (self.vendor_name(subsystem_vendor_id), subsystem_name) {
+                    (Some(vendor_name), Some(subsystem_name)) => Some(format!(
+                        "{} {}",
+                        short_vendor_name(vendor_name),
+                        subsystem_name
+                    )),
+                    (Some(vendor_name), None) => Some(vendor_name.to_owned()),
+                    (None, subsystem_name) => subsystem_name.cloned(),
+                }
+            });
+        DeviceName {
+            vendor: self
+                .vendor_name(vendor_id)
+                .map(str::to_owned),
+            device: device.map(|device| device.name.clone()),
+            subsystem,
+        }
+    }
+}
+
+// The names of a device as listed in the ID files, such as `NVIDIA
+// Corporation` and `AD102 [GeForce RTX 4090]`. It is displayed in the short
+// form that users know from the box, as `NVIDIA GeForce RTX 4090`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct DeviceName {
+    pub vendor: Option<String>,
+    pub device: Option<String>,
+    pub subsystem: Option<String>,
+}
+
+impl DeviceName {
+    pub fn is_empty(&self) -> bool {
+        self.vendor
+            .is_none()
+            && self
+                .device
+                .is_none()
+    }
+}
+
+impl fmt::Display for DeviceName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let vendor = self
+            .vendor
+            .as_deref()
+            .map(short_vendor_name);
+        let device = self
+            .device
+            .as_deref()
+            .map(marketing_name);
+        match (vendor, device) {
+            (Some(vendor), Some(device)) if device.starts_with(vendor) => write!(f, "{device}"),
+            (Some(vendor), Some(device)) => write!(f, "{vendor} {device}"),
+            (Some(vendor), None) => write!(f, "{vendor}"),
+            (None, Some(device)) => write!(f, "{device}"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+fn parse_id(line: &str) -> Option<(u16, &str)> {
+    let (id, name) = line.split_once(char::is_whitespace)?;
+    if id.len() != 4 {
+        return None;
+    }
+    Some((u16::from_str_radix(id, 16).ok()?, name.trim()))
+}
+
+// `Advanced Micro Devices, Inc. [AMD/ATI]` becomes `AMD/ATI` and `NVIDIA
+// Corporation` becomes `NVIDIA`
+fn short_vendor_name(vendor_name: &str) -> &str {
+    if let Some(bracketed_name) = bracketed_name(vendor_name) {
+        return bracketed_name;
+    }
+    let mut short_name = vendor_name;
+    for suffix in [
+        "Corporation",
+        "Corp.",
+        "Inc.",
+        "Co., Ltd.",
+        "Co., Ltd",
+        "Ltd.",
+        "Limited",
+        "GmbH",
+    ] {
+        short_name = short_name
+            .trim_end()
+            .trim_end_matches(suffix)
+            .trim_end()
+            .trim_end_matches(',');
+    }
+    match short_name.is_empty() {
+        true => vendor_name,
+        false => short_name,
+    }
+}
+
+// `AD102 [GeForce RTX 4090]` becomes `GeForce RTX 4090`
+fn marketing_name(device_name: &str) -> &str {
+    bracketed_name(device_name).unwrap_or(device_name)
+}
+
+fn bracketed_name(name: &str) -> Option<&str> {
+    let (_, bracketed_name) = name
+        .trim_end()
+        .strip_suffix(']')?
+        .rsplit_once('[')?;
+    Some(bracketed_name).filter(|bracketed_name| !bracketed_name.is_empty())
+}
+
+const BUNDLED_PCI_IDS: &str = include_str!("../../resources/hwdata/pci.ids");
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(
+        display("could not read the ID file at {:?}: {source}", path),
+        visibility(pub)
+    )]
+    ReadIdsFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+// region: IMPORTS
+
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use snafu::{ResultExt, Snafu};
+
+use crate::{app::Config, probe::PciDevice};
+
+// endregion: IMPORTS
@@ -1,13 +1,737 @@
+// The human-readable input file from which the database is generated. See
+// `aldm-db/input-file.yaml` for a documented example.
+#[derive(Clone, Debug, Deserialize)]
+pub struct InputFile {
+    pub version: String,
+
+    #[serde(default)]
+    pub driver_options: Vec<DriverOptionInput>,
+
+    // A list of single-entry maps, so that the order in the file is preserved
+    #[serde(default)]
+    pub named_conditions: Vec<BTreeMap<String, CriteriaInput>>,
+
+    #[serde(default)]
+    pub driver_tags: BTreeMap<String, serde_yaml::Value>,
+
+    // Packages that are in the Arch User Repository (AUR) instead of the
+    // repositories
+    #[serde(default)]
+    pub aur_packages: OneOrMany,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DriverOptionInput {
+    pub id: Option<String>,
+    pub condition: String,
+    pub install: OneOrMany,
+
+    // Installed along with the driver when the device is the secondary GPU of
+    // a system with hybrid graphics, such as `nvidia-prime` for PRIME render
+    // offload
+    #[serde(default)]
+    pub hybrid_graphics_install: OneOrMany,
+
+    #[serde(default)]
+    pub modprobe: ModprobeInput,
+
+    // The modules to load early from the initramfs, such as `nvidia_drm`
+    #[serde(default)]
+    pub early_kms: OneOrMany,
+
+    // Added to the kernel command line in the boot loader configuration, such
+    // as `nvidia-drm.fbdev=1`
+    #[serde(default)]
+    pub kernel_parameters: OneOrMany,
+
+    // What installing the driver does besides installing its packages, which
+    // is undone when the driver is removed
+    #[serde(default)]
+    pub actions: Vec<ActionInput>,
+}
+
+// Each action is a map with a single key naming it, as in
+// `- enable_service: nvidia-suspend.service`. Blacklisting modules, kernel
+// parameters and mkinitcpio modules are the same as the `modprobe`,
+// `kernel_parameters` and `early_kms` keys of a driver option.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "ActionMap")]
+pub enum ActionInput {
+    EnableService(OneOrMany),
+    WriteFile(WriteFileInput),
+    BlacklistModule(OneOrMany),
+    KernelParam(OneOrMany),
+    MkinitcpioModule(OneOrMany),
+}
+
+// serde_yaml reads enums from tags like `!enable_service`, so the map form is
+// read into this first
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ActionMap {
+    enable_service: Option<OneOrMany>,
+    write_file: Option<WriteFileInput>,
+    blacklist_module: Option<OneOrMany>,
+    kernel_param: Option<OneOrMany>,
+    mkinitcpio_module: Option<OneOrMany>,
+}
+
+impl TryFrom<ActionMap> for ActionInput {
+    type Error = String;
+
+    fn try_from(action_map: ActionMap) -> Result<Self, Self::Error> {
+        let actions = [
+            action_map
+                .enable_service
+                .map(ActionInput::EnableService),
+            action_map
+                .write_file
+                .map(ActionInput::WriteFile),
+            action_map
+                .blacklist_module
+                .map(ActionInput::BlacklistModule),
+            action_map
+                .kernel_param
+                .map(ActionInput::KernelParam),
+            action_map
+                .mkinitcpio_module
+                .map(ActionInput::MkinitcpioModule),
+        ];
+        let mut actions = actions
+            .into_iter()
+            .flatten();
+        match (actions.next(), actions.next()) {
+            (Some(action), None) => Ok(action),
+            _ => Err(String::from(
+                "each action should have exactly one of the keys enable_service, write_file, blacklist_module, kernel_param and mkinitcpio_module",
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WriteFileInput {
+    pub path: String,
+    pub contents: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModprobeInput {
+    #[serde(default)]
+    pub blacklist: OneOrMany,
+
+    #[serde(default)]
+    pub options: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CriteriaInput {
+    #[serde(default)]
+    pub vendor_id: OneOrMany,
+
+    #[serde(default)]
+    pub device_id: OneOrMany,
+
+    #[serde(default)]
+    pub bound_driver: OneOrMany,
+
+    #[serde(default)]
+    pub hybrid_graphics: Option<bool>,
+
+    #[serde(default)]
+    pub kernel: OneOrMany,
+
+    #[serde(default)]
+    pub package: OneOrMany,
+
+    #[serde(default)]
+    pub driver_package: OneOrMany,
+}
+
+// A single value or a list of values, all kept as strings. IDs like `2684` are
+// read by YAML as integers, so integers are converted back to their digits.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OneOrMany(pub Vec<String>);
+
+impl<'de> Deserialize<'de> for OneOrMany {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct OneOrManyVisitor;
+
+        impl<'de> Visitor<'de> for OneOrManyVisitor {
+            type Value = OneOrMany;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a string or a list of strings")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                Ok(OneOrMany(vec![value.to_owned()]))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                Ok(OneOrMany(vec![value.to_string()]))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+                Ok(OneOrMany(vec![value.to_string()]))
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+                Err(E::custom(format!(
+                    "the value {value} was read as a number. Quote values like 1e02 so that they are read as text"
+                )))
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut values = Vec::new();
+                while let Some(OneOrMany(value)) = seq.next_element()? {
+                    values.extend(value);
+                }
+                Ok(OneOrMany(values))
+            }
+        }
+
+        deserializer.deserialize_any(OneOrManyVisitor)
+    }
+}
+
+impl InputFile {
+    pub fn read_from_file(input_filepath: &Path) -> Result<Self, Error> {
+        let input_file = File::open(input_filepath).context(ReadInputFileSnafu {
+            path: input_filepath.to_owned(),
+        })?;
+        serde_yaml::from_reader(BufReader::new(input_file)).context(InputFileFormatSnafu {
+            path: input_filepath.to_owned(),
+        })
+    }
+
+    // Validate the input and compile it into a database
+    pub fn compile(&self) -> Result<Database, Error> {
+        let mut named_conditions = BTreeMap::new();
+        for (name, criteria_input) in self
+            .named_conditions
+            .iter()
+            .flatten()
+        {
+            if named_conditions.contains_key(name) {
+                return DuplicateNamedConditionSnafu { name }.fail();
+            }
+            named_conditions.insert(name.clone(), compile_criteria(name, criteria_input)?);
+        }
+
+        let mut driver_options: Vec<DriverOption> = Vec::new();
+        for driver_option_input in &self.driver_options {
+            let condition_expression = &driver_option_input.condition;
+            let condition = condition_expression
+                .parse::<Condition>()
+                .context(InvalidConditionSnafu {})?;
+            if let Some(name) = condition
+                .named_conditions()
+                .into_iter()
+                .find(|name| !named_conditions.contains_key(*name))
+            {
+                return UnknownNamedConditionSnafu {
+                    name,
+                    condition: condition_expression,
+                }
+                .fail();
+            }
+
+            let packages = trimmed_packages(&driver_option_input.install);
+            if packages.is_empty() {
+                return EmptyInstallSnafu {
+                    condition: condition_expression,
+                }
+                .fail();
+            }
+
+            let id = driver_option_input
+                .id
+                .clone()
+                .unwrap_or_else(|| packages.join("+"));
+            if driver_options
+                .iter()
+                .any(|driver_option| driver_option.id == id)
+            {
+                return DuplicateDriverOptionIdSnafu { id }.fail();
+            }
+
+            let modprobe = compile_modprobe(&id, &driver_option_input.modprobe)?;
+            let early_kms_modules = driver_option_input
+                .early_kms
+                .0
+                .iter()
+                .map(|module| check_module_name(&id, module))
+                .collect::<Result<_, _>>()?;
+            let kernel_parameters = driver_option_input
+                .kernel_parameters
+                .0
+                .iter()
+                .map(|parameter| check_kernel_parameter(&id, parameter))
+                .collect::<Result<_, _>>()?;
+            let mut driver_option = DriverOption {
+                id,
+                condition,
+                packages,
+                hybrid_graphics_packages: trimmed_packages(
+                    &driver_option_input.hybrid_graphics_install,
+                ),
+                modprobe,
+                early_kms_modules,
+                kernel_parameters,
+                services: vec![],
+                files: vec![],
+            };
+            compile_actions(&mut driver_option, &driver_option_input.actions)?;
+            driver_options.push(driver_option);
+        }
+
+        let mut packages = BTreeMap::new();
+        for (key, value) in &self.driver_tags {
+            collect_package_info(key, value, None, &BTreeSet::new(), &mut packages)?;
+        }
+        for package_name in &self.aur_packages.0 {
+            packages
+                .entry(
+                    package_name
+                        .trim()
+                        .to_owned(),
+                )
+                .or_default()
+                .source = PackageSource::Aur;
+        }
+
+        Ok(Database {
+            version: self.version.clone(),
+            driver_options,
+            named_conditions,
+            packages,
+        })
+    }
+}
+
+fn trimmed_packages(packages: &OneOrMany) -> Vec<String> {
+    packages
+        .0
+        .iter()
+        .map(|package| {
+            package
+                .trim()
+                .to_owned()
+        })
+        .filter(|package| !package.is_empty())
+        .collect()
+}
+
+// Module names are single words
+fn check_module_name(id: &str, module: &str) -> Result<String, Error> {
+    let module = module.trim();
+    ensure!(
+        !module.is_empty() && !module.contains(char::is_whitespace),
+        InvalidModuleNameSnafu { id, module }
+    );
+    Ok(module.to_owned())
+}
+
+// Add what the actions do to the driver option. Repeated modules, parameters
+// and services are only added once.
+fn compile_actions(driver_option: &mut DriverOption, actions: &[ActionInput]) -> Result<(), Error> {
+    let id = driver_option
+        .id
+        .clone();
+    let push_new = |values: &mut Vec<String>, value: String| {
+        if !values.contains(&value) {
+            values.push(value);
+        }
+    };
+    for action in actions {
+        match action {
+            ActionInput::EnableService(units) => {
+                for unit in &units.0 {
+                    push_new(&mut driver_option.services, check_unit_name(&id, unit)?);
+                }
+            }
+            ActionInput::WriteFile(write_file) => {
+                let path = write_file
+                    .path
+                    .trim();
+                ensure!(
+                    Path::new(path).is_absolute()
+                        && !Path::new(path)
+                            .components()
+                            .any(|component| component == Component::ParentDir)
+                        && !path.ends_with('/'),
+                    InvalidFilePathSnafu { id: &id, path }
+                );
+                ensure!(
+                    !driver_option
+                        .files
+                        .iter()
+                        .any(|driver_file| driver_file.path == path),
+                    DuplicateFilePathSnafu { id: &id, path }
+                );
+                driver_option
+                    .files
+                    .push(DriverFile {
+                        path: path.to_owned(),
+                        contents: write_file
+                            .contents
+                            .clone(),
+                    });
+            }
+            ActionInput::BlacklistModule(modules) => {
+                for module in &modules.0 {
+                    push_new(
+                        &mut driver_option
+                            .modprobe
+                            .blacklist,
+                        check_module_name(&id, module)?,
+                    );
+                }
+            }
+            ActionInput::KernelParam(parameters) => {
+                for parameter in &parameters.0 {
+                    push_new(
+                        &mut driver_option.kernel_parameters,
+                        check_kernel_parameter(&id, parameter)?,
+                    );
+                }
+            }
+            ActionInput::MkinitcpioModule(modules) => {
+                for module in &modules.0 {
+                    push_new(
+                        &mut driver_option.early_kms_modules,
+                        check_module_name(&id, module)?,
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Units are single words like `nvidia-suspend.service`. Without a suffix, a
+// unit is a service, as for systemctl.
+fn check_unit_name(id: &str, unit: &str) -> Result<String, Error> {
+    let unit = unit.trim();
+    ensure!(
+        !unit.is_empty() && !unit.contains(|c: char| c.is_whitespace() || c == '/'),
+        InvalidUnitNameSnafu { id, unit }
+    );
+    Ok(match unit.contains('.') {
+        true => unit.to_owned(),
+        false => format!("{unit}.service"),
+    })
+}
+
+// Kernel parameters are single words, and quotes would end the quoted
+// command line of GRUB
+fn check_kernel_parameter(id: &str, parameter: &str) -> Result<String, Error> {
+    let parameter = parameter.trim();
+    ensure!(
+        !parameter.is_empty()
+            && !parameter.contains(|c: char| c.is_whitespace() || c == '"' || c == '\''),
+        InvalidKernelParameterSnafu { id, parameter }
+    );
+    Ok(parameter.to_owned())
+}
+
+// Module options cannot span lines
+fn compile_modprobe(id: &str, modprobe_input: &ModprobeInput) -> Result<ModprobeConfig, Error> {
+    let mut options = BTreeMap::new();
+    for (module, module_options) in &modprobe_input.options {
+        ensure!(
+            !module_options.contains('\n'),
+            InvalidModuleOptionsSnafu {
+                id,
+                module,
+                options: module_options
+            }
+        );
+        options.insert(
+            check_module_name(id, module)?,
+            module_options
+                .trim()
+                .to_owned(),
+        );
+    }
+    Ok(ModprobeConfig {
+        blacklist: modprobe_input
+            .blacklist
+            .0
+            .iter()
+            .map(|module| check_module_name(id, module))
+            .collect::<Result<_, _>>()?,
+        options,
+    })
+}
+
+fn compile_criteria(name: &str, criteria_input: &CriteriaInput) -> Result<Criteria, Error> {
+    let parse_ids = |ids: &OneOrMany| {
+        ids.0
+            .iter()
+            .map(|id| {
+                u16::from_str_radix(id.trim(), 16)
+                    .ok()
+                    .context(InvalidIdSnafu {
+                        name,
+                        id: id.clone(),
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let parse_requirements = |requirements: &OneOrMany| {
+        requirements
+            .0
+            .iter()
+            .map(|requirement| {
+                requirement
+                    .parse::<PackageRequirement>()
+                    .context(InvalidRequirementSnafu { name })
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+    Ok(Criteria {
+        vendor_ids: parse_ids(&criteria_input.vendor_id)?,
+        device_ids: parse_ids(&criteria_input.device_id)?,
+        bound_drivers: criteria_input
+            .bound_driver
+            .0
+            .iter()
+            .map(|driver| normalize_name(driver))
+            .collect(),
+        hybrid_graphics: criteria_input.hybrid_graphics,
+        kernels: parse_requirements(&criteria_input.kernel)?,
+        packages: parse_requirements(&criteria_input.package)?,
+        driver_packages: parse_requirements(&criteria_input.driver_package)?,
+    })
+}
+
+// Walk the driver tags tree. Keys that name a kind of hardware mark the
+// packages below them as drivers for that hardware. All other keys are tags,
+// several of which can share a key when separated by commas. The leaves are
+// package names.
+fn collect_package_info(
+    key: &str,
+    value: &serde_yaml::Value,
+    hardware_kind: Option<HardwareKind>,
+    tags: &BTreeSet<String>,
+    packages: &mut BTreeMap<String, PackageInfo>,
+) -> Result<(), Error> {
+    let mut hardware_kind = hardware_kind;
+    let mut tags = tags.clone();
+    match key.parse::<HardwareKind>() {
+        Ok(key_hardware_kind) => hardware_kind = Some(key_hardware_kind),
+        Err(_) => tags.extend(
+            key.split(',')
+                .map(normalize_tag)
+                .filter(|tag| !tag.is_empty()),
+        ),
+    }
+
+    let mut add_package = |package_name: &str| {
+        let package_info = packages
+            .entry(
+                package_name
+                    .trim()
+                    .to_owned(),
+            )
+            .or_default();
+        package_info
+            .hardware_kinds
+            .extend(hardware_kind);
+        package_info
+            .tags
+            .extend(tags.iter().cloned());
+    };
+
+    match value {
+        serde_yaml::Value::Null => {}
+        serde_yaml::Value::String(package_name) => add_package(package_name),
+        serde_yaml::Value::Sequence(package_names) => {
+            for package_name in package_names {
+                match package_name.as_str() {
+                    Some(package_name) => add_package(package_name),
+                    None => return InvalidDriverTagsSnafu { key }.fail(),
+                }
+            }
+        }
+        serde_yaml::Value::Mapping(mapping) => {
+            for (inner_key, inner_value) in mapping {
+                let inner_key = inner_key
+                    .as_str()
+                    .context(InvalidDriverTagsSnafu { key })?;
+                collect_package_info(inner_key, inner_value, hardware_kind, &tags, packages)?;
+            }
+        }
+        _ => return InvalidDriverTagsSnafu { key }.fail(),
+    }
+
+    Ok(())
+}
+
+// Hyphens and spaces in tags are replaced with underscores, as they are in the
+// tags supplied by the user.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim()
+        .to_lowercase()
+        .replace(['-', ' '], "_")
+}
+
 #[derive(Debug, Snafu)]
 #[non_exhaustive]
 pub enum Error {
     #[non_exhaustive]
-    #[snafu(display(""))]
-    InputFileDummy {},
+    #[snafu(
+        display("could not read the input file at {:?}: {source}", path),
+        visibility(pub)
+    )]
+    ReadInputFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("The input file at {:?} has incorrect format: {source}", path),
+        visibility(pub)
+    )]
+    InputFileFormat {
+        path: PathBuf,
+        source: serde_yaml::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the named condition '{name}' is defined more than once"),
+        visibility(pub)
+    )]
+    DuplicateNamedCondition { name: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the named condition '{name}' has '{id}', which is not a hexadecimal ID"),
+        visibility(pub)
+    )]
+    InvalidId { name: String, id: String },
+
+    #[non_exhaustive]
+    #[snafu(display("in the named condition '{name}': {source}"), visibility(pub))]
+    InvalidRequirement {
+        name: String,
+        source: requirement::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(display("{source}"), visibility(pub))]
+    InvalidCondition { source: condition::Error },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the condition '{condition}' refers to the unknown named condition '{name}'"),
+        visibility(pub)
+    )]
+    UnknownNamedCondition { name: String, condition: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the driver option with the condition '{condition}' installs nothing"),
+        visibility(pub)
+    )]
+    EmptyInstall { condition: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the driver option ID '{id}' is used more than once"),
+        visibility(pub)
+    )]
+    DuplicateDriverOptionId { id: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the driver option '{id}' has '{module}', which is not a kernel module name"),
+        visibility(pub)
+    )]
+    InvalidModuleName { id: String, module: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display(
+            "the driver option '{id}' has options for '{module}' that span lines: {options:?}"
+        ),
+        visibility(pub)
+    )]
+    InvalidModuleOptions {
+        id: String,
+        module: String,
+        options: String,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the driver option '{id}' has '{parameter}', which is not a kernel parameter"),
+        visibility(pub)
+    )]
+    InvalidKernelParameter { id: String, parameter: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the driver option '{id}' has '{unit}', which is not a systemd unit name"),
+        visibility(pub)
+    )]
+    InvalidUnitName { id: String, unit: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the driver option '{id}' writes to '{path}', which is not an absolute file path"),
+        visibility(pub)
+    )]
+    InvalidFilePath { id: String, path: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the driver option '{id}' writes to '{path}' more than once"),
+        visibility(pub)
+    )]
+    DuplicateFilePath { id: String, path: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the driver tags under '{key}' should be package names or nested tags"),
+        visibility(pub)
+    )]
+    InvalidDriverTags { key: String },
 }
 
 // region: IMPORTS
 
-use snafu::Snafu;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    fs::File,
+    io::BufReader,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+
+use crate::data::{
+    condition, normalize_name, requirement, Condition, Criteria, Database, DriverFile,
+    DriverOption, HardwareKind, ModprobeConfig, PackageInfo, PackageRequirement, PackageSource,
+};
 
 // endregion: IMPORTS
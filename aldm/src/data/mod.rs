@@ -19,6 +19,26 @@ pub enum HardwareKind {
     Audio,
 }
 
+impl HardwareKind {
+    // Classify a PCI device by its class code, which packs the class, subclass
+    // and programming interface into `0xCCSSPP`. The programming interface
+    // only tells apart variants of the same kind, such as VGA and 8514
+    // display controllers, so it does not change the kind.
+    pub fn from_pci_class(class_code: u32) -> Option<Self> {
+        let class = (class_code >> 16) & 0xff;
+        let subclass = (class_code >> 8) & 0xff;
+        match (class, subclass) {
+            // Display controllers: VGA, XGA, 3D and others
+            (0x03, _) => Some(HardwareKind::Graphics),
+            (0x02, 0x00) => Some(HardwareKind::Ethernet),
+            (0x02, 0x80) => Some(HardwareKind::Wireless),
+            // Multimedia audio and HD audio controllers
+            (0x04, 0x01) | (0x04, 0x03) => Some(HardwareKind::Audio),
+            _ => None,
+        }
+    }
+}
+
 impl FromStr for HardwareKind {
     type Err = Error;
 
@@ -83,14 +103,24 @@ use std::{fmt, str::FromStr};
 
 // region: MODULES
 
+pub mod condition;
+pub mod criteria;
 pub mod database;
+pub mod hwdata;
 pub mod input_file;
+pub mod requirement;
+pub mod version;
 
 // endregion: MODULES
 
 // region: RE-EXPORTS
 
+pub use condition::*;
+pub use criteria::*;
 pub use database::*;
+pub use hwdata::*;
 pub use input_file::*;
+pub use requirement::*;
+pub use version::*;
 
 // endregion: RE-EXPORTS
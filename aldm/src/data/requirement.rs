@@ -0,0 +1,206 @@
+// A package, optionally with a constraint on its version, like `linux>=6.6` or
+// `nvidia-utils=545.*`. The name is kept normalized, see `normalize_name`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Readable, Writable)]
+pub struct PackageRequirement {
+    pub name: String,
+    pub constraint: Option<VersionConstraint>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Readable, Writable)]
+pub struct VersionConstraint {
+    pub operator: VersionOperator,
+    pub version: Version,
+    // Whether the version ends with `.*`, like `545.*`, to match every
+    // version that starts with it. Only allowed with `=`.
+    pub is_wildcard: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Readable, Writable)]
+pub enum VersionOperator {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl PackageRequirement {
+    // Whether the given version of the package meets the constraint. A
+    // requirement without a constraint is met by any version.
+    pub fn is_met_by(&self, version: &Version) -> bool {
+        match &self.constraint {
+            Some(constraint) => constraint.is_met_by(version),
+            None => true,
+        }
+    }
+}
+
+impl VersionConstraint {
+    pub fn is_met_by(&self, version: &Version) -> bool {
+        if self.is_wildcard {
+            return self.matches_wildcard(version);
+        }
+        // Like in pacman, a constraint without a release, like `=1.0`, is met
+        // by any release of the version
+        let ordering = vercmp(
+            version.as_str(),
+            self.version
+                .as_str(),
+        );
+        match self.operator {
+            VersionOperator::Less => ordering == Ordering::Less,
+            VersionOperator::LessOrEqual => ordering != Ordering::Greater,
+            VersionOperator::Equal => ordering == Ordering::Equal,
+            VersionOperator::GreaterOrEqual => ordering != Ordering::Less,
+            VersionOperator::Greater => ordering == Ordering::Greater,
+        }
+    }
+
+    // `545.*` matches `545`, `545.29.06-1` and `545.29.06-2`, but not `5450`
+    fn matches_wildcard(&self, version: &Version) -> bool {
+        let prefix = self
+            .version
+            .upstream_version();
+        version.epoch() == self.version.epoch()
+            && version
+                .upstream_version()
+                .strip_prefix(prefix)
+                .is_some_and(|rest| {
+                    rest.is_empty()
+                        || !rest.starts_with(|character: char| character.is_ascii_alphanumeric())
+                })
+    }
+}
+
+impl FromStr for PackageRequirement {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let requirement = s.trim();
+        let Some(operator_start) = requirement.find(['<', '>', '=']) else {
+            ensure!(
+                !requirement.is_empty(),
+                MalformedRequirementSnafu {
+                    requirement,
+                    reason: "the package name is missing"
+                }
+            );
+            return Ok(PackageRequirement {
+                name: normalize_name(requirement),
+                constraint: None,
+            });
+        };
+
+        let name = requirement[..operator_start].trim();
+        let rest = &requirement[operator_start..];
+        let (operator, version) = [
+            ("<=", VersionOperator::LessOrEqual),
+            (">=", VersionOperator::GreaterOrEqual),
+            ("==", VersionOperator::Equal),
+            ("<", VersionOperator::Less),
+            (">", VersionOperator::Greater),
+            ("=", VersionOperator::Equal),
+        ]
+        .into_iter()
+        .find_map(|(symbol, operator)| {
+            rest.strip_prefix(symbol)
+                .map(|version| (operator, version.trim()))
+        })
+        .context(MalformedRequirementSnafu {
+            requirement,
+            reason: "the comparison is not one of <, <=, =, >= or >",
+        })?;
+        let (version, is_wildcard) = match version.strip_suffix(".*") {
+            Some(version) => (version, true),
+            None => (version, false),
+        };
+
+        ensure!(
+            !name.is_empty(),
+            MalformedRequirementSnafu {
+                requirement,
+                reason: "the package name is missing"
+            }
+        );
+        ensure!(
+            !version.is_empty(),
+            MalformedRequirementSnafu {
+                requirement,
+                reason: "the version is missing"
+            }
+        );
+        ensure!(
+            !version.contains(['<', '>', '=', '*', ' ']),
+            MalformedRequirementSnafu {
+                requirement,
+                reason: "the version contains characters that versions cannot have"
+            }
+        );
+        ensure!(
+            !is_wildcard || operator == VersionOperator::Equal,
+            MalformedRequirementSnafu {
+                requirement,
+                reason: "a version ending with .* can only be compared with ="
+            }
+        );
+
+        Ok(PackageRequirement {
+            name: normalize_name(name),
+            constraint: Some(VersionConstraint {
+                operator,
+                version: Version::new(version),
+                is_wildcard,
+            }),
+        })
+    }
+}
+
+impl fmt::Display for PackageRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(constraint) = &self.constraint {
+            write!(f, "{constraint}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for VersionConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operator = match self.operator {
+            VersionOperator::Less => "<",
+            VersionOperator::LessOrEqual => "<=",
+            VersionOperator::Equal => "=",
+            VersionOperator::GreaterOrEqual => ">=",
+            VersionOperator::Greater => ">",
+        };
+        write!(f, "{}{}", operator, self.version)?;
+        if self.is_wildcard {
+            write!(f, ".*")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(
+        display("'{requirement}' is not a valid package requirement: {reason}"),
+        visibility(pub)
+    )]
+    MalformedRequirement { requirement: String, reason: String },
+}
+
+// region: IMPORTS
+
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use serde::Serialize;
+use snafu::{ensure, OptionExt, Snafu};
+use speedy::{Readable, Writable};
+
+use crate::data::{normalize_name, vercmp, Version};
+
+// endregion: IMPORTS
@@ -0,0 +1,334 @@
+// A package version, ordered the way pacman orders them. A version looks like
+// `[<epoch>:]<version>[-<release>]`, like `1:550.78-2`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Readable, Writable)]
+#[serde(transparent)]
+pub struct Version(String);
+
+impl Version {
+    pub fn new(version: &str) -> Self {
+        Version(
+            version
+                .trim()
+                .to_owned(),
+        )
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    // The epoch, which is `0` when there is none
+    pub fn epoch(&self) -> &str {
+        split_version(&self.0).0
+    }
+
+    // The version without the epoch and the release
+    pub fn upstream_version(&self) -> &str {
+        split_version(&self.0).1
+    }
+
+    pub fn release(&self) -> Option<&str> {
+        split_version(&self.0).2
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Unlike `vercmp`, a version without a release is older than the same version
+// with any release, which keeps the order total: `vercmp` has `1.0` equal to
+// both `1.0-1` and `1.0-2`. Requirements match with `vercmp` instead.
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        vercmp(&self.0, &other.0).then_with(|| {
+            self.release()
+                .is_some()
+                .cmp(
+                    &other
+                        .release()
+                        .is_some(),
+                )
+        })
+    }
+}
+
+impl FromStr for Version {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Version::new(s))
+    }
+}
+
+impl From<&str> for Version {
+    fn from(version: &str) -> Self {
+        Version::new(version)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// Compare two versions like pacman's `vercmp`. The epochs are compared first,
+// then the versions, and the releases last, but only if both versions have
+// one, so that `1.0` matches any release of `1.0`.
+pub fn vercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    let (epoch_a, version_a, release_a) = split_version(a);
+    let (epoch_b, version_b, release_b) = split_version(b);
+    rpmvercmp(epoch_a, epoch_b)
+        .then_with(|| rpmvercmp(version_a, version_b))
+        .then_with(|| match (release_a, release_b) {
+            (Some(release_a), Some(release_b)) => rpmvercmp(release_a, release_b),
+            _ => Ordering::Equal,
+        })
+}
+
+// Split a version into its epoch, version and release. The epoch is the digits
+// before a `:`, and the release is whatever follows the last `-`.
+fn split_version(version: &str) -> (&str, &str, Option<&str>) {
+    let digit_count = version
+        .bytes()
+        .take_while(u8::is_ascii_digit)
+        .count();
+    let (epoch, rest) = match version[digit_count..].starts_with(':') {
+        true => (
+            match digit_count {
+                0 => "0",
+                _ => &version[..digit_count],
+            },
+            &version[digit_count + 1..],
+        ),
+        false => ("0", version),
+    };
+    match rest.rsplit_once('-') {
+        Some((version, release)) => (epoch, version, Some(release)),
+        None => (epoch, rest, None),
+    }
+}
+
+// Compare two version segments like librpm's `rpmvercmp`, which pacman uses.
+// The strings are split into runs of digits and runs of letters, separated by
+// anything else. Numeric runs compare as numbers and are newer than alphabetic
+// runs, and a longer separator wins over a shorter one.
+fn rpmvercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let (mut one, mut two) = (0, 0);
+
+    while one < a.len() && two < b.len() {
+        let separator_start_one = one;
+        let separator_start_two = two;
+        while one < a.len() && !a[one].is_ascii_alphanumeric() {
+            one += 1;
+        }
+        while two < b.len() && !b[two].is_ascii_alphanumeric() {
+            two += 1;
+        }
+        if one == a.len() || two == b.len() {
+            break;
+        }
+        let separator_length_one = one - separator_start_one;
+        let separator_length_two = two - separator_start_two;
+        if separator_length_one != separator_length_two {
+            return separator_length_one.cmp(&separator_length_two);
+        }
+
+        let is_numeric = a[one].is_ascii_digit();
+        let is_segment_byte = |byte: &u8| match is_numeric {
+            true => byte.is_ascii_digit(),
+            false => byte.is_ascii_alphabetic(),
+        };
+        let end_one = one
+            + a[one..]
+                .iter()
+                .take_while(|byte| is_segment_byte(byte))
+                .count();
+        let end_two = two
+            + b[two..]
+                .iter()
+                .take_while(|byte| is_segment_byte(byte))
+                .count();
+
+        // The segments are of different kinds, and numbers are newer
+        if two == end_two {
+            return match is_numeric {
+                true => Ordering::Greater,
+                false => Ordering::Less,
+            };
+        }
+
+        let mut segment_one = &a[one..end_one];
+        let mut segment_two = &b[two..end_two];
+        if is_numeric {
+            segment_one = trim_leading_zeros(segment_one);
+            segment_two = trim_leading_zeros(segment_two);
+            // The longer number is the bigger one
+            let ordering = segment_one
+                .len()
+                .cmp(&segment_two.len());
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        let ordering = segment_one.cmp(segment_two);
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+
+        one = end_one;
+        two = end_two;
+    }
+
+    let rest_one = &a[one..];
+    let rest_two = &b[two..];
+    if rest_one.is_empty() && rest_two.is_empty() {
+        return Ordering::Equal;
+    }
+    // A remaining alphabetic segment never wins over nothing, as in
+    // `1.0alpha` < `1.0`, while anything else does, as in `1.0.1` > `1.0`
+    match (rest_one.is_empty()
+        && !rest_two
+            .first()
+            .is_some_and(u8::is_ascii_alphabetic))
+        || rest_one
+            .first()
+            .is_some_and(u8::is_ascii_alphabetic)
+    {
+        true => Ordering::Less,
+        false => Ordering::Greater,
+    }
+}
+
+fn trim_leading_zeros(digits: &[u8]) -> &[u8] {
+    let zero_count = digits
+        .iter()
+        .take_while(|digit| **digit == b'0')
+        .count();
+    &digits[zero_count..]
+}
+
+// region: IMPORTS
+
+use std::{cmp::Ordering, convert::Infallible, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use speedy::{Readable, Writable};
+
+// endregion: IMPORTS
+
+#[cfg(test)]
+mod tests {
+    // The cases of pacman's `vercmptest.sh`, each checked both ways
+    #[test]
+    fn vercmp_matches_pacman() {
+        let cases = [
+            // Same length, no release
+            ("1.5.0", "1.5.0", Ordering::Equal),
+            ("1.5.1", "1.5.0", Ordering::Greater),
+            // Mixed length
+            ("1.5.1", "1.5", Ordering::Greater),
+            // With releases
+            ("1.5.0-1", "1.5.0-1", Ordering::Equal),
+            ("1.5.0-1", "1.5.0-2", Ordering::Less),
+            ("1.5.0-1", "1.5.1-1", Ordering::Less),
+            ("1.5.0-2", "1.5.1-1", Ordering::Less),
+            ("1.5-1", "1.5.1-1", Ordering::Less),
+            ("1.5-2", "1.5.1-1", Ordering::Less),
+            ("1.5-2", "1.5.1-2", Ordering::Less),
+            // A missing release matches any release
+            ("1.5", "1.5-1", Ordering::Equal),
+            ("1.1-1", "1.1", Ordering::Equal),
+            ("1.0-1", "1.1", Ordering::Less),
+            ("1.1-1", "1.0", Ordering::Greater),
+            // Letters
+            ("1.5b-1", "1.5-1", Ordering::Less),
+            ("1.5b", "1.5", Ordering::Less),
+            ("1.5b-1", "1.5", Ordering::Less),
+            ("1.5b", "1.5.1", Ordering::Less),
+            ("1.0a", "1.0", Ordering::Less),
+            ("1.0a", "1.0alpha", Ordering::Less),
+            ("1.0alpha", "1.0b", Ordering::Less),
+            ("1.0b", "1.0beta", Ordering::Less),
+            ("1.0beta", "1.0rc", Ordering::Less),
+            ("1.0rc", "1.0", Ordering::Less),
+            ("1.5.a", "1.5", Ordering::Greater),
+            ("1.5.b", "1.5.a", Ordering::Greater),
+            ("1.5.1", "1.5.b", Ordering::Greater),
+            ("1.5.b-1", "1.5.b", Ordering::Equal),
+            ("1.5-1", "1.5.b", Ordering::Less),
+            // Separators
+            ("2.0", "2_0", Ordering::Equal),
+            ("2.0_a", "2_0.a", Ordering::Equal),
+            ("2.0a", "2.0.a", Ordering::Less),
+            ("2___a", "2_a", Ordering::Greater),
+            ("1.0", "1.0.", Ordering::Less),
+            // Leading zeros
+            ("1.01", "1.1", Ordering::Equal),
+            ("1.010", "1.9", Ordering::Greater),
+            // Epochs
+            ("0:1.0", "0:1.0", Ordering::Equal),
+            ("0:1.0", "0:1.1", Ordering::Less),
+            ("1:1.0", "0:1.0", Ordering::Greater),
+            ("1:1.0", "0:1.1", Ordering::Greater),
+            ("1:1.0", "2:1.1", Ordering::Less),
+            ("1:1.0", "0:1.0-1", Ordering::Greater),
+            ("1:1.0-1", "0:1.1-1", Ordering::Greater),
+            ("0:1.0", "1.0", Ordering::Equal),
+            ("0:1.1", "1.0", Ordering::Greater),
+            ("0:1.1", "1.1", Ordering::Equal),
+            ("1.0", "0:1.1", Ordering::Less),
+            ("1:1.0", "1.0", Ordering::Greater),
+            ("1:1.1", "1.1", Ordering::Greater),
+            ("1.1", "1:1.1", Ordering::Less),
+            ("1:550.78-1", "550.78-2", Ordering::Greater),
+        ];
+        for (a, b, ordering) in cases {
+            assert_eq!(vercmp(a, b), ordering, "vercmp({a}, {b})");
+            assert_eq!(vercmp(b, a), ordering.reverse(), "vercmp({b}, {a})");
+        }
+    }
+
+    #[test]
+    fn order_is_total() {
+        let mut versions = ["1.0-2", "1.0", "1.0-10", "0:1.0-1", "1.0a", "1:0.1"]
+            .map(Version::new)
+            .to_vec();
+        versions.sort();
+        assert_eq!(
+            versions
+                .iter()
+                .map(Version::as_str)
+                .collect::<Vec<_>>(),
+            ["1.0a", "1.0", "0:1.0-1", "1.0-2", "1.0-10", "1:0.1"]
+        );
+        assert_ne!(Version::new("1.0"), Version::new("1.0-1"));
+        assert_eq!(Version::new("1.0-1"), Version::new("0:1.0-1"));
+    }
+
+    // region: IMPORTS
+
+    use std::cmp::Ordering;
+
+    use super::{vercmp, Version};
+
+    // endregion: IMPORTS
+}
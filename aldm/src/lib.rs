@@ -38,7 +38,10 @@ use std::any;
 
 pub mod actions;
 pub mod app;
+pub mod backend;
 pub mod data;
+pub mod probe;
+pub mod system;
 pub mod ui;
 
 // endregion: MODULES
@@ -0,0 +1,142 @@
+// What aldm knows about the system it runs on. Everything is read from below
+// the given root directory, so that a snapshot can also be taken of a mounted
+// system or of a fake system tree in tests.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SystemSnapshot {
+    pub pci_devices: Vec<PciDevice>,
+    pub kernel_release: Option<String>,
+    pub installed_kernels: BTreeSet<String>,
+    pub installed_packages: BTreeMap<String, Version>,
+    // Left out when serialized, as there are thousands of them
+    #[serde(skip)]
+    pub available_packages: BTreeMap<String, Version>,
+}
+
+impl SystemSnapshot {
+    #[tracing::instrument(level = "trace")]
+    pub fn probe(root: &Path) -> Result<Self, Error> {
+        let kernel_release = modules::probe_kernel_release(root);
+        let module_aliases = match &kernel_release {
+            Some(kernel_release) => {
+                ModuleAliases::read(root, kernel_release).context(ModulesSnafu {})?
+            }
+            None => ModuleAliases::default(),
+        };
+        let mut pci_devices = pci::probe_pci_devices(root).context(PciSnafu {})?;
+        for pci_device in &mut pci_devices {
+            if let Some(modalias) = &pci_device.modalias {
+                pci_device.candidate_modules = module_aliases.modules_for(modalias);
+            }
+        }
+
+        let system_snapshot = SystemSnapshot {
+            pci_devices,
+            kernel_release,
+            installed_kernels: packages::probe_installed_kernels(root),
+            installed_packages: packages::probe_installed_packages(root)
+                .context(PackagesSnafu {})?,
+            available_packages: packages::probe_available_packages(root),
+        };
+        tracing::trace!(
+            "Probed {} PCI device(s), {} kernel(s), {} installed package(s) and {} available package(s)",
+            system_snapshot
+                .pci_devices
+                .len(),
+            system_snapshot
+                .installed_kernels
+                .len(),
+            system_snapshot
+                .installed_packages
+                .len(),
+            system_snapshot
+                .available_packages
+                .len()
+        );
+        Ok(system_snapshot)
+    }
+
+    pub fn is_package_installed(&self, package_name: &str) -> bool {
+        self.installed_packages
+            .contains_key(package_name)
+    }
+
+    // The GPUs of the system, integrated or not
+    pub fn display_controllers(&self) -> impl Iterator<Item = &PciDevice> {
+        self.pci_devices
+            .iter()
+            .filter(|pci_device| pci_device.hardware_kind() == Some(HardwareKind::Graphics))
+    }
+
+    // Whether the system has more than one GPU, usually an integrated Intel or
+    // AMD GPU that drives the display and a discrete NVIDIA GPU that renders
+    // on demand, as with NVIDIA Optimus and PRIME render offload
+    pub fn is_hybrid_graphics(&self) -> bool {
+        self.display_controllers()
+            .count()
+            > 1
+    }
+
+    pub fn package_version(&self, package_name: &str) -> Option<&Version> {
+        self.installed_packages
+            .get(package_name)
+    }
+}
+
+pub fn default_root() -> PathBuf {
+    PathBuf::from("/")
+}
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(display("in probing PCI devices: {source}"), visibility(pub))]
+    Pci {
+        #[snafu(backtrace)]
+        source: pci::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(display("in probing kernel modules: {source}"), visibility(pub))]
+    Modules {
+        #[snafu(backtrace)]
+        source: modules::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(display("in probing packages: {source}"), visibility(pub))]
+    Packages {
+        #[snafu(backtrace)]
+        source: packages::Error,
+    },
+}
+
+// region: IMPORTS
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use snafu::{ResultExt, Snafu};
+
+use crate::data::{HardwareKind, Version};
+
+// endregion: IMPORTS
+
+// region: MODULES
+
+pub mod modules;
+pub mod packages;
+pub mod pci;
+
+// endregion: MODULES
+
+// region: RE-EXPORTS
+
+pub use modules::*;
+pub use packages::*;
+pub use pci::*;
+
+// endregion: RE-EXPORTS
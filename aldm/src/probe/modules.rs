@@ -0,0 +1,153 @@
+// The release of the running kernel, as in `6.6.1-arch1-1`
+pub fn probe_kernel_release(root: &Path) -> Option<String> {
+    let kernel_release = fs::read_to_string(root.join("proc/sys/kernel/osrelease")).ok()?;
+    Some(
+        kernel_release
+            .trim()
+            .to_owned(),
+    )
+    .filter(|kernel_release| !kernel_release.is_empty())
+}
+
+// The PCI aliases of the kernel modules, which say which modules can drive a
+// device with a given modalias
+#[derive(Clone, Debug, Default)]
+pub struct ModuleAliases {
+    aliases: Vec<(String, String)>,
+}
+
+impl ModuleAliases {
+    // Read the `modules.alias` file of the given kernel, which has lines like
+    // `alias pci:v000010DEd*sv*sd*bc03sc*i* nouveau`
+    pub fn read(root: &Path, kernel_release: &str) -> Result<Self, Error> {
+        let aliases_filepath = root
+            .join("lib/modules")
+            .join(kernel_release)
+            .join("modules.alias");
+        let aliases = match fs::read_to_string(&aliases_filepath) {
+            Ok(aliases) => aliases,
+            // No modules, for example in containers
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                return Ok(ModuleAliases::default())
+            }
+            Err(error) => {
+                return Err(error).context(ReadModuleAliasesSnafu {
+                    path: aliases_filepath,
+                })
+            }
+        };
+
+        Ok(ModuleAliases {
+            aliases: aliases
+                .lines()
+                .filter_map(|line| {
+                    let mut words = line.split_whitespace();
+                    match (words.next(), words.next(), words.next()) {
+                        (Some("alias"), Some(pattern), Some(module))
+                            if pattern.starts_with("pci:") =>
+                        {
+                            Some((pattern.to_owned(), module.to_owned()))
+                        }
+                        _ => None,
+                    }
+                })
+                .collect(),
+        })
+    }
+
+    // The modules whose aliases match the modalias, in the order of the file
+    pub fn modules_for(&self, modalias: &str) -> Vec<String> {
+        let mut modules = Vec::<String>::new();
+        for (pattern, module) in &self.aliases {
+            if !modules.contains(module) && glob_matches(pattern.as_bytes(), modalias.as_bytes()) {
+                modules.push(module.clone());
+            }
+        }
+        modules
+    }
+}
+
+// Match the shell-style patterns of module aliases, with `*`, `?` and
+// character classes like `[0-9]`. On a mismatch, the last `*` is retried with
+// one more character, which is enough as `*` matches any text.
+fn glob_matches(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut pattern_index, mut text_index) = (0, 0);
+    let mut last_star = None;
+    while text_index < text.len() {
+        let step = match pattern.get(pattern_index) {
+            Some(b'*') => {
+                last_star = Some((pattern_index, text_index));
+                pattern_index += 1;
+                continue;
+            }
+            Some(b'?') => Some(1),
+            Some(b'[') => match_class(&pattern[pattern_index + 1..], text[text_index]),
+            Some(&byte) => (byte == text[text_index]).then_some(1),
+            None => None,
+        };
+        match (step, last_star) {
+            (Some(step), _) => {
+                pattern_index += step;
+                text_index += 1;
+            }
+            (None, Some((star_pattern_index, star_text_index))) => {
+                last_star = Some((star_pattern_index, star_text_index + 1));
+                pattern_index = star_pattern_index + 1;
+                text_index = star_text_index + 1;
+            }
+            (None, None) => return false,
+        }
+    }
+    pattern[pattern_index..]
+        .iter()
+        .all(|&byte| byte == b'*')
+}
+
+// Whether the byte is in the class that starts after the `[`, returning how
+// much of the pattern the class takes up including the brackets
+fn match_class(class: &[u8], byte: u8) -> Option<usize> {
+    let end = class
+        .iter()
+        .position(|&class_byte| class_byte == b']')?;
+    let mut index = 0;
+    let mut is_in_class = false;
+    while index < end {
+        match class[index..end] {
+            [first, b'-', last, ..] => {
+                is_in_class |= (first..=last).contains(&byte);
+                index += 3;
+            }
+            [first, ..] => {
+                is_in_class |= first == byte;
+                index += 1;
+            }
+            [] => break,
+        }
+    }
+    is_in_class.then_some(end + 2)
+}
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(
+        display("could not read the module aliases at {:?}: {source}", path),
+        visibility(pub)
+    )]
+    ReadModuleAliases {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+// region: IMPORTS
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use snafu::{ResultExt, Snafu};
+
+// endregion: IMPORTS
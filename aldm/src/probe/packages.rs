@@ -0,0 +1,173 @@
+// The packages installed according to the local pacman database, mapped to
+// their versions
+pub fn probe_installed_packages(root: &Path) -> Result<BTreeMap<String, Version>, Error> {
+    let local_database_dirpath = root.join("var/lib/pacman/local");
+    let entries = match fs::read_dir(&local_database_dirpath) {
+        Ok(entries) => entries,
+        // No pacman, for example on a development machine
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(error) => {
+            return Err(error).context(ReadLocalPackageDatabaseSnafu {
+                path: local_database_dirpath,
+            })
+        }
+    };
+
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let desc = fs::read_to_string(
+                entry
+                    .path()
+                    .join("desc"),
+            )
+            .ok()?;
+            parse_desc(&desc)
+        })
+        .collect())
+}
+
+// The packages in the repositories, according to the sync databases that
+// pacman last downloaded, mapped to their versions. A package in several
+// repositories is mapped to its newest version. Sync databases that cannot be
+// read are skipped, as they only narrow down what can be installed.
+pub fn probe_available_packages(root: &Path) -> BTreeMap<String, Version> {
+    let sync_database_dirpath = root.join("var/lib/pacman/sync");
+    let Ok(entries) = fs::read_dir(&sync_database_dirpath) else {
+        return BTreeMap::new();
+    };
+
+    let mut packages = BTreeMap::new();
+    for sync_database_filepath in entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "db")
+        })
+    {
+        if let Err(error) = read_sync_database(&sync_database_filepath, &mut packages) {
+            tracing::debug!(
+                "Could not read the sync database at {:?}: {}",
+                sync_database_filepath,
+                error
+            );
+        }
+    }
+    packages
+}
+
+// A sync database is a gzipped tar archive with a `desc` file per package
+fn read_sync_database(
+    sync_database_filepath: &Path,
+    packages: &mut BTreeMap<String, Version>,
+) -> io::Result<()> {
+    let sync_database_file = File::open(sync_database_filepath)?;
+    let mut archive = tar::Archive::new(GzDecoder::new(BufReader::new(sync_database_file)));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry
+            .path()?
+            .file_name()
+            != Some(OsStr::new("desc"))
+        {
+            continue;
+        }
+        let mut desc = String::new();
+        entry.read_to_string(&mut desc)?;
+        if let Some((name, version)) = parse_desc(&desc) {
+            match packages.entry(name) {
+                Entry::Vacant(vacant_entry) => {
+                    vacant_entry.insert(version);
+                }
+                Entry::Occupied(mut occupied_entry) => {
+                    if version > *occupied_entry.get() {
+                        occupied_entry.insert(version);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// The kernels installed, by package name (like `linux` or `linux-lts`), as
+// recorded by the kernel packages in their module directories
+pub fn probe_installed_kernels(root: &Path) -> BTreeSet<String> {
+    let modules_dirpath = root.join("usr/lib/modules");
+    fs::read_dir(modules_dirpath)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    fs::read_to_string(
+                        entry
+                            .path()
+                            .join("pkgbase"),
+                    )
+                    .ok()
+                })
+                .map(|pkgbase| {
+                    pkgbase
+                        .trim()
+                        .to_owned()
+                })
+                .filter(|pkgbase| !pkgbase.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Parse the name and version out of a `desc` file in the local pacman
+// database, which consists of sections like:
+//
+// %NAME%
+// nvidia-utils
+//
+// %VERSION%
+// 545.29.06-1
+fn parse_desc(desc: &str) -> Option<(String, Version)> {
+    let mut lines = desc.lines();
+    let mut name = None;
+    let mut version = None;
+    while let Some(line) = lines.next() {
+        match line {
+            "%NAME%" => name = lines.next(),
+            "%VERSION%" => version = lines.next(),
+            _ => {}
+        }
+    }
+    Some((name?.to_owned(), Version::new(version?)))
+}
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(
+        display("could not read the local package database at {:?}: {source}", path),
+        visibility(pub)
+    )]
+    ReadLocalPackageDatabase {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+// region: IMPORTS
+
+use std::{
+    collections::{btree_map::Entry, BTreeMap, BTreeSet},
+    ffi::OsStr,
+    fs::{self, File},
+    io::{self, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+
+use snafu::{ResultExt, Snafu};
+
+use crate::data::Version;
+
+// endregion: IMPORTS
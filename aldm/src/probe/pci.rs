@@ -0,0 +1,182 @@
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PciDevice {
+    pub slot: String,
+
+    #[serde(serialize_with = "serialize_hex_id")]
+    pub vendor_id: u16,
+
+    #[serde(serialize_with = "serialize_hex_id")]
+    pub device_id: u16,
+
+    #[serde(serialize_with = "serialize_hex_id")]
+    pub subsystem_vendor_id: u16,
+
+    #[serde(serialize_with = "serialize_hex_id")]
+    pub subsystem_device_id: u16,
+
+    // The class, subclass and programming interface, as in `0x030000`
+    pub class_code: u32,
+
+    pub modalias: Option<String>,
+
+    // Whether the firmware used this device for the display at boot, which is
+    // the integrated GPU of most laptops with hybrid graphics
+    pub is_boot_vga: bool,
+
+    // The kernel driver the device is bound to, such as `nouveau`
+    pub bound_driver: Option<String>,
+
+    // The kernel modules whose aliases match the modalias, which are filled in
+    // from the modules of the running kernel
+    pub candidate_modules: Vec<String>,
+}
+
+impl PciDevice {
+    // The `vendor:device` ID pair, as shown by `lspci -n`
+    pub fn id_pair(&self) -> String {
+        format!("{:04x}:{:04x}", self.vendor_id, self.device_id)
+    }
+
+    pub fn hardware_kind(&self) -> Option<HardwareKind> {
+        HardwareKind::from_pci_class(self.class_code)
+    }
+}
+
+impl fmt::Display for PciDevice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} [{}]", self.slot, self.id_pair())
+    }
+}
+
+pub fn probe_pci_devices(root: &Path) -> Result<Vec<PciDevice>, Error> {
+    let devices_dirpath = root.join("sys/bus/pci/devices");
+    let entries = match fs::read_dir(&devices_dirpath) {
+        Ok(entries) => entries,
+        // No PCI bus, for example in some containers
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(error) => {
+            return Err(error).context(ReadPciDevicesSnafu {
+                path: devices_dirpath,
+            })
+        }
+    };
+
+    let mut pci_devices = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let uevent = fs::read_to_string(
+                entry
+                    .path()
+                    .join("uevent"),
+            )
+            .ok()?;
+            let Some(mut pci_device) = parse_uevent(&uevent) else {
+                tracing::debug!("Could not parse the PCI device at {:?}", entry.path());
+                return None;
+            };
+            pci_device.is_boot_vga = fs::read_to_string(
+                entry
+                    .path()
+                    .join("boot_vga"),
+            )
+            .is_ok_and(|boot_vga| boot_vga.trim() == "1");
+            // The driver is a link to the directory of the driver in sysfs
+            if let Ok(driver_dirpath) = fs::read_link(
+                entry
+                    .path()
+                    .join("driver"),
+            ) {
+                pci_device.bound_driver = driver_dirpath
+                    .file_name()
+                    .map(|driver| {
+                        driver
+                            .to_string_lossy()
+                            .into_owned()
+                    });
+            }
+            Some(pci_device)
+        })
+        .collect::<Vec<_>>();
+    pci_devices.sort_by(|a, b| a.slot.cmp(&b.slot));
+    Ok(pci_devices)
+}
+
+// Parse the `uevent` file of a PCI device in sysfs, which looks like:
+//
+// PCI_CLASS=30000
+// PCI_ID=10DE:2684
+// PCI_SUBSYS_ID=1043:889D
+// PCI_SLOT_NAME=0000:01:00.0
+// MODALIAS=pci:v000010DEd00002684sv00001043sd0000889Dbc03sc00i00
+fn parse_uevent(uevent: &str) -> Option<PciDevice> {
+    let values = uevent
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .collect::<Vec<_>>();
+    let value = |key: &str| {
+        values
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| *v)
+    };
+    let parse_id_pair = |id_pair: &str| {
+        let (first, second) = id_pair.split_once(':')?;
+        Some((
+            u16::from_str_radix(first, 16).ok()?,
+            u16::from_str_radix(second, 16).ok()?,
+        ))
+    };
+
+    let (vendor_id, device_id) = parse_id_pair(value("PCI_ID")?)?;
+    let (subsystem_vendor_id, subsystem_device_id) = value("PCI_SUBSYS_ID")
+        .and_then(parse_id_pair)
+        .unwrap_or((0, 0));
+    Some(PciDevice {
+        slot: value("PCI_SLOT_NAME")?.to_owned(),
+        vendor_id,
+        device_id,
+        subsystem_vendor_id,
+        subsystem_device_id,
+        class_code: u32::from_str_radix(value("PCI_CLASS")?, 16).ok()?,
+        modalias: value("MODALIAS").map(str::to_owned),
+        is_boot_vga: false,
+        // Only there when the device is bound, and read from the link instead
+        bound_driver: value("DRIVER").map(str::to_owned),
+        candidate_modules: vec![],
+    })
+}
+
+fn serialize_hex_id<S>(id: &u16, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&format!("{id:04x}"))
+}
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(
+        display("could not read the PCI devices at {:?}: {source}", path),
+        visibility(pub)
+    )]
+    ReadPciDevices {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+// region: IMPORTS
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Serialize, Serializer};
+use snafu::{ResultExt, Snafu};
+
+use crate::data::HardwareKind;
+
+// endregion: IMPORTS
@@ -0,0 +1,486 @@
+// The boot loaders whose kernel command line aldm can change
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub enum Bootloader {
+    Grub,
+    SystemdBoot,
+}
+
+impl fmt::Display for Bootloader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Bootloader::Grub => write!(f, "GRUB"),
+            Bootloader::SystemdBoot => write!(f, "systemd-boot"),
+        }
+    }
+}
+
+// systemd-boot is checked first, since the GRUB defaults file is left behind
+// on systems that switched away from GRUB
+pub fn detect_bootloader(root: &Path) -> Option<Bootloader> {
+    if !loader_entry_filepaths(root)
+        .unwrap_or_default()
+        .is_empty()
+    {
+        return Some(Bootloader::SystemdBoot);
+    }
+    system::path_below_root(root, Path::new(GRUB_DEFAULTS_FILEPATH))
+        .is_file()
+        .then_some(Bootloader::Grub)
+}
+
+// What changing the kernel parameters did, so that it can be told and undone
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct KernelParameterChanges {
+    pub bootloader: Option<Bootloader>,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub edited_files: Vec<EditedFile>,
+}
+
+// A file that was changed, along with the copy of it from before the change
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct EditedFile {
+    pub path: PathBuf,
+    pub backup_path: PathBuf,
+}
+
+// Add and remove kernel parameters in the configuration of the boot loader.
+// Adding is idempotent: a parameter that is already there is left alone, and
+// one with the same name but another value is replaced. Only the parameters
+// that aldm added itself are removed, which it keeps a record of, so that the
+// parameters of the user stay. Every file is backed up before it is changed,
+// and the GRUB configuration is regenerated afterwards.
+pub fn update_kernel_parameters(
+    root: &Path,
+    parameters_to_add: &[String],
+    parameters_to_remove: &[String],
+    command_runner: &dyn CommandRunner,
+    progress: &dyn ProgressSink,
+) -> Result<KernelParameterChanges, Error> {
+    let mut recorded_parameters = recorded_kernel_parameters(root)?;
+    let parameters_to_remove = parameters_to_remove
+        .iter()
+        .filter(|parameter| recorded_parameters.contains(parameter))
+        .cloned()
+        .collect::<Vec<_>>();
+    if parameters_to_add.is_empty() && parameters_to_remove.is_empty() {
+        return Ok(KernelParameterChanges::default());
+    }
+
+    let Some(bootloader) = detect_bootloader(root) else {
+        if !parameters_to_add.is_empty() {
+            progress.log(&format!(
+                "No GRUB or systemd-boot configuration was found. Add the kernel parameters {} to the boot loader yourself.",
+                parameters_to_add.join(" ")
+            ));
+        }
+        return Ok(KernelParameterChanges::default());
+    };
+    let filepaths = bootloader_config_filepaths(root, bootloader)?;
+
+    let mut changes = KernelParameterChanges {
+        bootloader: Some(bootloader),
+        ..Default::default()
+    };
+    let backup_dirpath = system::state_dirpath()
+        .join("backups")
+        .join(app::filename_timestamp());
+    for path in filepaths {
+        let filepath = system::path_below_root(root, &path);
+        let contents = fs::read_to_string(&filepath).context(ReadBootloaderConfigSnafu {
+            path: filepath.clone(),
+        })?;
+        let edit = match bootloader {
+            Bootloader::Grub => {
+                edit_grub_defaults(&contents, parameters_to_add, &parameters_to_remove)
+            }
+            Bootloader::SystemdBoot => {
+                edit_loader_entry(&contents, parameters_to_add, &parameters_to_remove)
+            }
+        };
+        if edit.contents == contents {
+            continue;
+        }
+
+        let backup_path = backup_dirpath.join(
+            path.strip_prefix("/")
+                .unwrap_or(&path),
+        );
+        let backup_filepath = system::path_below_root(root, &backup_path);
+        if let Some(dirpath) = backup_filepath.parent() {
+            fs::create_dir_all(dirpath).context(BackUpBootloaderConfigSnafu {
+                path: filepath.clone(),
+            })?;
+        }
+        // A backup from earlier in the same second is older, so it is kept
+        if !backup_filepath.exists() {
+            fs::copy(&filepath, &backup_filepath).context(BackUpBootloaderConfigSnafu {
+                path: filepath.clone(),
+            })?;
+        }
+        progress.log(&format!(
+            "Changing the kernel parameters in {}, with a backup at {}",
+            path.display(),
+            backup_path.display()
+        ));
+        fs::write(&filepath, &edit.contents)
+            .context(WriteBootloaderConfigSnafu { path: filepath })?;
+
+        for parameter in edit.added {
+            if !changes
+                .added
+                .contains(&parameter)
+            {
+                changes
+                    .added
+                    .push(parameter);
+            }
+        }
+        for parameter in edit.removed {
+            if !changes
+                .removed
+                .contains(&parameter)
+            {
+                changes
+                    .removed
+                    .push(parameter);
+            }
+        }
+        changes
+            .edited_files
+            .push(EditedFile { path, backup_path });
+    }
+
+    recorded_parameters.retain(|parameter| {
+        !changes
+            .removed
+            .contains(parameter)
+    });
+    for parameter in &changes.added {
+        if !recorded_parameters.contains(parameter) {
+            recorded_parameters.push(parameter.clone());
+        }
+    }
+    write_recorded_kernel_parameters(root, &recorded_parameters)?;
+
+    if bootloader == Bootloader::Grub
+        && !changes
+            .edited_files
+            .is_empty()
+    {
+        regenerate_grub_config(command_runner, progress)?;
+    }
+    Ok(changes)
+}
+
+// The files that hold the kernel command line of the boot loader, as paths on
+// the system
+pub fn bootloader_config_filepaths(
+    root: &Path,
+    bootloader: Bootloader,
+) -> Result<Vec<PathBuf>, Error> {
+    match bootloader {
+        Bootloader::Grub => Ok(vec![PathBuf::from(GRUB_DEFAULTS_FILEPATH)]),
+        Bootloader::SystemdBoot => loader_entry_filepaths(root),
+    }
+}
+
+// Whether the file is the GRUB defaults file, after changing which the GRUB
+// configuration has to be regenerated
+pub fn is_grub_defaults_file(path: &Path) -> bool {
+    path == Path::new(GRUB_DEFAULTS_FILEPATH)
+}
+
+pub fn regenerate_grub_config(
+    command_runner: &dyn CommandRunner,
+    progress: &dyn ProgressSink,
+) -> Result<(), Error> {
+    command_runner
+        .run("grub-mkconfig", &["-o", GRUB_CONFIG_FILEPATH], progress)
+        .context(RegenerateGrubConfigSnafu {})
+}
+
+// The kernel parameters that aldm added, as they were recorded when adding them
+pub fn recorded_kernel_parameters(root: &Path) -> Result<Vec<String>, Error> {
+    let record_filepath = system::record_filepath(root, system::KERNEL_PARAMETERS_RECORD);
+    system::read_record(&record_filepath).context(ReadKernelParameterRecordSnafu {
+        path: record_filepath,
+    })
+}
+
+fn write_recorded_kernel_parameters(root: &Path, parameters: &[String]) -> Result<(), Error> {
+    let record_filepath = system::record_filepath(root, system::KERNEL_PARAMETERS_RECORD);
+    system::write_record(&record_filepath, parameters).context(WriteKernelParameterRecordSnafu {
+        path: record_filepath,
+    })
+}
+
+// The systemd-boot entries, as paths on the system
+fn loader_entry_filepaths(root: &Path) -> Result<Vec<PathBuf>, Error> {
+    let dirpath = system::path_below_root(root, Path::new(LOADER_ENTRIES_DIRPATH));
+    let entries = match fs::read_dir(&dirpath) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(error) => return Err(error).context(ReadBootloaderConfigSnafu { path: dirpath }),
+    };
+    let mut paths = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| Path::new(LOADER_ENTRIES_DIRPATH).join(entry.file_name()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "conf")
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+    Ok(paths)
+}
+
+#[derive(Clone, Debug, Default)]
+struct ConfigEdit {
+    contents: String,
+    added: Vec<String>,
+    removed: Vec<String>,
+}
+
+// The kernel command line is in `GRUB_CMDLINE_LINUX_DEFAULT="..."`. The file
+// is sourced by a shell, so the last assignment is the one that counts.
+fn edit_grub_defaults(
+    contents: &str,
+    parameters_to_add: &[String],
+    parameters_to_remove: &[String],
+) -> ConfigEdit {
+    let mut lines = contents
+        .lines()
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    let mut edit = ConfigEdit::default();
+    let line_index = lines
+        .iter()
+        .rposition(|line| {
+            line.trim_start()
+                .starts_with(GRUB_CMDLINE_KEY)
+        });
+    match line_index {
+        Some(line_index) => {
+            let value = lines[line_index]
+                .trim_start()
+                .trim_start_matches(GRUB_CMDLINE_KEY)
+                .trim();
+            let quote = value
+                .chars()
+                .next()
+                .filter(|c| *c == '"' || *c == '\'')
+                .unwrap_or('"');
+            let parameters = edit_parameters(
+                value.trim_matches(quote),
+                parameters_to_add,
+                parameters_to_remove,
+                &mut edit,
+            );
+            lines[line_index] = format!("{GRUB_CMDLINE_KEY}{quote}{parameters}{quote}");
+        }
+        None if !parameters_to_add.is_empty() => {
+            let parameters = edit_parameters("", parameters_to_add, &[], &mut edit);
+            lines.push(format!("{GRUB_CMDLINE_KEY}\"{parameters}\""));
+        }
+        None => {}
+    }
+    edit.contents = join_lines(contents, &lines);
+    edit
+}
+
+// The kernel command line is in the `options` lines of an entry, of which
+// there can be several. Parameters are removed from all of them and added to
+// the first.
+fn edit_loader_entry(
+    contents: &str,
+    parameters_to_add: &[String],
+    parameters_to_remove: &[String],
+) -> ConfigEdit {
+    let mut lines = contents
+        .lines()
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    let mut edit = ConfigEdit::default();
+    let mut is_first = true;
+    for line in &mut lines {
+        let Some(value) = line
+            .trim_start()
+            .strip_prefix(OPTIONS_KEY)
+            .filter(|value| value.starts_with(char::is_whitespace))
+        else {
+            continue;
+        };
+        let parameters_to_add = match is_first {
+            true => parameters_to_add,
+            false => &[],
+        };
+        let parameters = edit_parameters(value, parameters_to_add, parameters_to_remove, &mut edit);
+        *line = format!("{OPTIONS_KEY} {parameters}");
+        is_first = false;
+    }
+    if is_first && !parameters_to_add.is_empty() {
+        let parameters = edit_parameters("", parameters_to_add, &[], &mut edit);
+        lines.push(format!("{OPTIONS_KEY} {parameters}"));
+    }
+    edit.contents = join_lines(contents, &lines);
+    edit
+}
+
+fn edit_parameters(
+    parameters: &str,
+    parameters_to_add: &[String],
+    parameters_to_remove: &[String],
+    edit: &mut ConfigEdit,
+) -> String {
+    let mut parameters = parameters
+        .split_whitespace()
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    for parameter_to_remove in parameters_to_remove {
+        let length = parameters.len();
+        parameters.retain(|parameter| parameter != parameter_to_remove);
+        if parameters.len() != length {
+            edit.removed
+                .push(parameter_to_remove.clone());
+        }
+    }
+    for parameter_to_add in parameters_to_add {
+        match parameters
+            .iter()
+            .position(|parameter| parameter_name(parameter) == parameter_name(parameter_to_add))
+        {
+            Some(index) if parameters[index] == *parameter_to_add => {}
+            Some(index) => {
+                parameters[index] = parameter_to_add.clone();
+                edit.added
+                    .push(parameter_to_add.clone());
+            }
+            None => {
+                parameters.push(parameter_to_add.clone());
+                edit.added
+                    .push(parameter_to_add.clone());
+            }
+        }
+    }
+    parameters.join(" ")
+}
+
+// The kernel does not tell `-` and `_` apart in parameter names, so
+// `nvidia-drm.modeset` and `nvidia_drm.modeset` are the same parameter
+fn parameter_name(parameter: &str) -> String {
+    parameter
+        .split('=')
+        .next()
+        .unwrap_or_default()
+        .replace('-', "_")
+}
+
+// Keep the final newline of the file, if it had one
+fn join_lines(contents: &str, lines: &[String]) -> String {
+    let mut joined = lines.join("\n");
+    if contents.ends_with('\n') || contents.is_empty() {
+        joined.push('\n');
+    }
+    joined
+}
+
+const GRUB_DEFAULTS_FILEPATH: &str = "/etc/default/grub";
+const GRUB_CONFIG_FILEPATH: &str = "/boot/grub/grub.cfg";
+const GRUB_CMDLINE_KEY: &str = "GRUB_CMDLINE_LINUX_DEFAULT=";
+const LOADER_ENTRIES_DIRPATH: &str = "/boot/loader/entries";
+const OPTIONS_KEY: &str = "options";
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(
+        display("could not read the boot loader configuration at {:?}: {source}", path),
+        visibility(pub)
+    )]
+    ReadBootloaderConfig {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display(
+            "could not back up the boot loader configuration at {:?}: {source}",
+            path
+        ),
+        visibility(pub)
+    )]
+    BackUpBootloaderConfig {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display(
+            "could not write the boot loader configuration at {:?}: {source}",
+            path
+        ),
+        visibility(pub)
+    )]
+    WriteBootloaderConfig {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display(
+            "could not read the record of kernel parameters at {:?}: {source}",
+            path
+        ),
+        visibility(pub)
+    )]
+    ReadKernelParameterRecord {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display(
+            "could not write the record of kernel parameters at {:?}: {source}",
+            path
+        ),
+        visibility(pub)
+    )]
+    WriteKernelParameterRecord {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not regenerate the GRUB configuration: {source}"),
+        visibility(pub)
+    )]
+    RegenerateGrubConfig {
+        #[snafu(backtrace)]
+        source: command::Error,
+    },
+}
+
+// region: IMPORTS
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use snafu::{ResultExt, Snafu};
+
+use crate::{
+    actions::ProgressSink,
+    app,
+    system::{self, command, CommandRunner},
+};
+
+// endregion: IMPORTS
@@ -0,0 +1,280 @@
+// Runs the commands that change the system besides the package manager, such
+// as `mkinitcpio -P`. The fake runner stands in for the real one in the test
+// mode and in tests, so that nothing is run on the machine.
+pub trait CommandRunner: Send + Sync {
+    // Run the program as root, reporting each line of its output
+    fn run(
+        &self,
+        program: &str,
+        arguments: &[&str],
+        progress: &dyn ProgressSink,
+    ) -> Result<(), Error>;
+}
+
+// Runs the commands on the system. Without root, they are run through pkexec.
+// For a root directory other than `/`, they are run in a chroot of it.
+#[derive(Clone, Debug)]
+pub struct SystemCommandRunner {
+    root: PathBuf,
+}
+
+impl SystemCommandRunner {
+    pub fn new(root: &Path) -> Self {
+        SystemCommandRunner {
+            root: root.to_owned(),
+        }
+    }
+
+    fn command(&self, program: &str, arguments: &[&str]) -> Command {
+        let mut command_words = Vec::<OsString>::new();
+        if !Uid::effective().is_root() {
+            command_words.push("pkexec".into());
+        }
+        if self.root != probe::default_root() {
+            command_words.push("chroot".into());
+            command_words.push(
+                self.root
+                    .clone()
+                    .into(),
+            );
+        }
+        command_words.push(program.into());
+        let mut command = Command::new(&command_words[0]);
+        command
+            .args(&command_words[1..])
+            .args(arguments);
+        command
+    }
+}
+
+impl CommandRunner for SystemCommandRunner {
+    fn run(
+        &self,
+        program: &str,
+        arguments: &[&str],
+        progress: &dyn ProgressSink,
+    ) -> Result<(), Error> {
+        let mut command = self.command(program, arguments);
+        let command_line = command_line(program, arguments);
+        tracing::info!("Running `{}`", command_line);
+        let mut child = command
+            .env("LC_ALL", "C")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context(SpawnCommandSnafu {
+                command: command_line.clone(),
+            })?;
+
+        // Read stdout and stderr on their own threads, so that neither blocks
+        // the other
+        let (line_sender, line_receiver) = mpsc::channel();
+        let readers = [
+            child
+                .stdout
+                .take()
+                .map(|stdout| Box::new(stdout) as Box<dyn Read + Send>),
+            child
+                .stderr
+                .take()
+                .map(|stderr| Box::new(stderr) as Box<dyn Read + Send>),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|reader| {
+            let line_sender = line_sender.clone();
+            thread::spawn(move || {
+                for line in BufReader::new(reader)
+                    .lines()
+                    .map_while(Result::ok)
+                {
+                    if line_sender
+                        .send(line)
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+        drop(line_sender);
+
+        for line in line_receiver {
+            tracing::debug!(target: "command", "{}", line);
+            progress.log(&line);
+        }
+        for reader in readers {
+            reader.join().ok();
+        }
+
+        let exit_status = child
+            .wait()
+            .context(WaitCommandSnafu {
+                command: command_line.clone(),
+            })?;
+        ensure!(
+            exit_status.success(),
+            CommandFailedSnafu {
+                command: command_line,
+                exit_code: exit_status.code(),
+            }
+        );
+        Ok(())
+    }
+}
+
+// A runner that runs nothing. It records every call and answers from a
+// script, like the fake package backend.
+#[derive(Debug, Default)]
+pub struct FakeCommandRunner {
+    state: Mutex<FakeRunnerState>,
+}
+
+#[derive(Debug, Default)]
+struct FakeRunnerState {
+    script: VecDeque<FakeStep>,
+    calls: Vec<CommandCall>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CommandCall {
+    pub program: String,
+    pub arguments: Vec<String>,
+}
+
+impl FakeCommandRunner {
+    pub fn new() -> Self {
+        FakeCommandRunner::default()
+    }
+
+    pub fn with_step(self, step: FakeStep) -> Self {
+        self.lock()
+            .script
+            .push_back(step);
+        self
+    }
+
+    pub fn calls(&self) -> Vec<CommandCall> {
+        self.lock()
+            .calls
+            .clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, FakeRunnerState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+impl CommandRunner for FakeCommandRunner {
+    fn run(
+        &self,
+        program: &str,
+        arguments: &[&str],
+        progress: &dyn ProgressSink,
+    ) -> Result<(), Error> {
+        let step = {
+            let mut state = self.lock();
+            state
+                .calls
+                .push(CommandCall {
+                    program: program.to_owned(),
+                    arguments: arguments
+                        .iter()
+                        .map(|argument| (*argument).to_owned())
+                        .collect(),
+                });
+            state
+                .script
+                .pop_front()
+                .unwrap_or_default()
+        };
+
+        for line in &step.output {
+            progress.log(line);
+        }
+        match step.result {
+            FakeResult::Complete => Ok(()),
+            FakeResult::Fail { exit_code } => CommandFailedSnafu {
+                command: command_line(program, arguments),
+                exit_code: Some(exit_code),
+            }
+            .fail(),
+        }
+    }
+}
+
+fn command_line(program: &str, arguments: &[&str]) -> String {
+    std::iter::once(program)
+        .chain(
+            arguments
+                .iter()
+                .copied(),
+        )
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(display("could not run `{command}`: {source}"), visibility(pub))]
+    SpawnCommand {
+        command: String,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not wait for `{command}` to finish: {source}"),
+        visibility(pub)
+    )]
+    WaitCommand {
+        command: String,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display(
+            "`{command}` failed with {}",
+            match exit_code {
+                Some(exit_code) => format!("the exit code {exit_code}"),
+                None => String::from("a signal"),
+            }
+        ),
+        visibility(pub)
+    )]
+    CommandFailed {
+        command: String,
+        exit_code: Option<i32>,
+    },
+}
+
+// region: IMPORTS
+
+use std::{
+    collections::VecDeque,
+    ffi::OsString,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{mpsc, Mutex, MutexGuard},
+    thread,
+};
+
+use nix::unistd::Uid;
+use serde::Serialize;
+use snafu::{ensure, ResultExt, Snafu};
+
+use crate::{
+    actions::ProgressSink,
+    backend::{FakeResult, FakeStep},
+    probe,
+};
+
+// endregion: IMPORTS
@@ -0,0 +1,245 @@
+// A file that aldm wrote for a driver, with the backup of the file that it
+// replaced, if there was one. Both are paths on the system.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordedDriverFile {
+    pub path: String,
+    pub backup_path: Option<PathBuf>,
+}
+
+// Write a file of a driver. A file that aldm did not write before is backed up
+// before it is replaced, and the path is recorded along with the backup, so
+// that only files that aldm wrote are ever removed, and the replaced file comes
+// back when they are.
+pub fn write_driver_file(
+    root: &Path,
+    driver_file: &DriverFile,
+    progress: &dyn ProgressSink,
+) -> Result<(), Error> {
+    let path = Path::new(&driver_file.path);
+    let filepath = system::path_below_root(root, path);
+    let mut recorded_files = recorded_driver_files(root)?;
+    let is_recorded = recorded_files
+        .iter()
+        .any(|recorded_file| recorded_file.path == driver_file.path);
+    let mut backup_path = None;
+    if filepath.exists() && !is_recorded {
+        let new_backup_path = system::state_dirpath()
+            .join("backups")
+            .join(app::filename_timestamp())
+            .join(
+                path.strip_prefix("/")
+                    .unwrap_or(path),
+            );
+        let backup_filepath = system::path_below_root(root, &new_backup_path);
+        if let Some(dirpath) = backup_filepath.parent() {
+            fs::create_dir_all(dirpath).context(BackUpDriverFileSnafu {
+                path: filepath.clone(),
+            })?;
+        }
+        fs::copy(&filepath, &backup_filepath).context(BackUpDriverFileSnafu {
+            path: filepath.clone(),
+        })?;
+        progress.log(&format!(
+            "Replacing {}, with a backup at {}",
+            path.display(),
+            new_backup_path.display()
+        ));
+        backup_path = Some(new_backup_path);
+    }
+
+    progress.log(&format!("Writing {}", path.display()));
+    if let Some(dirpath) = filepath.parent() {
+        fs::create_dir_all(dirpath).context(WriteDriverFileSnafu {
+            path: filepath.clone(),
+        })?;
+    }
+    fs::write(&filepath, &driver_file.contents).context(WriteDriverFileSnafu { path: filepath })?;
+
+    if !is_recorded {
+        recorded_files.push(RecordedDriverFile {
+            path: driver_file
+                .path
+                .clone(),
+            backup_path,
+        });
+        write_recorded_driver_files(root, &recorded_files)?;
+    }
+    Ok(())
+}
+
+// Remove a file that aldm wrote for a driver, putting back the file that it
+// replaced, if any. Files that aldm has no record of are left alone.
+pub fn remove_driver_file(
+    root: &Path,
+    path: &str,
+    progress: &dyn ProgressSink,
+) -> Result<bool, Error> {
+    let mut recorded_files = recorded_driver_files(root)?;
+    let Some(recorded_file) = recorded_files
+        .iter()
+        .find(|recorded_file| recorded_file.path == path)
+        .cloned()
+    else {
+        tracing::info!("Leaving {} alone, since aldm did not write it", path);
+        return Ok(false);
+    };
+
+    let filepath = system::path_below_root(root, Path::new(path));
+    let backup_filepath = recorded_file
+        .backup_path
+        .as_deref()
+        .map(|backup_path| system::path_below_root(root, backup_path))
+        .filter(|backup_filepath| backup_filepath.exists());
+    match backup_filepath {
+        // The backup is copied rather than moved, so that it is still there
+        // if the removal is rolled back
+        Some(backup_filepath) => {
+            progress.log(&format!("Restoring {path} from its backup"));
+            fs::copy(&backup_filepath, &filepath).context(RestoreDriverFileSnafu {
+                path: filepath,
+                backup_path: backup_filepath,
+            })?;
+        }
+        None => {
+            if let Some(backup_path) = &recorded_file.backup_path {
+                tracing::warn!(
+                    "The backup of {} at {} is missing, so it is removed instead",
+                    path,
+                    backup_path.display()
+                );
+            }
+            progress.log(&format!("Removing {path}"));
+            match fs::remove_file(&filepath) {
+                Ok(()) => {}
+                Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+                Err(error) => return Err(error).context(RemoveDriverFileSnafu { path: filepath }),
+            }
+        }
+    }
+    recorded_files.retain(|recorded_file| recorded_file.path != path);
+    write_recorded_driver_files(root, &recorded_files)?;
+    Ok(true)
+}
+
+// The files that aldm wrote for drivers. Each entry of the record is the path
+// of a file, followed by a tab and the path of its backup if it has one.
+pub fn recorded_driver_files(root: &Path) -> Result<Vec<RecordedDriverFile>, Error> {
+    let record_filepath = system::record_filepath(root, system::FILES_RECORD);
+    let entries = system::read_record(&record_filepath).context(ReadDriverFileRecordSnafu {
+        path: record_filepath,
+    })?;
+    Ok(entries
+        .into_iter()
+        .map(|entry| match entry.split_once('\t') {
+            Some((path, backup_path)) => RecordedDriverFile {
+                path: path.to_owned(),
+                backup_path: Some(PathBuf::from(backup_path)),
+            },
+            None => RecordedDriverFile {
+                path: entry,
+                backup_path: None,
+            },
+        })
+        .collect())
+}
+
+fn write_recorded_driver_files(
+    root: &Path,
+    recorded_files: &[RecordedDriverFile],
+) -> Result<(), Error> {
+    let record_filepath = system::record_filepath(root, system::FILES_RECORD);
+    let entries = recorded_files
+        .iter()
+        .map(|recorded_file| match &recorded_file.backup_path {
+            Some(backup_path) => format!("{}\t{}", recorded_file.path, backup_path.display()),
+            None => recorded_file
+                .path
+                .clone(),
+        })
+        .collect::<Vec<_>>();
+    system::write_record(&record_filepath, &entries).context(WriteDriverFileRecordSnafu {
+        path: record_filepath,
+    })
+}
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(
+        display("could not back up the file {:?}: {source}", path),
+        visibility(pub)
+    )]
+    BackUpDriverFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not write the file {:?}: {source}", path),
+        visibility(pub)
+    )]
+    WriteDriverFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display(
+            "could not restore the file {:?} from its backup at {:?}: {source}",
+            path,
+            backup_path
+        ),
+        visibility(pub)
+    )]
+    RestoreDriverFile {
+        path: PathBuf,
+        backup_path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not remove the file {:?}: {source}", path),
+        visibility(pub)
+    )]
+    RemoveDriverFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not read the record of written files at {:?}: {source}", path),
+        visibility(pub)
+    )]
+    ReadDriverFileRecord {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not write the record of written files at {:?}: {source}", path),
+        visibility(pub)
+    )]
+    WriteDriverFileRecord {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+// region: IMPORTS
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use snafu::{ResultExt, Snafu};
+
+use crate::{actions::ProgressSink, app, data::DriverFile, system};
+
+// endregion: IMPORTS
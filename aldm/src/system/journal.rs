@@ -0,0 +1,538 @@
+// The journal of a transaction, kept in
+// `/var/lib/aldm/transactions/<id>/journal.yaml`. It is written before each
+// change is made, so that even a transaction that was interrupted can be
+// rolled back. Every file is copied next to the journal before it is first
+// changed, the packages are recorded before the package manager changes them
+// and marked as done after, and the services are recorded once they are
+// changed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Journal {
+    // The time the transaction started, as a file name, such as
+    // `2024-03-01T12-00-00Z`. Transactions started in the same second get a
+    // suffix like `-002`, padded so that the IDs sort in the order the
+    // transactions started.
+    pub id: String,
+    // The time the transaction started, in seconds since the Unix epoch
+    pub started_at: i64,
+    // The user who ran aldm, rather than root when it was run through sudo or
+    // pkexec
+    pub user: String,
+    pub kind: JournalKind,
+    pub slot: String,
+    // The device as shown to the user, with its name
+    pub device: String,
+    pub driver_id: String,
+    pub is_reboot_required: bool,
+    // The installed versions of the packages of the transaction, before and
+    // after it
+    pub packages_before: BTreeMap<String, Version>,
+    pub packages_after: BTreeMap<String, Version>,
+    pub changes: Vec<JournalChange>,
+    // None while the transaction runs, and for one that was interrupted
+    pub result: Option<JournalResult>,
+    // The transaction that a rollback undid
+    pub rollback_of: Option<String>,
+    // The rollback that undid the transaction
+    pub rolled_back_by: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalKind {
+    Install,
+    Remove,
+    Rollback,
+}
+
+impl From<TransactionKind> for JournalKind {
+    fn from(kind: TransactionKind) -> Self {
+        match kind {
+            TransactionKind::Install => JournalKind::Install,
+            TransactionKind::Remove => JournalKind::Remove,
+        }
+    }
+}
+
+impl fmt::Display for JournalKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JournalKind::Install => write!(f, "install"),
+            JournalKind::Remove => write!(f, "remove"),
+            JournalKind::Rollback => write!(f, "rollback"),
+        }
+    }
+}
+
+// The changes in the order they were made. The paths are as they are on the
+// system. A file without a backup did not exist before the transaction. The
+// packages of a step that is not done may have been changed only in part, if
+// at all, such as when the package manager failed or was interrupted.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum JournalChange {
+    PackagesRemoved {
+        packages: Vec<String>,
+        // Journals from before the steps were recorded ahead only had the
+        // steps that were done
+        #[serde(default = "is_done_by_default")]
+        is_done: bool,
+    },
+    PackagesInstalled {
+        packages: Vec<String>,
+        #[serde(default = "is_done_by_default")]
+        is_done: bool,
+    },
+    FileChanged {
+        path: PathBuf,
+        backup_path: Option<PathBuf>,
+    },
+    ServicesDisabled {
+        units: Vec<String>,
+    },
+    ServicesEnabled {
+        units: Vec<String>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum JournalResult {
+    Succeeded,
+    RebootRequired,
+    Cancelled,
+    Failed { error: String, is_rolled_back: bool },
+}
+
+impl Journal {
+    // Start the journal of a transaction, giving it an ID of its own
+    pub fn begin(
+        root: &Path,
+        kind: JournalKind,
+        slot: &str,
+        device: &str,
+        driver_id: &str,
+        is_reboot_required: bool,
+    ) -> Result<Self, Error> {
+        let started_at = OffsetDateTime::now_utc().unix_timestamp();
+        let transactions_dirpath = transactions_dirpath(root);
+        fs::create_dir_all(&transactions_dirpath).context(CreateJournalSnafu {
+            path: transactions_dirpath.clone(),
+        })?;
+        let timestamp = app::filename_timestamp();
+        let mut id = timestamp.clone();
+        for suffix in 2.. {
+            let dirpath = transactions_dirpath.join(&id);
+            match fs::create_dir(&dirpath) {
+                Ok(()) => break,
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+                    id = format!("{timestamp}-{suffix:03}");
+                }
+                Err(error) => return Err(error).context(CreateJournalSnafu { path: dirpath }),
+            }
+        }
+
+        let journal = Journal {
+            id,
+            started_at,
+            user: invoking_user(),
+            kind,
+            slot: slot.to_owned(),
+            device: device.to_owned(),
+            driver_id: driver_id.to_owned(),
+            is_reboot_required,
+            packages_before: BTreeMap::new(),
+            packages_after: BTreeMap::new(),
+            changes: vec![],
+            result: None,
+            rollback_of: None,
+            rolled_back_by: None,
+        };
+        journal.save(root)?;
+        Ok(journal)
+    }
+
+    pub fn save(&self, root: &Path) -> Result<(), Error> {
+        let filepath = journal_filepath(root, &self.id);
+        let contents = serde_yaml::to_string(self).context(SerializeJournalSnafu {})?;
+        fs::write(&filepath, contents).context(WriteJournalSnafu { path: filepath })
+    }
+
+    pub fn record(&mut self, root: &Path, change: JournalChange) -> Result<(), Error> {
+        self.changes
+            .push(change);
+        self.save(root)
+    }
+
+    // Mark the packages that were recorded last as changed, once the package
+    // manager is done with them
+    pub fn complete_packages(&mut self, root: &Path) -> Result<(), Error> {
+        if let Some(
+            JournalChange::PackagesRemoved { is_done, .. }
+            | JournalChange::PackagesInstalled { is_done, .. },
+        ) = self
+            .changes
+            .last_mut()
+        {
+            *is_done = true;
+        }
+        self.save(root)
+    }
+
+    // Drop the packages that were recorded last, when the package manager was
+    // cancelled before it changed any of them
+    pub fn discard_packages(&mut self, root: &Path) -> Result<(), Error> {
+        if let Some(
+            JournalChange::PackagesRemoved { .. } | JournalChange::PackagesInstalled { .. },
+        ) = self.changes.last()
+        {
+            self.changes.pop();
+        }
+        self.save(root)
+    }
+
+    pub fn finish(&mut self, root: &Path, result: JournalResult) -> Result<(), Error> {
+        self.result = Some(result);
+        self.save(root)
+    }
+
+    // Copy the file next to the journal before it is changed. Only the first
+    // copy counts, since that is the file as it was before the transaction.
+    pub fn back_up_file(&mut self, root: &Path, path: &Path) -> Result<(), Error> {
+        if self
+            .changed_files()
+            .any(|(changed_path, _)| changed_path == path)
+        {
+            return Ok(());
+        }
+        let filepath = system::path_below_root(root, path);
+        let backup_path = match filepath.is_file() {
+            true => {
+                let backup_path = transactions_path()
+                    .join(&self.id)
+                    .join("files")
+                    .join(
+                        path.strip_prefix("/")
+                            .unwrap_or(path),
+                    );
+                let backup_filepath = system::path_below_root(root, &backup_path);
+                if let Some(dirpath) = backup_filepath.parent() {
+                    fs::create_dir_all(dirpath).context(BackUpFileSnafu {
+                        path: filepath.clone(),
+                    })?;
+                }
+                fs::copy(&filepath, &backup_filepath).context(BackUpFileSnafu {
+                    path: filepath.clone(),
+                })?;
+                Some(backup_path)
+            }
+            false => None,
+        };
+        self.record(
+            root,
+            JournalChange::FileChanged {
+                path: path.to_owned(),
+                backup_path,
+            },
+        )
+    }
+
+    // The records that aldm keeps of its changes go along with them
+    pub fn back_up_records(&mut self, root: &Path) -> Result<(), Error> {
+        for record_name in system::RECORDS {
+            self.back_up_file(root, &system::state_dirpath().join(record_name))?;
+        }
+        Ok(())
+    }
+
+    pub fn changed_files(&self) -> impl Iterator<Item = (&Path, Option<&Path>)> {
+        self.changes
+            .iter()
+            .filter_map(|change| match change {
+                JournalChange::FileChanged { path, backup_path } => {
+                    Some((path.as_path(), backup_path.as_deref()))
+                }
+                _ => None,
+            })
+    }
+
+    // Whether the transaction was undone, either by a rollback or right away
+    // when it failed
+    pub fn is_rolled_back(&self) -> bool {
+        self.rolled_back_by
+            .is_some()
+            || matches!(
+                self.result,
+                Some(JournalResult::Failed {
+                    is_rolled_back: true,
+                    ..
+                })
+            )
+    }
+}
+
+impl fmt::Display for Journal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Transaction: {} ({})\nDriver: {}\nDevice: {}",
+            self.id, self.kind, self.driver_id, self.device
+        )?;
+        for change in &self.changes {
+            match change {
+                JournalChange::PackagesRemoved {
+                    packages,
+                    is_done: true,
+                } => write!(f, "\nRemoved packages: {}", packages.join(", "))?,
+                JournalChange::PackagesRemoved {
+                    packages,
+                    is_done: false,
+                } => write!(
+                    f,
+                    "\nRemoving packages (not finished): {}",
+                    packages.join(", ")
+                )?,
+                JournalChange::PackagesInstalled {
+                    packages,
+                    is_done: true,
+                } => write!(f, "\nInstalled packages: {}", packages.join(", "))?,
+                JournalChange::PackagesInstalled {
+                    packages,
+                    is_done: false,
+                } => write!(
+                    f,
+                    "\nInstalling packages (not finished): {}",
+                    packages.join(", ")
+                )?,
+                JournalChange::FileChanged {
+                    path,
+                    backup_path: Some(_),
+                } => write!(f, "\nChanged file: {}", path.display())?,
+                JournalChange::FileChanged {
+                    path,
+                    backup_path: None,
+                } => write!(f, "\nCreated file: {}", path.display())?,
+                JournalChange::ServicesDisabled { units } => {
+                    write!(f, "\nDisabled services: {}", units.join(", "))?
+                }
+                JournalChange::ServicesEnabled { units } => {
+                    write!(f, "\nEnabled services: {}", units.join(", "))?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn read_journal(root: &Path, id: &str) -> Result<Journal, Error> {
+    let filepath = journal_filepath(root, id);
+    let contents = match fs::read_to_string(&filepath) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            return UnknownTransactionSnafu { id }.fail()
+        }
+        Err(error) => return Err(error).context(ReadJournalSnafu { path: filepath }),
+    };
+    serde_yaml::from_str(&contents).context(ParseJournalSnafu { path: filepath })
+}
+
+// The journals of all transactions, the oldest first. Journals that cannot be
+// read are skipped.
+pub fn read_journals(root: &Path) -> Result<Vec<Journal>, Error> {
+    let dirpath = transactions_dirpath(root);
+    let entries = match fs::read_dir(&dirpath) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(error) => return Err(error).context(ReadJournalSnafu { path: dirpath }),
+    };
+    let mut ids = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            entry
+                .file_name()
+                .into_string()
+                .ok()
+        })
+        .collect::<Vec<_>>();
+    ids.sort();
+    Ok(ids
+        .iter()
+        .filter_map(|id| match read_journal(root, id) {
+            Ok(journal) => Some(journal),
+            Err(error) => {
+                tracing::warn!("Skipping the transaction {}: {}", id, error);
+                None
+            }
+        })
+        .collect())
+}
+
+// Put back the file as it was before a transaction, or remove it if it did
+// not exist then. Returns whether the file was any different.
+pub fn restore_file(root: &Path, path: &Path, backup_path: Option<&Path>) -> Result<bool, Error> {
+    let filepath = system::path_below_root(root, path);
+    let current_contents = match fs::read(&filepath) {
+        Ok(contents) => Some(contents),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+        Err(error) => return Err(error).context(RestoreFileSnafu { path: filepath }),
+    };
+    match backup_path {
+        Some(backup_path) => {
+            let backup_filepath = system::path_below_root(root, backup_path);
+            let contents = fs::read(&backup_filepath).context(RestoreFileSnafu {
+                path: filepath.clone(),
+            })?;
+            if current_contents.as_ref() == Some(&contents) {
+                return Ok(false);
+            }
+            if let Some(dirpath) = filepath.parent() {
+                fs::create_dir_all(dirpath).context(RestoreFileSnafu {
+                    path: filepath.clone(),
+                })?;
+            }
+            fs::write(&filepath, contents).context(RestoreFileSnafu { path: filepath })?;
+            Ok(true)
+        }
+        None => match current_contents {
+            Some(_) => {
+                fs::remove_file(&filepath).context(RestoreFileSnafu { path: filepath })?;
+                Ok(true)
+            }
+            None => Ok(false),
+        },
+    }
+}
+
+// The user who ran aldm. Through sudo or pkexec, that is the user who ran
+// them, and otherwise the user aldm runs as.
+fn invoking_user() -> String {
+    if let Some(sudo_user) = env::var("SUDO_USER")
+        .ok()
+        .filter(|sudo_user| !sudo_user.is_empty())
+    {
+        return sudo_user;
+    }
+    let uid = env::var("PKEXEC_UID")
+        .ok()
+        .and_then(|pkexec_uid| {
+            pkexec_uid
+                .parse()
+                .ok()
+        })
+        .map(Uid::from_raw)
+        .unwrap_or_else(Uid::current);
+    User::from_uid(uid)
+        .ok()
+        .flatten()
+        .map(|user| user.name)
+        .unwrap_or_else(|| uid.to_string())
+}
+
+// Where the journals are, such as `/var/lib/aldm/transactions`
+pub fn transactions_path() -> PathBuf {
+    system::state_dirpath().join("transactions")
+}
+
+fn transactions_dirpath(root: &Path) -> PathBuf {
+    system::path_below_root(root, &transactions_path())
+}
+
+fn journal_filepath(root: &Path, id: &str) -> PathBuf {
+    transactions_dirpath(root)
+        .join(id)
+        .join(JOURNAL_FILE_NAME)
+}
+
+fn is_done_by_default() -> bool {
+    true
+}
+
+const JOURNAL_FILE_NAME: &str = "journal.yaml";
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(
+        display("could not create the journal at {:?}: {source}", path),
+        visibility(pub)
+    )]
+    CreateJournal {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(display("could not serialize the journal: {source}"), visibility(pub))]
+    SerializeJournal { source: serde_yaml::Error },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not write the journal at {:?}: {source}", path),
+        visibility(pub)
+    )]
+    WriteJournal {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not read the journal at {:?}: {source}", path),
+        visibility(pub)
+    )]
+    ReadJournal {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the journal at {:?} has incorrect format: {source}", path),
+        visibility(pub)
+    )]
+    ParseJournal {
+        path: PathBuf,
+        source: serde_yaml::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(display("there is no transaction with the ID '{id}'"), visibility(pub))]
+    UnknownTransaction { id: String },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not back up the file {:?}: {source}", path),
+        visibility(pub)
+    )]
+    BackUpFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not restore the file {:?}: {source}", path),
+        visibility(pub)
+    )]
+    RestoreFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+// region: IMPORTS
+
+use std::{
+    collections::BTreeMap,
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use nix::unistd::{Uid, User};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use time::OffsetDateTime;
+
+use crate::{actions::TransactionKind, app, data::Version, system};
+
+// endregion: IMPORTS
@@ -0,0 +1,275 @@
+// The mkinitcpio drop-in that aldm writes for a driver, such as
+// `/etc/mkinitcpio.conf.d/aldm-nvidia.conf`. It adds the modules of the driver
+// to the initramfs, so that they are loaded early and take over the display
+// before the desktop starts (early KMS).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MkinitcpioFile {
+    pub path: PathBuf,
+    pub contents: String,
+}
+
+impl MkinitcpioFile {
+    pub fn new(driver_id: &str, modules: &[String]) -> Self {
+        MkinitcpioFile {
+            path: mkinitcpio_filepath(driver_id),
+            contents: format!(
+                "{}\n# It is removed along with the {driver_id} driver.\nMODULES+=({})\n",
+                system::FILE_HEADER,
+                modules.join(" ")
+            ),
+        }
+    }
+
+    pub fn write(&self, root: &Path) -> Result<(), Error> {
+        let filepath = system::path_below_root(root, &self.path);
+        if let Some(dirpath) = filepath.parent() {
+            fs::create_dir_all(dirpath).context(CreateMkinitcpioDirectorySnafu {
+                path: dirpath.to_owned(),
+            })?;
+        }
+        fs::write(&filepath, &self.contents).context(WriteMkinitcpioFileSnafu { path: filepath })
+    }
+}
+
+pub fn mkinitcpio_filepath(driver_id: &str) -> PathBuf {
+    Path::new(DROP_IN_DIRPATH).join(system::aldm_file_name(driver_id))
+}
+
+// Remove a drop-in that aldm wrote. A drop-in that is already gone is fine.
+pub fn remove_mkinitcpio_file(root: &Path, path: &Path) -> Result<bool, Error> {
+    ensure!(
+        system::is_aldm_file(Path::new(DROP_IN_DIRPATH), path),
+        NotAldmDropInSnafu {
+            path: path.to_owned()
+        }
+    );
+    let filepath = system::path_below_root(root, path);
+    match fs::remove_file(&filepath) {
+        Ok(()) => Ok(true),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(error) => Err(error).context(RemoveMkinitcpioFileSnafu { path: filepath }),
+    }
+}
+
+// Whether the system builds its initramfs with mkinitcpio, rather than with
+// dracut or not at all as in containers
+pub fn is_mkinitcpio_used(root: &Path) -> bool {
+    system::path_below_root(root, Path::new(CONFIG_FILEPATH)).is_file()
+}
+
+// The hooks of the configuration, as mkinitcpio sees them after sourcing the
+// main file and then the drop-ins in order. Each file can set the hooks with
+// `HOOKS=(...)` or add to them with `HOOKS+=(...)`, and an array can span
+// several lines.
+pub fn mkinitcpio_hooks(root: &Path) -> Result<Vec<String>, Error> {
+    let mut config_filepaths = vec![system::path_below_root(root, Path::new(CONFIG_FILEPATH))];
+    let drop_in_dirpath = system::path_below_root(root, Path::new(DROP_IN_DIRPATH));
+    match fs::read_dir(&drop_in_dirpath) {
+        Ok(entries) => {
+            let mut drop_in_filepaths = entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|extension| extension == "conf")
+                })
+                .collect::<Vec<_>>();
+            drop_in_filepaths.sort();
+            config_filepaths.extend(drop_in_filepaths);
+        }
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+        Err(error) => {
+            return Err(error).context(ReadMkinitcpioConfigSnafu {
+                path: drop_in_dirpath,
+            })
+        }
+    }
+
+    let mut hooks = Vec::new();
+    for config_filepath in config_filepaths {
+        let config = match fs::read_to_string(&config_filepath) {
+            Ok(config) => config,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+            Err(error) => {
+                return Err(error).context(ReadMkinitcpioConfigSnafu {
+                    path: config_filepath,
+                })
+            }
+        };
+        apply_hook_assignments(&config, &mut hooks);
+    }
+    Ok(hooks)
+}
+
+fn apply_hook_assignments(config: &str, hooks: &mut Vec<String>) {
+    let mut lines = config
+        .lines()
+        .map(|line| {
+            line.split('#')
+                .next()
+                .unwrap_or_default()
+                .trim()
+        });
+    while let Some(line) = lines.next() {
+        let (is_appended, value) = match (line.strip_prefix("HOOKS+="), line.strip_prefix("HOOKS="))
+        {
+            (Some(value), _) => (true, value),
+            (None, Some(value)) => (false, value),
+            (None, None) => continue,
+        };
+        let mut value = value.to_owned();
+        // Older configurations use a string instead of an array
+        let words = match value.strip_prefix('(') {
+            Some(_) => {
+                while !value.contains(')') {
+                    let Some(line) = lines.next() else {
+                        break;
+                    };
+                    value.push(' ');
+                    value.push_str(line);
+                }
+                value
+                    .trim_start_matches('(')
+                    .split(')')
+                    .next()
+                    .unwrap_or_default()
+                    .to_owned()
+            }
+            None => value
+                .trim_matches(|c| c == '"' || c == '\'')
+                .to_owned(),
+        };
+        if !is_appended {
+            hooks.clear();
+        }
+        hooks.extend(
+            words
+                .split_whitespace()
+                .map(|hook| {
+                    hook.trim_matches(|c| c == '"' || c == '\'')
+                        .to_owned()
+                }),
+        );
+    }
+}
+
+// The blacklisted modules that the `kms` hook puts in the initramfs anyway.
+// The hook adds the kernel modesetting drivers of the GPUs, so that the
+// blacklisted driver can still take over the display at boot.
+pub fn kms_hook_conflicts(root: &Path, blacklist: &[String]) -> Result<Vec<String>, Error> {
+    if !is_mkinitcpio_used(root)
+        || !mkinitcpio_hooks(root)?
+            .iter()
+            .any(|hook| hook == KMS_HOOK)
+    {
+        return Ok(vec![]);
+    }
+    Ok(blacklist
+        .iter()
+        .filter(|module| KMS_HOOK_MODULES.contains(&module.as_str()))
+        .cloned()
+        .collect())
+}
+
+// Whether the file ends up in the initramfs, so that changing it only takes
+// effect once the initramfs is rebuilt. The modconf hook copies the modprobe.d
+// files.
+pub fn is_initramfs_file(path: &Path) -> bool {
+    [MODPROBE_DIRPATH, CONFIG_FILEPATH, DROP_IN_DIRPATH]
+        .iter()
+        .any(|dirpath| path.starts_with(dirpath))
+}
+
+// Rebuild the initramfs of every installed kernel
+pub fn regenerate_initramfs(
+    command_runner: &dyn CommandRunner,
+    progress: &dyn ProgressSink,
+) -> Result<(), Error> {
+    command_runner
+        .run("mkinitcpio", &["-P"], progress)
+        .context(RegenerateInitramfsSnafu {})
+}
+
+const CONFIG_FILEPATH: &str = "/etc/mkinitcpio.conf";
+const DROP_IN_DIRPATH: &str = "/etc/mkinitcpio.conf.d";
+const MODPROBE_DIRPATH: &str = "/etc/modprobe.d";
+const KMS_HOOK: &str = "kms";
+const KMS_HOOK_MODULES: [&str; 5] = ["nouveau", "amdgpu", "radeon", "i915", "xe"];
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(
+        display("could not create the directory {:?}: {source}", path),
+        visibility(pub)
+    )]
+    CreateMkinitcpioDirectory {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not read the mkinitcpio configuration at {:?}: {source}", path),
+        visibility(pub)
+    )]
+    ReadMkinitcpioConfig {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not write the file {:?}: {source}", path),
+        visibility(pub)
+    )]
+    WriteMkinitcpioFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not remove the file {:?}: {source}", path),
+        visibility(pub)
+    )]
+    RemoveMkinitcpioFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the file {:?} was not written by aldm, so it is left alone", path),
+        visibility(pub)
+    )]
+    NotAldmDropIn { path: PathBuf },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not regenerate the initramfs: {source}"),
+        visibility(pub)
+    )]
+    RegenerateInitramfs {
+        #[snafu(backtrace)]
+        source: command::Error,
+    },
+}
+
+// region: IMPORTS
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, ResultExt, Snafu};
+
+use crate::{
+    actions::ProgressSink,
+    system::{self, command, CommandRunner},
+};
+
+// endregion: IMPORTS
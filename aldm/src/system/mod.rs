@@ -0,0 +1,188 @@
+// Changes to the configuration of the system that drivers need besides their
+// packages. Every file is written below a root directory, which is `/` on a
+// real system and a scratch directory in tests.
+
+pub fn configured_root(config: &Config) -> PathBuf {
+    config
+        .system_root
+        .clone()
+        .map(PathBuf::from)
+        .unwrap_or_else(probe::default_root)
+}
+
+// Where aldm keeps its records of the system, such as `/var/lib/aldm`
+pub fn state_dirpath() -> PathBuf {
+    PathBuf::from(format!("/var/lib/{}", *app::APP_NAME))
+}
+
+// The records are lists that aldm keeps in its state directory, one entry per
+// line, of what it changed on the system so that it only undoes its own
+// changes
+pub(crate) fn record_filepath(root: &Path, record_name: &str) -> PathBuf {
+    path_below_root(root, &state_dirpath().join(record_name))
+}
+
+pub(crate) fn read_record(record_filepath: &Path) -> io::Result<Vec<String>> {
+    match fs::read_to_string(record_filepath) {
+        Ok(record) => Ok(record
+            .lines()
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(str::to_owned)
+            .collect()),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(error) => Err(error),
+    }
+}
+
+pub(crate) fn write_record(record_filepath: &Path, entries: &[String]) -> io::Result<()> {
+    if let Some(dirpath) = record_filepath.parent() {
+        fs::create_dir_all(dirpath)?;
+    }
+    let mut record = String::new();
+    for entry in entries {
+        record.push_str(entry);
+        record.push('\n');
+    }
+    fs::write(record_filepath, record)
+}
+
+// The path of a file below the root, for a path as it would be on the system
+// such as `/etc/modprobe.d/aldm-nvidia.conf`
+pub fn path_below_root(root: &Path, path: &Path) -> PathBuf {
+    root.join(
+        path.strip_prefix("/")
+            .unwrap_or(path),
+    )
+}
+
+// The name of the file that aldm writes for a driver in a configuration
+// directory, such as `aldm-nvidia.conf`. Characters that do not belong in a
+// file name, like the `+` of IDs made of several packages, are replaced.
+pub fn aldm_file_name(driver_id: &str) -> String {
+    let file_stem = driver_id
+        .chars()
+        .map(
+            |c| match c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                true => c,
+                false => '_',
+            },
+        )
+        .collect::<String>();
+    format!("{FILE_PREFIX}{file_stem}.conf")
+}
+
+// Whether the path is one of the files that aldm writes in the directory.
+// Files of other programs and of the user are never touched.
+pub fn is_aldm_file(dirpath: &Path, path: &Path) -> bool {
+    path.parent() == Some(dirpath)
+        && path
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .is_some_and(|file_name| {
+                file_name.starts_with(FILE_PREFIX) && file_name.ends_with(".conf")
+            })
+}
+
+const FILE_PREFIX: &str = "aldm-";
+pub(crate) const FILE_HEADER: &str =
+    "# Written by aldm. Changes to this file are lost when aldm changes drivers.";
+pub(crate) const FILES_RECORD: &str = "files";
+pub(crate) const KERNEL_PARAMETERS_RECORD: &str = "kernel-parameters";
+pub(crate) const SERVICES_RECORD: &str = "services";
+pub(crate) const RECORDS: [&str; 3] = [FILES_RECORD, KERNEL_PARAMETERS_RECORD, SERVICES_RECORD];
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(display("in managing modprobe.d files: {source}"), visibility(pub))]
+    Modprobe {
+        #[snafu(backtrace)]
+        source: modprobe::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("in managing the mkinitcpio configuration: {source}"),
+        visibility(pub)
+    )]
+    Mkinitcpio {
+        #[snafu(backtrace)]
+        source: mkinitcpio::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("in changing the kernel parameters: {source}"),
+        visibility(pub)
+    )]
+    Bootloader {
+        #[snafu(backtrace)]
+        source: bootloader::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(display("in managing the files of drivers: {source}"), visibility(pub))]
+    Files {
+        #[snafu(backtrace)]
+        source: files::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(display("in managing services: {source}"), visibility(pub))]
+    Services {
+        #[snafu(backtrace)]
+        source: services::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("in keeping the journal of the transaction: {source}"),
+        visibility(pub)
+    )]
+    Journal {
+        #[snafu(backtrace)]
+        source: journal::Error,
+    },
+}
+
+// region: IMPORTS
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use snafu::Snafu;
+
+use crate::{
+    app::{self, Config},
+    probe,
+};
+
+// endregion: IMPORTS
+
+// region: MODULES
+
+pub mod bootloader;
+pub mod command;
+pub mod files;
+pub mod journal;
+pub mod mkinitcpio;
+pub mod modprobe;
+pub mod services;
+
+// endregion: MODULES
+
+// region: RE-EXPORTS
+
+pub use bootloader::*;
+pub use command::*;
+pub use files::*;
+pub use journal::*;
+pub use mkinitcpio::*;
+pub use modprobe::*;
+pub use services::*;
+
+// endregion: RE-EXPORTS
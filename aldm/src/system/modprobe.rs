@@ -0,0 +1,146 @@
+// The modprobe.d file that aldm writes for a driver, such as
+// `/etc/modprobe.d/aldm-nvidia.conf`. Files of other programs and of the user
+// are never touched, since aldm only manages files named `aldm-*.conf`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModprobeFile {
+    pub path: PathBuf,
+    pub contents: String,
+    // The modules that the file keeps from loading
+    pub blacklist: Vec<String>,
+}
+
+impl ModprobeFile {
+    pub fn new(driver_id: &str, config: &ModprobeConfig) -> Self {
+        let mut contents = format!(
+            "{}\n# It is removed along with the {driver_id} driver.\n",
+            system::FILE_HEADER
+        );
+        for module in &config.blacklist {
+            contents.push_str(&format!("blacklist {module}\n"));
+        }
+        for (module, options) in &config.options {
+            contents.push_str(&format!("options {module} {options}\n"));
+        }
+        ModprobeFile {
+            path: modprobe_filepath(driver_id),
+            contents,
+            blacklist: config
+                .blacklist
+                .clone(),
+        }
+    }
+
+    pub fn write(&self, root: &Path) -> Result<(), Error> {
+        let filepath = system::path_below_root(root, &self.path);
+        if let Some(dirpath) = filepath.parent() {
+            fs::create_dir_all(dirpath).context(CreateModprobeDirectorySnafu {
+                path: dirpath.to_owned(),
+            })?;
+        }
+        fs::write(&filepath, &self.contents).context(WriteModprobeFileSnafu { path: filepath })
+    }
+}
+
+pub fn modprobe_filepath(driver_id: &str) -> PathBuf {
+    Path::new(MODPROBE_DIRPATH).join(system::aldm_file_name(driver_id))
+}
+
+// Remove a file that aldm wrote. A file that is already gone is fine.
+pub fn remove_modprobe_file(root: &Path, path: &Path) -> Result<bool, Error> {
+    ensure!(
+        system::is_aldm_file(Path::new(MODPROBE_DIRPATH), path),
+        NotAldmFileSnafu {
+            path: path.to_owned()
+        }
+    );
+    let filepath = system::path_below_root(root, path);
+    match fs::remove_file(&filepath) {
+        Ok(()) => Ok(true),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(error) => Err(error).context(RemoveModprobeFileSnafu { path: filepath }),
+    }
+}
+
+// The files that aldm wrote, as paths on the system
+pub fn modprobe_files(root: &Path) -> Result<Vec<PathBuf>, Error> {
+    let dirpath = system::path_below_root(root, Path::new(MODPROBE_DIRPATH));
+    let entries = match fs::read_dir(&dirpath) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(error) => return Err(error).context(ReadModprobeDirectorySnafu { path: dirpath }),
+    };
+    let mut paths = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| Path::new(MODPROBE_DIRPATH).join(entry.file_name()))
+        .filter(|path| system::is_aldm_file(Path::new(MODPROBE_DIRPATH), path))
+        .collect::<Vec<_>>();
+    paths.sort();
+    Ok(paths)
+}
+
+const MODPROBE_DIRPATH: &str = "/etc/modprobe.d";
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(
+        display("could not create the directory {:?}: {source}", path),
+        visibility(pub)
+    )]
+    CreateModprobeDirectory {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not read the directory {:?}: {source}", path),
+        visibility(pub)
+    )]
+    ReadModprobeDirectory {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not write the file {:?}: {source}", path),
+        visibility(pub)
+    )]
+    WriteModprobeFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("could not remove the file {:?}: {source}", path),
+        visibility(pub)
+    )]
+    RemoveModprobeFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display("the file {:?} was not written by aldm, so it is left alone", path),
+        visibility(pub)
+    )]
+    NotAldmFile { path: PathBuf },
+}
+
+// region: IMPORTS
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, ResultExt, Snafu};
+
+use crate::{data::ModprobeConfig, system};
+
+// endregion: IMPORTS
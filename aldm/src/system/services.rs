@@ -0,0 +1,165 @@
+// Whether the systemd unit is enabled, which is when a target in /etc wants
+// or requires it
+pub fn is_service_enabled(root: &Path, unit: &str) -> bool {
+    let Ok(entries) = fs::read_dir(system::path_below_root(root, Path::new(UNITS_DIRPATH))) else {
+        return false;
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let file_name = entry.file_name();
+            let file_name = file_name.to_string_lossy();
+            file_name.ends_with(".wants") || file_name.ends_with(".requires")
+        })
+        .any(|entry| {
+            entry
+                .path()
+                .join(unit)
+                .symlink_metadata()
+                .is_ok()
+        })
+}
+
+// Enable the units with systemctl. Units that are enabled already are left
+// alone, and the others are recorded, so that only those are disabled later.
+pub fn enable_services(
+    root: &Path,
+    units: &[String],
+    command_runner: &dyn CommandRunner,
+    progress: &dyn ProgressSink,
+) -> Result<Vec<String>, Error> {
+    let mut recorded_units = recorded_services(root)?;
+    let units = units
+        .iter()
+        .filter(|unit| !is_service_enabled(root, unit))
+        .cloned()
+        .collect::<Vec<_>>();
+    if units.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut arguments = vec!["enable"];
+    arguments.extend(
+        units
+            .iter()
+            .map(String::as_str),
+    );
+    command_runner
+        .run("systemctl", &arguments, progress)
+        .context(EnableServicesSnafu {})?;
+    for unit in &units {
+        if !recorded_units.contains(unit) {
+            recorded_units.push(unit.clone());
+        }
+    }
+    write_recorded_services(root, &recorded_units)?;
+    Ok(units)
+}
+
+// Disable the units that aldm enabled
+pub fn disable_services(
+    root: &Path,
+    units: &[String],
+    command_runner: &dyn CommandRunner,
+    progress: &dyn ProgressSink,
+) -> Result<Vec<String>, Error> {
+    let mut recorded_units = recorded_services(root)?;
+    let units = units
+        .iter()
+        .filter(|unit| recorded_units.contains(unit))
+        .cloned()
+        .collect::<Vec<_>>();
+    if units.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut arguments = vec!["disable"];
+    arguments.extend(
+        units
+            .iter()
+            .map(String::as_str),
+    );
+    command_runner
+        .run("systemctl", &arguments, progress)
+        .context(DisableServicesSnafu {})?;
+    recorded_units.retain(|unit| !units.contains(unit));
+    write_recorded_services(root, &recorded_units)?;
+    Ok(units)
+}
+
+// The units that aldm enabled
+pub fn recorded_services(root: &Path) -> Result<Vec<String>, Error> {
+    let record_filepath = system::record_filepath(root, system::SERVICES_RECORD);
+    system::read_record(&record_filepath).context(ReadServiceRecordSnafu {
+        path: record_filepath,
+    })
+}
+
+fn write_recorded_services(root: &Path, units: &[String]) -> Result<(), Error> {
+    let record_filepath = system::record_filepath(root, system::SERVICES_RECORD);
+    system::write_record(&record_filepath, units).context(WriteServiceRecordSnafu {
+        path: record_filepath,
+    })
+}
+
+const UNITS_DIRPATH: &str = "/etc/systemd/system";
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[non_exhaustive]
+    #[snafu(display("could not enable the services: {source}"), visibility(pub))]
+    EnableServices {
+        #[snafu(backtrace)]
+        source: command::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(display("could not disable the services: {source}"), visibility(pub))]
+    DisableServices {
+        #[snafu(backtrace)]
+        source: command::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display(
+            "could not read the record of enabled services at {:?}: {source}",
+            path
+        ),
+        visibility(pub)
+    )]
+    ReadServiceRecord {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[non_exhaustive]
+    #[snafu(
+        display(
+            "could not write the record of enabled services at {:?}: {source}",
+            path
+        ),
+        visibility(pub)
+    )]
+    WriteServiceRecord {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+// region: IMPORTS
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use snafu::{ResultExt, Snafu};
+
+use crate::{
+    actions::ProgressSink,
+    system::{self, command, CommandRunner},
+};
+
+// endregion: IMPORTS
@@ -1,5 +1,5 @@
 #[tracing::instrument(level = "trace")]
-pub fn run_common<C>() -> Result<(C, Vec<WorkerGuard>), crate::Error>
+pub fn run_common<C>() -> Result<(C, Session), crate::Error>
 where
     C: clap::Parser + CliModifier + fmt::Debug,
     <C as GlobalArguments>::L: LogLevel,
@@ -39,9 +39,53 @@ where
     let verbosity_filter = cli_input
         .verbosity_filter()
         .or(config_verbosity_filter);
-    let (mut handle, log_filepath) = logging::init_log(config_log_dirpath, verbosity_filter)
-        .context(app::LoggingSnafu {})
-        .context(crate::AppSnafu {})?;
+    let log_directives = env::var(format!(
+        "{}_LOG",
+        String::from(*app::APP_NAME).to_uppercase()
+    ))
+    .ok()
+    .filter(|value| !value.is_empty())
+    .or_else(|| {
+        config
+            .log_directives
+            .clone()
+    });
+    let log_file_format = config
+        .log_file_format
+        .unwrap_or_default();
+    // ANSI escapes only make sense in human-readable log files
+    let is_log_file_ansi = config
+        .log_file_ansi
+        .unwrap_or(log_file_format == LogFileFormat::Pretty);
+    let default_log_retention = LogRetention::default();
+    let log_retention = LogRetention {
+        max_files: config
+            .log_max_files
+            .unwrap_or(default_log_retention.max_files),
+        max_age_days: config
+            .log_max_age_days
+            .unwrap_or(default_log_retention.max_age_days),
+        max_file_size: config
+            .log_max_file_size
+            .unwrap_or(default_log_retention.max_file_size),
+    };
+    let (mut handle, log_filepath) = logging::init_log(
+        config_log_dirpath,
+        verbosity_filter,
+        log_directives.as_deref(),
+        log_file_format,
+        is_log_file_ansi,
+        log_retention,
+    )
+    .context(app::LoggingSnafu {})
+    .context(crate::AppSnafu {})?;
+
+    // Write a crash report next to the log files if aldm panics
+    let log_dirpath = log_filepath
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    crash::install_panic_hook(log_dirpath.clone(), config_filepaths.clone());
 
     // Modify logging behavior if Plain or Json output is desired
     if cli_input.is_json() {
@@ -143,7 +187,26 @@ where
         cli_input.dimmed()
     );
 
-    Ok((cli_input, handle.worker_guards))
+    Ok((
+        cli_input,
+        Session {
+            config,
+            config_filepaths,
+            log_dirpath,
+            log_filepath,
+            logging_handle: handle,
+        },
+    ))
+}
+
+// What the frontends need to keep around after the common initialization
+pub struct Session {
+    pub config: Config,
+    pub config_filepaths: Vec<PathBuf>,
+    pub log_dirpath: PathBuf,
+    // The log file that was current when the session started
+    pub log_filepath: PathBuf,
+    pub logging_handle: logging::Handle,
 }
 
 impl<T> CliModifier for T
@@ -226,11 +289,13 @@ use clap_verbosity_flag::LogLevel;
 use core::fmt;
 use owo_colors::OwoColorize;
 use snafu::{ResultExt, Snafu};
-use std::{env, path::PathBuf};
-use tracing_appender::non_blocking::WorkerGuard;
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
 use tracing_subscriber::filter::LevelFilter;
 
-use crate::app::{self, config, logging};
+use crate::app::{self, config, crash, logging, Config, LogFileFormat, LogRetention};
 
 // endregion: IMPORTS
 
@@ -0,0 +1,348 @@
+// The install, remove and rollback logic run end to end below a scratch root,
+// with the fake package backend and command runner standing in for pacman and
+// the system commands
+
+// A root directory of its own for each test, removed again at the end
+struct ScratchRoot {
+    path: PathBuf,
+}
+
+impl ScratchRoot {
+    fn new(name: &str) -> Self {
+        let path = env::temp_dir().join(format!("aldm-test-{}-{name}", process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(path.join("etc")).unwrap();
+        // The initramfs is only rebuilt on systems that use mkinitcpio
+        fs::write(
+            path.join("etc/mkinitcpio.conf"),
+            "HOOKS=(base udev modconf)\n",
+        )
+        .unwrap();
+        ScratchRoot { path }
+    }
+
+    fn file(&self, path: &str) -> PathBuf {
+        system::path_below_root(&self.path, Path::new(path))
+    }
+
+    fn read(&self, path: &str) -> Option<String> {
+        fs::read_to_string(self.file(path)).ok()
+    }
+}
+
+impl Drop for ScratchRoot {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+fn nvidia_open(is_installed: bool) -> DriverChoice {
+    DriverChoice {
+        id: "nvidia-open".to_owned(),
+        packages: vec!["nvidia-open".to_owned(), "nvidia-utils".to_owned()],
+        hardware_kinds: BTreeSet::from([HardwareKind::Graphics]),
+        tags: BTreeSet::new(),
+        aur_packages: vec![],
+        modprobe: ModprobeConfig {
+            blacklist: vec!["nouveau".to_owned()],
+            options: BTreeMap::from([("nvidia_drm".to_owned(), "modeset=1".to_owned())]),
+        },
+        early_kms_modules: vec!["nvidia".to_owned(), "nvidia_drm".to_owned()],
+        kernel_parameters: vec![],
+        services: vec!["nvidia-suspend.service".to_owned()],
+        files: vec![DriverFile {
+            path: XORG_FILE.to_owned(),
+            contents: "Section \"OutputClass\"\nEndSection\n".to_owned(),
+        }],
+        is_installed,
+    }
+}
+
+fn device_drivers(installed: Vec<DriverChoice>, recommended: DriverChoice) -> DeviceDrivers {
+    DeviceDrivers {
+        device: PciDevice {
+            slot: "0000:01:00.0".to_owned(),
+            vendor_id: 0x10de,
+            device_id: 0x2684,
+            subsystem_vendor_id: 0x1043,
+            subsystem_device_id: 0x889d,
+            class_code: 0x030000,
+            modalias: None,
+            is_boot_vga: true,
+            bound_driver: None,
+            candidate_modules: vec![],
+        },
+        device_name: DeviceName::default(),
+        hardware_kinds: BTreeSet::from([HardwareKind::Graphics]),
+        installed,
+        recommended: Some(recommended),
+        alternatives: vec![],
+    }
+}
+
+fn ran(command_runner: &FakeCommandRunner, program: &str, arguments: &[&str]) -> bool {
+    command_runner
+        .calls()
+        .iter()
+        .any(|call| call.program == program && call.arguments == arguments)
+}
+
+#[test]
+fn install_and_remove_change_the_system_and_its_records() {
+    let root = ScratchRoot::new("install-remove");
+    let backend = FakeBackend::new()
+        .with_available("nvidia-open", "570.144-1")
+        .with_available("nvidia-utils", "570.144-1");
+    let command_runner = FakeCommandRunner::new();
+
+    let plan =
+        actions::plan_install(&device_drivers(vec![], nvidia_open(false)), "nvidia-open").unwrap();
+    let outcome = actions::execute_transaction(
+        &plan,
+        &backend,
+        &command_runner,
+        &root.path,
+        &NoProgress,
+        &CancellationToken::new(),
+    )
+    .unwrap();
+    assert_eq!(outcome, TransactionOutcome::RebootRequired);
+    assert!(backend
+        .installed()
+        .contains_key("nvidia-open"));
+    let modprobe_file = root
+        .read("/etc/modprobe.d/aldm-nvidia-open.conf")
+        .unwrap();
+    assert!(modprobe_file.contains("blacklist nouveau\n"));
+    assert!(modprobe_file.contains("options nvidia_drm modeset=1\n"));
+    let mkinitcpio_file = root
+        .read("/etc/mkinitcpio.conf.d/aldm-nvidia-open.conf")
+        .unwrap();
+    assert!(mkinitcpio_file.contains("nvidia nvidia_drm"));
+    assert!(root
+        .read(XORG_FILE)
+        .is_some());
+    assert_eq!(
+        system::recorded_driver_files(&root.path)
+            .unwrap()
+            .iter()
+            .map(|recorded_file| recorded_file
+                .path
+                .as_str())
+            .collect::<Vec<_>>(),
+        [XORG_FILE]
+    );
+    assert_eq!(
+        system::recorded_services(&root.path).unwrap(),
+        ["nvidia-suspend.service"]
+    );
+    assert!(ran(
+        &command_runner,
+        "systemctl",
+        &["enable", "nvidia-suspend.service"]
+    ));
+    assert!(ran(&command_runner, "mkinitcpio", &["-P"]));
+
+    let plan =
+        actions::plan_remove(&device_drivers(vec![nvidia_open(true)], nvidia_open(true))).unwrap();
+    actions::execute_transaction(
+        &plan,
+        &backend,
+        &command_runner,
+        &root.path,
+        &NoProgress,
+        &CancellationToken::new(),
+    )
+    .unwrap();
+    assert!(!backend
+        .installed()
+        .contains_key("nvidia-open"));
+    assert!(root
+        .read("/etc/modprobe.d/aldm-nvidia-open.conf")
+        .is_none());
+    assert!(root
+        .read("/etc/mkinitcpio.conf.d/aldm-nvidia-open.conf")
+        .is_none());
+    assert!(root
+        .read(XORG_FILE)
+        .is_none());
+    assert!(system::recorded_driver_files(&root.path)
+        .unwrap()
+        .is_empty());
+    assert!(system::recorded_services(&root.path)
+        .unwrap()
+        .is_empty());
+    assert!(ran(
+        &command_runner,
+        "systemctl",
+        &["disable", "nvidia-suspend.service"]
+    ));
+}
+
+#[test]
+fn failed_install_is_rolled_back() {
+    let root = ScratchRoot::new("failed-install");
+    fs::create_dir_all(
+        root.file(XORG_FILE)
+            .parent()
+            .unwrap(),
+    )
+    .unwrap();
+    fs::write(root.file(XORG_FILE), "# The user's own\n").unwrap();
+    // nvidia-utils was there before, so the rollback has to keep it
+    let backend = FakeBackend::new()
+        .with_installed("nvidia-utils", "570.144-1")
+        .with_available("nvidia-open", "570.144-1")
+        .with_available("nvidia-utils", "570.144-1");
+    let command_runner = FakeCommandRunner::new().with_step(FakeStep {
+        output: vec!["Failed to enable unit".to_owned()],
+        result: FakeResult::Fail { exit_code: 1 },
+    });
+
+    let plan =
+        actions::plan_install(&device_drivers(vec![], nvidia_open(false)), "nvidia-open").unwrap();
+    let result = actions::execute_transaction(
+        &plan,
+        &backend,
+        &command_runner,
+        &root.path,
+        &NoProgress,
+        &CancellationToken::new(),
+    );
+    assert!(matches!(result, Err(install::Error::RolledBack { .. })));
+    assert_eq!(
+        backend
+            .installed()
+            .keys()
+            .collect::<Vec<_>>(),
+        ["nvidia-utils"]
+    );
+    assert!(root
+        .read("/etc/modprobe.d/aldm-nvidia-open.conf")
+        .is_none());
+    assert!(root
+        .read("/etc/mkinitcpio.conf.d/aldm-nvidia-open.conf")
+        .is_none());
+    assert_eq!(
+        root.read(XORG_FILE)
+            .unwrap(),
+        "# The user's own\n"
+    );
+    assert!(system::recorded_driver_files(&root.path)
+        .unwrap()
+        .is_empty());
+
+    let journals = system::read_journals(&root.path).unwrap();
+    assert_eq!(journals.len(), 1);
+    assert!(journals[0].is_rolled_back());
+    assert!(actions::plan_rollback(&root.path, None).is_err());
+}
+
+#[test]
+fn rollback_switches_back_to_the_old_driver() {
+    let root = ScratchRoot::new("rollback");
+    let mut nvidia = nvidia_open(true);
+    nvidia.id = "nvidia".to_owned();
+    nvidia.packages = vec!["nvidia".to_owned(), "nvidia-utils".to_owned()];
+    nvidia.files = vec![];
+    let backend = FakeBackend::new()
+        .with_installed("nvidia", "570.144-1")
+        .with_installed("nvidia-utils", "570.144-1")
+        .with_available("nvidia", "570.144-1")
+        .with_available("nvidia-open", "570.144-1")
+        .with_available("nvidia-utils", "570.144-1");
+    let command_runner = FakeCommandRunner::new();
+
+    // The old driver as it was installed by aldm
+    let plan = actions::plan_install(&device_drivers(vec![], nvidia.clone()), "nvidia").unwrap();
+    actions::execute_transaction(
+        &plan,
+        &backend,
+        &command_runner,
+        &root.path,
+        &NoProgress,
+        &CancellationToken::new(),
+    )
+    .unwrap();
+
+    let plan = actions::plan_install(
+        &device_drivers(vec![nvidia.clone()], nvidia_open(false)),
+        "nvidia-open",
+    )
+    .unwrap();
+    assert_eq!(plan.packages_to_remove, ["nvidia"]);
+    actions::execute_transaction(
+        &plan,
+        &backend,
+        &command_runner,
+        &root.path,
+        &NoProgress,
+        &CancellationToken::new(),
+    )
+    .unwrap();
+    assert!(root
+        .read("/etc/modprobe.d/aldm-nvidia.conf")
+        .is_none());
+    assert!(root
+        .read("/etc/modprobe.d/aldm-nvidia-open.conf")
+        .is_some());
+
+    let journal = actions::plan_rollback(&root.path, None).unwrap();
+    assert_eq!(journal.driver_id, "nvidia-open");
+    actions::execute_rollback(&journal, &backend, &command_runner, &root.path, &NoProgress)
+        .unwrap();
+    assert_eq!(
+        backend
+            .installed()
+            .keys()
+            .collect::<Vec<_>>(),
+        ["nvidia", "nvidia-utils"]
+    );
+    assert!(root
+        .read("/etc/modprobe.d/aldm-nvidia.conf")
+        .is_some());
+    assert!(root
+        .read("/etc/modprobe.d/aldm-nvidia-open.conf")
+        .is_none());
+    assert!(root
+        .read(XORG_FILE)
+        .is_none());
+    assert_eq!(
+        system::recorded_services(&root.path).unwrap(),
+        ["nvidia-suspend.service"]
+    );
+
+    let history = actions::history(&root.path).unwrap();
+    assert_eq!(
+        history[0]
+            .journal
+            .kind,
+        JournalKind::Rollback
+    );
+    assert_eq!(history[1].status, TransactionStatus::RolledBack);
+    assert_eq!(history[2].status, TransactionStatus::Succeeded);
+}
+
+const XORG_FILE: &str = "/etc/X11/xorg.conf.d/10-aldm-nvidia.conf";
+
+// region: IMPORTS
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use aldm::{
+    actions::{
+        self, install, CancellationToken, DeviceDrivers, DriverChoice, NoProgress,
+        TransactionOutcome, TransactionStatus,
+    },
+    backend::{FakeBackend, FakeResult, FakeStep},
+    data::{DeviceName, DriverFile, HardwareKind, ModprobeConfig},
+    probe::PciDevice,
+    system::{self, FakeCommandRunner, JournalKind},
+};
+
+// endregion: IMPORTS